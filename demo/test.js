@@ -34,7 +34,7 @@ const workerFunction = async () => {
         functionNo: 331100,
         subSystemNo: 1000,
         companyId: 91000,
-      }, JSON.stringify({
+      }, {
         op_branch_no: 0,
         op_entrust_way: "7",
         op_station: "0",
@@ -44,7 +44,7 @@ const workerFunction = async () => {
         content_type: "",
         password: "111111",
         password_type: "",
-      }));
+      });
 
       console.log(`Worker ${process.pid} error info:`, cc.getErrorInfo());
       console.log(`Worker ${process.pid} buffer:`, cc.getBuff());
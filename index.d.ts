@@ -11,6 +11,53 @@ export interface JsRouteInfo {
   connectId: number
   memberNo: number
 }
+/** [t2sdk] 节 */
+export interface T2SdkSection {
+  /** 服务器地址，多个地址用分号分隔，如 `127.0.0.1:9999;127.0.0.2:9999` */
+  servers: string
+  /** 许可证文件路径 */
+  license_file: string
+  /** 许可证密码 */
+  license_pwd?: string
+  /** 语言，1033 英文，2052 中文 */
+  lang?: number
+  /** 发送队列大小 */
+  send_queue_size?: number
+  /** 登录名 */
+  login_name?: string
+  /** 是否在连接时获取服务器负载 */
+  init_recv_load?: number
+  /** 是否自动重连 */
+  auto_reconnect?: number
+  /** 心跳间隔（秒） */
+  heartbeat_time?: number
+  /** 日志目录 */
+  logdir?: string
+  /** 是否记录收发数据 */
+  writedata?: number
+}
+/** [safe] 节 */
+export interface SafeSection {
+  /** 安全级别：none、pwd、ssl */
+  safe_level?: string
+  client_id?: string
+  /** 通信密码，safe_level 为 pwd 时使用 */
+  comm_pwd?: string
+  /** 证书文件，safe_level 为 ssl 时必填 */
+  cert_file?: string
+  cert_pwd?: string
+}
+/** t2sdk.ini 的类型化配置，键名与 ini 文件一致 */
+export interface T2Config {
+  t2sdk: T2SdkSection
+  safe?: SafeSection
+}
+/** 与柜台交互时使用的文本编码，默认为 GBK */
+export const enum TextEncoding {
+  Gbk = 0,
+  Gb18030 = 1,
+  Utf8 = 2
+}
 export const PACKER_INT_FILE_LIST: string
 export const PACKER_INT_FILE_ID: string
 export const PACKER_INT_FILE_SIZE: string
@@ -22,11 +69,30 @@ export const PACKER_STRING_MD5_CODE: string
 export const PACKER_STRING_FILE_FLAG: string
 export const REQUEST_PACKET: number
 export const ANSWER_PACKET: number
+export const MSGCENTER_FUNC_HEART: number
+export const MSGCENTER_FUNC_REG: number
+export const MSGCENTER_FUNC_REG_CANCEL: number
+export const MSGCENTER_FUNC_SENDED: number
 export declare function init(libPath: string): Promise<void>
+/**
+* 设置日志级别，默认为 Warn
+* @param target 只设置某一来源的级别：library、connect、send、receive、callback、drop
+*/
+export declare function setLogLevel(level: LogLevel, target?: string | undefined | null): void
+/** 设置日志回调，替换之前的回调；传入 null 时恢复写到 stderr */
+export declare function setLogSink(sink: ((record: LogRecord) => void) | null): void
+/** 按类型统计未释放的 SDK 包装对象，用于排查长时间运行时的泄漏 */
+export declare function debugHandles(): Array<HandleStats>
+/** 开启后逐个记录包装对象的创建时间，debugHandles 返回 oldestAgeMs；默认关闭，只维护按类型的计数 */
+export declare function setHandleTracking(enabled: boolean): void
 export declare function getVersion(): number
-export declare function newPacker(version: number): Packer
-export declare function newUnpacker(buffer: Buffer): UnPacker
-export declare function newUnpackerV1(buffer: Buffer): UnPacker
+export declare function newPacker(version: number, encoding?: TextEncoding | undefined | null): Packer
+/**
+* 创建解包器，默认复制 buffer
+* options.zeroCopy 为 true 时不复制，解包器持有 buffer 的引用直到释放
+*/
+export declare function newUnpacker(buffer: Buffer, encoding?: TextEncoding | undefined | null, options?: UnpackOptions | undefined | null): UnPacker
+export declare function newUnpackerV1(buffer: Buffer, encoding?: TextEncoding | undefined | null, options?: UnpackOptions | undefined | null): UnPacker
 export declare function getPackVersion(buffer: Buffer): number
 export declare function newBizMessage(): BizMessage
 export interface T2Header {
@@ -36,7 +102,494 @@ export interface T2Header {
   subSystemNo?: number
   companyId?: number
 }
+export interface SendOptions {
+  /** 严格模式，有损或不支持的值直接报错，默认 false */
+  strict?: boolean
+  /** 关联 id，写入请求的 AppReserved，由后端在应答中带回，并出现在 on('trace') 的记录中 */
+  traceId?: string
+  /**
+  * 声明为 'D' 的字段接受十进制字符串（如 '123.45'），按字段的 scale 打包，默认 false
+  * 'D' 字段以 double 传输，只能精确表示 15 位有效数字；小数位超过 scale 或有效数字超过 15 位时报错
+  */
+  decimal?: boolean
+}
+/** 远端可更新的文件 */
+export interface RemoteFile {
+  id: number
+  name: string
+  path: string
+  localPath?: string
+  size: number
+  time: number
+  md5: string
+  flag: string
+}
+/** OnRecvFileList 事件 */
+export interface FileListEvent {
+  files: Array<RemoteFile>
+  /** 错误码，nLength 为负数时返回 */
+  errorNo?: number
+  errorInfo?: string
+}
+/** OnShowProcessBar 事件 */
+export interface ProgressEvent {
+  /** 当前文件进度 */
+  fileProgress: number
+  /** 总进度，到达 100 表示更新完成 */
+  totalProgress: number
+  errorInfo?: string
+}
+export interface FileUpdaterOptions {
+  /** 超时时间（毫秒），默认 5000 */
+  timeoutMs?: number
+  /** 目录过滤 */
+  dirFilter?: string
+}
+/** 消息中心的主题信息，来自 GetTopic */
+export interface TopicInfo {
+  /** 主题名字 */
+  name: string
+  /** 主题编号 */
+  no: number
+  /** 可靠等级 */
+  reliableLevel: number
+  /** 发布优先级 */
+  issuePriority: number
+  /** 消息有效期 */
+  msgLifetime: number
+  /** 主题状态 */
+  status: number
+  /** 剔除策略 */
+  tickStrategy: number
+  /** 是否业务校验 */
+  businessVerify: number
+  /** 是否本地主题 */
+  local: number
+  /** 过滤字段名，按 FilterField1 ~ FilterField16 的顺序，忽略空值 */
+  filterFields: Array<string>
+  /** 订阅者列表 */
+  subscribeStr: string
+  /** 发布者列表 */
+  publishStr: string
+}
+export interface FromRowsOptions {
+  /** 打包器版本，默认 0x20 */
+  version?: number
+  /** 结果集名，设置 datasetName 或 returnCode 时调用 newDataset */
+  datasetName?: string
+  returnCode?: number
+  /** 字符串和字段名使用的编码 */
+  encoding?: TextEncoding
+  /** 严格模式，有损或不支持的值直接报错，默认 false */
+  strict?: boolean
+  /** 声明为 'D' 的字段接受十进制字符串，按字段的 scale 打包，最多 15 位有效数字，默认 false */
+  decimal?: boolean
+}
+export interface AppendRowsOptions {
+  /** 严格模式，有损或不支持的值直接报错，默认 false */
+  strict?: boolean
+  /** 声明为 'D' 的字段接受十进制字符串，按字段的 scale 打包，最多 15 位有效数字，默认 false */
+  decimal?: boolean
+}
+/** 'D' 字段在 rows()、datasets()、toColumns() 中的返回形式 */
+export const enum DecimalMode {
+  /** number，默认 */
+  Number = 0,
+  /** 按字段精度格式化的字符串，如 '123.45' */
+  String = 1,
+  /** { units, scale }，值为 units / 10^scale */
+  Units = 2
+}
+/** 定点数，值为 units / 10^scale */
+export interface Decimal {
+  units: bigint
+  scale: number
+}
+/** UnPacker.toColumns() 返回的一个结果集 */
+export interface ColumnarDataset {
+  name: string
+  rowCount: number
+  columns: Record<string, Int32Array | Float64Array | Array<string> | Array<Buffer> | Array<Decimal>>
+  /** 空值位图，第 i 行为空时字节 i >> 3 的第 i & 7 位为 1，没有空值的字段不出现 */
+  nulls: Record<string, Buffer>
+}
+export interface UnpackOptions {
+  /**
+  * 不复制输入，解包器持有 buffer 的引用直到释放，默认为 false
+  * 解包器释放之前不要修改 buffer 的内容
+  */
+  zeroCopy?: boolean
+  /**
+  * 字段类型与取值方法不符时转换，默认为 false，不符时抛出错误
+  * 例如对 'S' 字段调用 getInt 时解析字符串，对 'D' 字段调用 getStr 时按精度格式化
+  */
+  coerce?: boolean
+  /** 'D' 字段在 rows()、datasets()、toColumns() 中的返回形式，默认 DecimalMode.Number */
+  decimal?: DecimalMode
+}
+export interface GetTopicsOptions {
+  /** 是否强制从服务端获取，默认使用缓存 */
+  force?: boolean
+  /** 超时时间（毫秒），默认 5000 */
+  timeoutMs?: number
+}
+/** 订阅推送的消息 */
+export interface PushMessage {
+  /** 订阅项的稳定标识，重新订阅后不变 */
+  id?: number
+  /** 订阅标识，来自 SDK，重新订阅后会变化 */
+  subscribeIndex: number
+  /** 主题名字 */
+  topic: string
+  /** 消息序号，来自业务体中的 msgNoField 字段 */
+  msgNo?: number
+  /** 业务体打包内容，可用 newUnpacker 解包 */
+  data: Buffer
+  /** 过滤字段的打包内容 */
+  filterData?: Buffer
+  /** 订阅时设置的附加数据 */
+  appData?: Buffer
+}
+/** 订阅项被剔除的通知，剔除的订阅项不会再重新订阅 */
+export interface TickEvent {
+  id?: number
+  subscribeIndex: number
+  /** 剔除原因，包含重复的订阅项位置信息 */
+  info: string
+}
+/** 重新订阅成功的订阅项 */
+export interface ResubscribedItem {
+  id: number
+  topic: string
+  oldIndex: number
+  newIndex: number
+}
+/** resubscribed 事件 */
+export interface ResubscribedEvent {
+  subscriptions: Array<ResubscribedItem>
+}
+/** resubscribeFailed 事件，每个失败的订阅项触发一次 */
+export interface ResubscribeFailedEvent {
+  id: number
+  topic: string
+  errorNo: number
+  errorInfo?: string
+}
+/** gap 事件，收到的消息序号与上一条不连续 */
+export interface GapEvent {
+  id?: number
+  topic: string
+  /** 订阅项的唯一键，如 `topic?a=1&b=2` */
+  key: string
+  /** 期望的消息序号 */
+  expected: number
+  /** 实际收到的消息序号 */
+  received: number
+}
+/** duplicate 事件，收到的消息序号不大于上一条 */
+export interface DuplicateEvent {
+  id?: number
+  topic: string
+  key: string
+  msgNo: number
+  /** 已记录的最后一条消息序号 */
+  lastMsgNo: number
+}
+/** reset 事件，消息序号回退（服务端重启或序号重置），检查点已改为新的序号 */
+export interface ResetEvent {
+  id?: number
+  topic: string
+  key: string
+  msgNo: number
+  /** 重置前记录的最后一条消息序号 */
+  lastMsgNo: number
+}
+/** 订阅项的检查点 */
+export interface CheckpointInfo {
+  key: string
+  topic: string
+  filters: Record<string, string>
+  msgNo: number
+}
+export interface SubscriberOptions {
+  /** 超时时间（毫秒），默认 5000 */
+  timeoutMs?: number
+  /** 接收队列初始长度，默认 256 */
+  initRecvQLen?: number
+  /** 接收队列扩展步长，默认 512 */
+  stepRecvQLen?: number
+  /** 重连后是否自动重新订阅，默认 true */
+  autoResubscribe?: boolean
+  /** 检查订阅会话状态、写入检查点文件的间隔（毫秒），默认 1000 */
+  sessionCheckIntervalMs?: number
+  /** 业务体中的消息序号字段，设置后按订阅项检测缺失和重复的消息 */
+  msgNoField?: string
+  /**
+  * 检查点文件，保存每个订阅项最后的消息序号，需要同时设置 msgNoField
+  * 检查点只在客户端使用：有检查点的订阅项以补缺方式订阅，由服务端重发，收到后按序号去重；
+  * 不支持从检查点的序号续订：SDK 的订阅接口没有设置起始序号的方法，服务端不再保留的消息只能通过 gap 事件发现
+  */
+  checkpointFile?: string
+  /** 是否丢弃重复的消息，默认 true */
+  dropDuplicates?: boolean
+  /** 序号回退到 1 或回退达到该值时视为序号重置（触发 reset 事件并改写检查点），而不是重复，默认 1000 */
+  msgNoResetThreshold?: number
+}
+/** 订阅参数 */
+export interface SubscribeParams {
+  /** 主题名字 */
+  topic: string
+  /** 过滤条件，字段名到值，最多 16 个 */
+  filters?: Record<string, string>
+  /** 发送间隔（秒） */
+  sendInterval?: number
+  /** 返回字段 */
+  returnFields?: string
+  /** 是否补缺，true 表示需要订阅之前的数据；默认 false，有检查点时为 true */
+  fromNow?: boolean
+  /** 附加数据，推送时原样返回 */
+  appData?: Buffer
+  /** 业务校验包 */
+  bizCheck?: Buffer
+}
+/** 批量订阅中单个订阅项的结果 */
+export interface SubscribeResult {
+  /** 订阅项的稳定标识，订阅失败时为空 */
+  id?: number
+  /** 主题名字 */
+  topic: string
+  /** 订阅标识，订阅失败时为空 */
+  subscribeIndex?: number
+  notifyStatus: number
+  errorNo: number
+  errorInfo?: string
+}
+/** 订阅者记录的订阅项 */
+export interface SubscriptionInfo {
+  id: number
+  subscribeIndex: number
+  topic: string
+  filters?: Record<string, string>
+}
+/** GetSubcribeTopic 返回的 SDK 当前订阅项 */
+export interface SubscribedTopic {
+  subscribeIndex: number
+  topic: string
+  topicNo: number
+  isBornTopic: boolean
+  sendInterval: number
+  returnFields: string
+  isReplace: boolean
+  isFromNow: boolean
+}
+/** 日志级别，低于设置级别的日志不输出 */
+export const enum LogLevel {
+  Off = 0,
+  Error = 1,
+  Warn = 2,
+  Info = 3,
+  Debug = 4,
+  Trace = 5
+}
+export interface LogRecord {
+  level: LogLevel
+  /** 'library' | 'connect' | 'send' | 'receive' | 'callback' | 'drop' */
+  target: string
+  message: string
+  /** 毫秒时间戳 */
+  timestamp: number
+}
+/** 队列已满时的处理方式 */
+export const enum OverflowPolicy {
+  /** 阻塞 SDK 回调线程，直到队列有空位或队列关闭；读取方停止消费时该订阅者不再收到任何推送 */
+  Block = 0,
+  /** 丢弃最早的消息 */
+  DropOldest = 1,
+  /** 丢弃新收到的消息 */
+  DropNewest = 2,
+  /** 丢弃新收到的消息，并在下一次读取时报错、关闭队列 */
+  Error = 3,
+  /** 同 Block，但最多等待 blockTimeoutMs，超时后丢弃新收到的消息并计入 blockTimeouts */
+  BlockWithTimeout = 4
+}
+export interface SubscribeV1Params {
+  /** 订阅类型 */
+  issueType: number
+  /** 关键字段，打包为单行作为 KeyInfo，数字按 'I'、字符串按 'S' 打包 */
+  keyInfo?: Record<string, string | number>
+  /** 附加数据 */
+  appData?: Buffer
+  /** 序号 */
+  sequenceNo?: number
+  /** 等待应答的超时时间，默认 30000 毫秒 */
+  timeoutMs?: number
+}
+/** 消息中心 1.0 的推送 */
+export interface PushMessageV1 {
+  /** 订阅 id */
+  id: number
+  issueType: number
+  sequenceNo: number
+  /** 解包后的关键字段，取第一行 */
+  keyInfo: Record<string, string>
+  /** 业务内容 */
+  content: Buffer
+  appData?: Buffer
+}
+/** 请求中的一个结果集 */
+export interface RequestDataset {
+  name: string
+  /** 'I'、'D' 字段为数字，'R' 字段为 Buffer，其余为字符串 */
+  rows: Array<Record<string, string | number | Buffer>>
+}
+/** 发给本进程的请求，由 serve 注册的 handler 处理 */
+export interface ServeRequest {
+  functionNo: number
+  packetId: number
+  senderId: number
+  systemNo: number
+  subSystemNo: number
+  branchNo: number
+  companyId: number
+  /** 发送者路由 */
+  sendInfo: JsRouteInfo
+  /** 业务内容 */
+  content: Buffer
+  /** 解包后的业务内容 */
+  datasets: Array<RequestDataset>
+}
+export interface LatencyBucket {
+  /** 桶上界，单位毫秒 */
+  le: number
+  /** 耗时不大于 le 的应答数（累计） */
+  count: number
+}
+export interface ReturnCodeCount {
+  returnCode: number
+  count: number
+}
+export interface FunctionMetrics {
+  functionNo: number
+  /** 已发送的请求数 */
+  sent: number
+  /** 收到应答的请求数 */
+  answered: number
+  /** 等待应答超时的请求数 */
+  timeouts: number
+  /** 发送失败的请求数 */
+  sendFailures: number
+  /** 等待应答中的请求数 */
+  inFlight: number
+  /** 返回码非 0 的应答 */
+  businessErrors: Array<ReturnCodeCount>
+  /** 往返耗时，从发送到收到应答 */
+  latencyBuckets: Array<LatencyBucket>
+  latencyCount: number
+  latencySumMs: number
+}
+export interface SdkMetrics {
+  /** 连接状态，见 ConnectionStatus，未连接时为 0 */
+  connectionStatus: number
+  /** 所有功能号等待应答中的请求数 */
+  inFlight: number
+  functions: Array<FunctionMetrics>
+}
+export interface TemplateOptions {
+  systemNo?: number
+  branchNo?: number
+  subSystemNo?: number
+  companyId?: number
+}
+/** 请求的一个阶段：pack（打包）、enqueue（提交给 SDK）、sent（SDK 发出）、answered（收到应答） */
+export interface TraceSpan {
+  name: string
+  /** 毫秒时间戳 */
+  startTime: number
+  endTime: number
+}
+/**
+* 一次请求的跟踪记录，请求结束（收到应答、超时或发送失败）时通过 `sdk.on('trace', ...)` 派发
+* 可在 JS 侧转为 OpenTelemetry 的 span
+*/
+export interface RequestTrace {
+  traceId?: string
+  functionNo: number
+  hSend?: number
+  /** 'ok' | 'error' | 'timeout' | 'sendFailed' */
+  status: string
+  returnCode?: number
+  errorNo?: number
+  errorInfo?: string
+  /** 应答中带回的 traceId */
+  answerTraceId?: string
+  startTime: number
+  endTime: number
+  spans: Array<TraceSpan>
+}
+export interface HandleStats {
+  /** 'Packer' | 'UnPacker' | 'BizMessage' | 'Config' | 'Connection' */
+  kind: string
+  /** 未释放的包装对象数 */
+  live: number
+  /** 其中持有引用、释放时 release() 的 */
+  owned: number
+  /** 其中借用 SDK 或其他对象指针的 */
+  borrowed: number
+  /** 累计创建数 */
+  created: number
+  /** 累计释放数 */
+  dropped: number
+  /** 最早创建的未释放对象的存活时间，毫秒，只统计 setHandleTracking(true) 之后创建的对象 */
+  oldestAgeMs?: number
+}
+export interface QueueOptions {
+  /** 队列容量，默认 1024 */
+  capacity?: number
+  /** 队列已满时的处理方式，默认 DropOldest */
+  overflow?: OverflowPolicy
+  /** BlockWithTimeout 策略下 SDK 回调线程最长等待的毫秒数，默认 1000 */
+  blockTimeoutMs?: number
+}
+export interface QueueStats {
+  /** 当前排队的消息数 */
+  queued: number
+  /** 进入队列的消息总数 */
+  received: number
+  /** 已读取的消息总数 */
+  delivered: number
+  /** 因队列已满丢弃的消息总数 */
+  dropped: number
+  /** BlockWithTimeout 策略下等待超时而丢弃的消息数，同时计入 dropped */
+  blockTimeouts: number
+}
+/**
+* 未匹配到请求的业务消息，通过 `sdk.on('message', ...)` 派发
+* 可用 `newBizMessage().setBuff(message.buff)` 还原完整消息
+*/
+export interface UnsolicitedMessage {
+  /** 'late' | 'push' | 'request' | 'unknown' */
+  reason: string
+  /** 发送句柄 */
+  hSend: number
+  functionNo: number
+  packetType: number
+  issueType: number
+  sequenceNo: number
+  returnCode: number
+  errorNo: number
+  errorInfo?: string
+  /** 业务内容 */
+  content: Buffer
+  /** 消息中心 1.0 推送的关键字段信息 */
+  keyInfo?: Buffer
+  /** 整个消息的二进制 */
+  buff: Buffer
+}
 export declare class BizMessage {
+  /** 设置错误信息使用的编码 */
+  setEncoding(encoding: TextEncoding): void
+  /** 获取错误信息使用的编码 */
+  getEncoding(): TextEncoding
   /** 设置功能号 */
   setFunction(functionNo: number): void
   /** 获取功能号 */
@@ -79,7 +632,7 @@ export declare class BizMessage {
   getErrorNo(): number
   /** 设置错误信息 */
   setErrorInfo(errorInfo: string): void
-  /** 获取错误信息 */
+  /** 获取错误信息，合法的 UTF-8 原样返回，否则按当前编码解码，非法序列替换为 U+FFFD */
   getErrorInfo(): string
   /** 设置返回码 */
   setReturnCode(returnCode: number): void
@@ -130,6 +683,26 @@ export declare class BizMessage {
   getAppReserved(): Buffer
 }
 export declare class Config {
+  /**
+  * 解析 ini 配置文件并校验必填项和许可证文件，未知的配置项同样写入配置并输出警告，可通过 getUnknownKeys 获取
+  * @param path ini 文件路径
+  * @param encoding 文件编码，默认为 GBK
+  */
+  static fromFile(path: string, encoding?: TextEncoding | undefined | null): Config
+  /** 获取 fromFile 解析时遇到的未知配置项（section.key） */
+  getUnknownKeys(): Array<string>
+  /**
+  * 校验当前配置并返回类型化配置
+  * 检查 servers、license_file 等必填项以及许可证、证书文件是否存在
+  */
+  validate(): T2Config
+  /**
+  * 设置文本编码，作用于字符串配置值；通过 T2SDK 使用时同时作用于请求打包、应答解包和错误信息
+  * @param encoding 默认为 GBK
+  */
+  setEncoding(encoding: TextEncoding): void
+  /** 获取文本编码 */
+  getEncoding(): TextEncoding
   /**
   * 从文件加载
   * @param szFileName 文件名，格式类似ini，具体参考开发包示例
@@ -176,12 +749,17 @@ export declare class Config {
   setInt(section: string, entry: string, value: number): number
 }
 export declare class Packer {
+  /** 设置字符串和字段名使用的编码 */
+  setEncoding(encoding: TextEncoding): void
+  /** 获取字符串和字段名使用的编码 */
+  getEncoding(): TextEncoding
   /**
   * 打包器初始化(使用调用者的缓存区)
   * 第一次使用打包器时，可先使用本方法设置好缓冲区(数据长度被置为iDataLen)
   * @param  char * pBuf  缓冲区地址
   * @param  int iBufSize  缓冲区空间
   * @param  int iDataLen  已有数据长度，新增数据加在已有数据之后（只对V1.0格式的包有效）
+  * 打包器持有 pBuf 的引用直到释放，打包期间不要修改 pBuf
   */
   setBuffer(pBuf: Buffer, iDataLen?: number | undefined | null): void
   /**
@@ -271,6 +849,7 @@ export declare class Packer {
   setReturnCode(dwRetCode: number): void
   /**
   * 直接返回当前打包结果的解包接口,必须在EndPack()之后才能调用,在打包器释放时相应的解包器实例也释放
+  * 返回的解包器持有打包器的引用，两者都释放后打包器才释放
   * @return 解包器接口，此解包接口不能调用 destroy()来释放
   */
   unpack(): UnPacker
@@ -294,22 +873,56 @@ export declare class Packer {
   
    */
   clearDataSet(): void
+  /**
+  * 按字段列表和多行数据打包，在一次调用中完成 addField 和所有取值，返回打包结果
+  * @param fields 字段列表，元素为字段名或 { name, type, width, scale }，未声明类型时按各行的值推断
+  * @param rows 行数据，元素为对象（按字段名取值）或数组（按字段顺序取值），转换规则与 send 相同
+  */
+  static fromRows(fields: Array<string | { name: string, type?: 'I' | 'D' | 'C' | 'S' | 'R', width?: number, scale?: number }>, rows: Array<object | Array<unknown>>, options?: FromRowsOptions): Buffer
+  /**
+  * 向当前结果集追加多行，在一次调用中完成所有取值，字段需已通过 addField 添加
+  * @param rows 行数据，元素为对象（按字段名取值）或数组（按字段顺序取值），转换规则与 send 相同
+  * @returns 追加的行数
+  */
+  appendRows(rows: Array<object | Array<unknown>>, options?: AppendRowsOptions): number
 }
 export declare class UnPacker {
+  /** 设置字符串、字段名和结果集名使用的编码 */
+  setEncoding(encoding: TextEncoding): void
+  /** 获取字符串、字段名和结果集名使用的编码 */
+  getEncoding(): TextEncoding
+  /** 字段类型与取值方法不符时是否转换，见 UnpackOptions.coerce */
+  setCoerce(coerce: boolean): void
+  getCoerce(): boolean
+  /** 'D' 字段在 rows()、datasets()、toColumns() 中的返回形式，见 UnpackOptions.decimal */
+  setDecimalMode(mode: DecimalMode): void
+  getDecimalMode(): DecimalMode
   getColCount(): number
   getColName(column: number): string
   getColType(column: number): number
   getColScale(column: number): number
   getColWidth(column: number): number
+  /** 字段不存在时返回 -1 */
   findColIndex(columnName: string): number
+  /** 'S'、'C' 字段，转换时也接受 'I'、'D'（按精度格式化）和 'R'（按编码解码）字段 */
   getStrByIndex(column: number): string
   getStr(columnName: string): string
+  /**
+  * 'D' 字段按 getColScale 的精度格式化为十进制字符串，避免 double 的舍入误差
+  * 'I' 字段返回整数，转换时也接受 'S'、'C' 字段，原样返回
+  */
+  getDecimalByIndex(column: number): string
+  getDecimal(columnName: string): string
+  /** 'C' 字段，转换时也接受 'S' 字段，取第一个字节 */
   getCharByIndex(column: number): number
   getChar(columnName: string): number
+  /** 'D'、'I' 字段，转换时也接受 'S'、'C' 字段 */
   getDoubleByIndex(column: number): number
   getDouble(columnName: string): number
+  /** 'I' 字段，转换时也接受 'S'、'C' 字段和 'D' 字段（截断小数） */
   getIntByIndex(column: number): number
   getInt(columnName: string): number
+  /** 'R' 字段，转换时也接受 'S'、'C' 字段，返回未解码的字符串内容 */
   getRawByIndex(column: number): Buffer
   getRaw(columnName: string): Buffer
   wasNull(): number
@@ -318,7 +931,11 @@ export declare class UnPacker {
   isEmpty(): number
   destroy(): void
   getVersion(): number
-  open(buffer: Buffer): number
+  /**
+  * 打开新的输入，默认复制 buffer；options.zeroCopy 为 true 时持有 buffer 的引用
+  * 之前的输入在解包器不再读取后释放
+  */
+  open(buffer: Buffer, options?: UnpackOptions | undefined | null): number
   getDatasetCount(): number
   setCurrentDatasetByIndex(index: number): number
   setCurrentDataset(datasetName: string): number
@@ -328,17 +945,263 @@ export declare class UnPacker {
   last(): void
   go(row: number): void
   getDatasetName(): string
+  /** 由 SDK 复制 buffer 后打开 */
   openAndCopy(buffer: Buffer): number
   getColTypeByName(columnName: string): number
   getColScaleByName(columnName: string): number
   getColWidthByName(columnName: string): number
+  /**
+  * 从第一行开始逐行读取当前结果集
+  * for (const row of unpacker.rows()) { ... }
+  */
+  rows(): RowIterator
+  /**
+  * 依次读取各结果集
+  * for (const dataset of unpacker.datasets()) { for (const row of dataset.rows()) { ... } }
+  */
+  datasets(): DatasetIterator
+  /**
+  * 按列读取所有结果集，'I' 字段为 Int32Array，'D' 字段为 Float64Array，'R' 字段为 Buffer 数组，其余为字符串数组
+  * DecimalMode 不为 Number 时 'D' 字段为十进制字符串数组或 Decimal 数组
+  * 读取后当前结果集为最后一个
+  */
+  toColumns(): Array<ColumnarDataset>
+}
+/**
+* 逐行读取结果集的迭代器，每次迭代在一次调用中构造一行
+* 与解包器共用游标，同一解包器同时只应有一个迭代器在读取
+*/
+export declare class RowIterator {
+  [Symbol.iterator](): Iterator<Record<string, string | number | Buffer | Decimal>, void, void>
+}
+/** 一个结果集，rows() 切换到该结果集后逐行读取 */
+export declare class DatasetCursor {
+  get index(): number
+  get name(): string
+  get rowCount(): number
+  rows(): RowIterator
+}
+/** 依次返回各结果集的迭代器 */
+export declare class DatasetIterator {
+  [Symbol.iterator](): Iterator<DatasetCursor, void, void>
+}
+/**
+* 文件更新，创建后服务端推送可更新的文件列表
+* 事件：fileList（收到文件列表）、progress（更新进度）、cancel（取消成功）
+*/
+export declare class FileUpdater {
+  /**
+  * 注册事件监听
+  * - fileList: `(event: FileListEvent) => void`
+  * - progress: `(event: ProgressEvent) => void`
+  * - cancel: `() => void`
+  */
+  on(event: 'fileList' | 'progress' | 'cancel', callback: (event: any) => void): void
+  /** 移除所有事件监听 */
+  removeAllListeners(): void
+  /**
+  * 获取远端文件列表，尚未收到时等待 OnRecvFileList
+  * @param timeoutMs 等待时间，默认为创建时的超时时间
+  */
+  listFiles(timeoutMs?: number | undefined | null): Promise<Array<RemoteFile>>
+  /**
+  * 下载选中的文件到更新目录，总进度到达 100 时完成
+  * @param ids 文件 id 列表，来自 listFiles
+  */
+  update(ids: Array<number>): Promise<void>
+  /** 取消正在进行的更新，成功后触发 cancel 事件 */
+  cancel(): number
+}
+/**
+* 订阅推送的有界队列，通过 Subscriber.createQueue 创建
+* JS 侧的 `subscriber.messages()` 和 `subscriber.stream()` 基于该队列实现
+*/
+export declare class MessageQueue {
+  /**
+  * 读取下一条消息，队列为空时等待
+  * @returns 队列关闭后返回 null；Error 策略下队列溢出时报错
+  */
+  next(): Promise<PushMessage | null>
+  /** 关闭队列，未读取的消息被丢弃，等待中的 next() 返回 null */
+  close(): void
+  get isClosed(): boolean
+  /** 获取队列计数 */
+  getStats(): QueueStats
+}
+/**
+* 订阅者，通过 T2SDK.newSubscriber 创建
+* 订阅者记录成功的订阅项，连接断开重连后自动重新订阅，订阅项的 id 保持不变
+* 事件：message（收到推送）、tick（订阅项被剔除）、resubscribed（重新订阅成功）、resubscribeFailed（重新订阅失败）
+*/
+export declare class Subscriber {
+  /**
+  * 注册事件监听
+  * - message: `(message: PushMessage) => void`
+  * - tick: `(event: TickEvent) => void`
+  * - resubscribed: `(event: ResubscribedEvent) => void`
+  * - resubscribeFailed: `(event: ResubscribeFailedEvent) => void`
+  * - gap: `(event: GapEvent) => void`，需要设置 msgNoField
+  * - duplicate: `(event: DuplicateEvent) => void`，需要设置 msgNoField
+  * - reset: `(event: ResetEvent) => void`，需要设置 msgNoField
+  */
+  on(event: 'message' | 'tick' | 'resubscribed' | 'resubscribeFailed' | 'gap' | 'duplicate' | 'reset', callback: (event: any) => void): void
+  /** 移除所有事件监听 */
+  removeAllListeners(): void
+  /**
+  * 批量订阅，一次往返创建多个订阅项
+  * @param params 订阅参数列表
+  * @param timeoutMs 超时时间，默认为创建时的超时时间
+  * @returns 每个订阅项的结果，顺序与服务端应答一致；失败的订阅项没有 id / subscribeIndex，带有 errorNo / errorInfo
+  *
+  * 订阅项有检查点且未指定 fromNow 时按补缺订阅，已处理过的消息按序号去重
+  */
+  subscribeMany(params: Array<SubscribeParams>, timeoutMs?: number | undefined | null): Promise<Array<SubscribeResult>>
+  /**
+  * 按稳定标识取消订阅
+  * @param id 订阅项的 id，来自 subscribeMany 的返回
+  * @returns 0 表示成功
+  */
+  unsubscribe(id: number): number
+  /**
+  * 按 SDK 的订阅标识取消订阅
+  * @param subscribeIndex 订阅标识
+  * @returns 0 表示成功
+  */
+  cancel(subscribeIndex: number): number
+  /** 获取订阅者记录的订阅项，重连后用于重新订阅 */
+  getSubscriptions(): Array<SubscriptionInfo>
+  /**
+  * 创建推送消息的有界队列，用于按需读取消息
+  * @param topic 只接收该主题的消息，默认接收所有主题
+  */
+  createQueue(topic?: string | undefined | null, options?: QueueOptions | undefined | null): MessageQueue
+  /**
+  * 以异步迭代器读取推送消息（main.js 实现）
+  * for await (const msg of subscriber.messages(topic, { capacity, overflow })) { ... }
+  * 提前退出循环时关闭队列
+  */
+  messages(topic?: string | undefined | null, options?: QueueOptions | undefined | null): AsyncIterable<PushMessage> & { queue: MessageQueue }
+  /** 以 objectMode 的 Readable 读取推送消息（main.js 实现），消费变慢时由队列的 overflow 策略处理积压 */
+  stream(topic?: string | undefined | null, options?: QueueOptions | undefined | null): import('stream').Readable & { queue: MessageQueue }
+  /** 获取每个订阅项最后收到的消息序号 */
+  getCheckpoints(): Array<CheckpointInfo>
+  /** 立即写入检查点文件 */
+  saveCheckpoints(): void
+  /** 获取 SDK 当前的订阅项（GetSubcribeTopic） */
+  getSubscribedTopics(): Array<SubscribedTopic>
+  /**
+  * 获取订阅会话状态
+  * @returns 0 与消息中心连接断开，1 正在建立连接，2 连接已建立
+  */
+  getSessionStatus(): number
+}
+/**
+* 预编译的请求模板，固定功能号和字段列表
+* 每次 send 复用同一个打包器和消息，按声明的字段类型转换取值，不再推断类型
+*/
+export declare class PackTemplate {
+  get functionNo(): number
+  /** 字段名，按打包顺序 */
+  get fieldNames(): Array<string>
+  /**
+  * 按模板打包一行并发送，转换规则与 T2SDK.send 相同
+  * @param values 对象（按字段名取值）或数组（按字段顺序取值），缺少的字段按空值打包
+  */
+  send(values: object | Array<unknown>, options?: SendOptions): Promise<BizMessage>
 }
 export type T2SDK = T2Sdk
 export declare class T2Sdk {
   constructor()
-  setConfig(config: object): void
-  send(header: T2Header, body: string): Promise<BizMessage>
+  /**
+  * 设置配置，格式与 t2sdk.ini 一致，如 `{ t2sdk: { servers, license_file }, safe: { safe_level } }`
+  * 可分多次设置，只校验本次传入的配置项（取值类型、地址格式、许可证文件是否存在等），必填项在 connect 时检查
+  * 未知的配置项与 Config.fromFile 一致：照常写入并输出警告
+  */
+  setConfig(config: { t2sdk?: Partial<T2SdkSection>, safe?: SafeSection }): void
+  /**
+  * 从 ini 文件加载配置，文件按当前文本编码读取
+  * @param path ini 文件路径
+  */
+  loadConfig(path: string): void
+  /**
+  * 发送业务请求，body 为 JS 对象（也兼容 JSON 字符串）
+  * - `{ datasets: [{ name, returnCode, fields?, rows: [...] }] }` 多结果集多行，未指定 fields 时取所有行键的并集
+  * - `{ ... }` 单结果集单行；值为对象的键各自作为一个结果集
+  * - `[{ ... }]` 每个元素为一个无名结果集
+  *
+  * `Buffer` 按 'R' 字段打包，`Date` 按字段类型转换
+  * （'I' 为 YYYYMMDD，'D' 为毫秒时间戳，'S' 为 `YYYY-MM-DD HH:mm:ss`，未声明类型时按 'I'）
+  *
+  * 默认为宽松模式：布尔按 'C' 打包为 '0'/'1'，null 打包为空值，超出 int32 的 `BigInt` 按字符串打包，
+  * 目标编码无法表示的字符替换为 '?'，嵌套对象/数组所在的列被忽略；
+  * 数字字符串写入 'I'/'D' 字段时按数值打包；其他与声明类型不符的值（如非数字字符串写入 'I' 字段）或声明为 'I' 的字段溢出仍会报错。
+  * `options.strict` 为 true 时不做上述转换，返回列出每个问题路径和原因的错误
+  * `options.decimal` 为 true 时声明为 'D' 的字段可传十进制字符串，避免 number 的舍入误差
+  */
+  send(header: T2Header, body: object | string, options?: SendOptions): Promise<BizMessage>
+  /**
+  * 预编译 functionNo 请求的字段列表，返回可重复发送的模板
+  * 模板复用打包器和消息，send 时只按声明的类型写入取值；每个字段都需声明 type
+  * 未声明 width 的 'S'、'R' 字段宽度为 255，取值超过宽度时 send 报错
+  * 模板持有 SDK 对象，断开后 send 报错，重新连接后可继续使用
+  */
+  compileTemplate(functionNo: number, fieldSchema: Array<{ name: string, type: 'I' | 'D' | 'C' | 'S' | 'R', width?: number, scale?: number }>, options?: TemplateOptions): PackTemplate
+  /** 连接服务器，连接前校验配置的必填项（servers、license_file 等），校验不通过时只记录警告，以 SDK 的连接结果为准 */
   connect(): number
+  /**
+  * 监听事件，在 connect 之前或之后调用均可
+  * - message: `(message: UnsolicitedMessage) => void`，连接上没有对应请求的业务消息，reason 为
+  *   'late'（请求超时后到达的应答）、'push'（消息中心 1.0 推送）、'request'（服务端发起的请求）或 'unknown'
+  * - trace: `(trace: RequestTrace) => void`，请求结束时的跟踪记录，有监听时才记录
+  */
+  on(event: 'message' | 'trace', callback: (event: any) => void): void
+  /** 移除所有事件监听 */
+  removeAllListeners(): void
+  /**
+  * 消息中心 1.0 订阅，发送 620001 请求，订阅成功后 handler 收到该订阅类型的推送（620003）
+  * 连接期间自动应答消息中心的心跳（620000）
+  * @returns 订阅 id，用于 unsubscribeV1
+  */
+  subscribeV1(params: SubscribeV1Params, handler: (message: PushMessageV1) => void): Promise<number>
+  /** 取消消息中心 1.0 订阅，发送 620002 请求，本地的 handler 立即移除 */
+  unsubscribeV1(id: number, timeoutMs?: number | undefined | null): Promise<void>
+  /**
+  * 处理发给本进程的 functionNo 请求，同一功能号重复注册时替换之前的 handler
+  * handler 返回应答体（格式与 send 的 body 相同）或其 Promise，返回 undefined 时应答空业务体；
+  * handler 抛出异常或 Promise 被拒绝时以 errorNo、returnCode 为 -1 和异常信息应答
+  */
+  serve(functionNo: number, handler: (request: ServeRequest) => object | string | undefined | Promise<object | string | undefined>): void
+  /** 移除 functionNo 的请求处理函数，之后的请求交给 on('message') */
+  unserve(functionNo: number): boolean
+  /** 获取按功能号统计的请求计数、往返耗时和连接状态 */
+  getMetrics(): SdkMetrics
+  /** 以 Prometheus 文本格式获取 getMetrics 的内容 */
+  getPrometheusMetrics(): string
+  /**
+  * 设置文本编码，作用于请求打包、应答的错误信息和连接错误信息
+  * @param encoding 默认为 GBK，可选 GB18030、UTF-8
+  */
+  setEncoding(encoding: TextEncoding): void
+  /** 获取文本编码 */
+  getEncoding(): TextEncoding
+  /**
+  * 创建文件更新对象，连接成功后服务端推送 topic 下可更新的文件列表
+  * 文件更新对象依附于当前连接，disconnect 后调用其方法报错 Connection closed
+  * @param topic 文件更新主题
+  * @param scanDir 本地扫描目录，用于比对已有文件
+  * @param updateDir 文件下载目录
+  */
+  newFileUpdater(topic: string, scanDir: string, updateDir: string, options?: FileUpdaterOptions | undefined | null): FileUpdater
+  /**
+  * 创建订阅者，订阅者依附于当前连接，disconnect 后调用其方法报错 Connection closed
+  * @param name 订阅者名字
+  */
+  newSubscriber(name: string, options?: SubscriberOptions | undefined | null): Subscriber
+  /**
+  * 获取消息中心的所有主题信息，结果按连接缓存
+  * @param options.force 为 true 时强制从服务端获取并刷新缓存
+  */
+  getTopics(options?: GetTopicsOptions | undefined | null): Promise<Array<TopicInfo>>
   get isConnected(): boolean
   disconnect(): void
 }
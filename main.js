@@ -1,4 +1,59 @@
+const { Readable } = require('stream');
 const addon = require('./node-t2sdk-rs.node');
 
+/**
+ * 以异步迭代器读取推送消息
+ * for await (const msg of subscriber.messages(topic, { capacity, overflow })) { ... }
+ * 提前退出循环时关闭队列
+ */
+addon.Subscriber.prototype.messages = function (topic, options) {
+  const queue = this.createQueue(topic, options);
+  return {
+    queue,
+    [Symbol.asyncIterator]() {
+      return {
+        async next() {
+          const value = await queue.next();
+          return value == null ? { done: true, value: undefined } : { done: false, value };
+        },
+        async return() {
+          queue.close();
+          return { done: true, value: undefined };
+        },
+      };
+    },
+  };
+};
 
-module.exports = addon;
\ No newline at end of file
+/**
+ * 以 objectMode 的 Readable 读取推送消息，消费变慢时由队列的 overflow 策略处理积压
+ */
+addon.Subscriber.prototype.stream = function (topic, options) {
+  const queue = this.createQueue(topic, options);
+  let reading = false;
+  const stream = new Readable({
+    objectMode: true,
+    read() {
+      if (reading) return;
+      reading = true;
+      queue.next().then(
+        (value) => {
+          reading = false;
+          this.push(value == null ? null : value);
+        },
+        (err) => {
+          reading = false;
+          this.destroy(err);
+        },
+      );
+    },
+    destroy(err, callback) {
+      queue.close();
+      callback(err);
+    },
+  });
+  stream.queue = queue;
+  return stream;
+};
+
+module.exports = addon;
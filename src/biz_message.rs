@@ -1,11 +1,11 @@
-extern crate encoding;
-use encoding::Encoding;
 use napi::{bindgen_prelude::Buffer, Error, Result, Status};
 use napi_derive::napi;
-use std::ffi::{c_void, CStr, CString};
+use std::ffi::{c_void, CStr};
 use std::os::raw::{c_char, c_int, c_ulong};
 
+use crate::charset::TextEncoding;
 // use crate::f2_packer::Packer;
+use crate::handles::{Handle, HandleKind};
 use crate::iknown::{IKnown, IKnownVTable};
 
 pub const IDENTITY_NAME_LENGTH: usize = 32;
@@ -325,6 +325,8 @@ impl IBizMessage for IBizMessageRust {
 #[napi]
 pub struct BizMessage {
   ptr: *const IBizMessageRust,
+  encoding: TextEncoding,
+  handle: Handle,
 }
 
 impl BizMessage {
@@ -337,15 +339,23 @@ impl BizMessage {
         return Err(napi::Error::from_reason("Failed to create biz message instance"));
       }
 
-      Ok(BizMessage { ptr })
+      Ok(BizMessage {
+        ptr,
+        encoding: TextEncoding::default(),
+        handle: Handle::owned(HandleKind::BizMessage),
+      })
     }
   }
-  pub fn new_form_ptr(ptr: *const IBizMessageRust) -> Self {
-    let message = BizMessage { ptr };
+  /// 包装 SDK 回调中的消息，add_ref() 后持有引用，可在回调返回后继续使用
+  pub fn new_form_ptr(ptr: *const IBizMessageRust, encoding: TextEncoding) -> Self {
     unsafe {
       (*ptr).add_ref();
     }
-    return message;
+    BizMessage {
+      ptr,
+      encoding,
+      handle: Handle::owned(HandleKind::BizMessage),
+    }
   }
 
   fn check_ptr(&self) -> Result<&IBizMessageRust> {
@@ -357,13 +367,20 @@ impl BizMessage {
     }
   }
 
-  fn to_c_string(s: String, field: &str) -> Result<CString> {
-    CString::new(s).map_err(|e| napi::Error::from_reason(format!("Invalid {}: {}", field, e)))
-  }
   // get ptr
   pub fn get_ptr(&self) -> *const IBizMessageRust {
     self.ptr
   }
+
+  /// 设置业务内容，SDK 复制 content
+  pub(crate) fn set_content_bytes(&self, content: &[u8]) -> Result<()> {
+    unsafe {
+      self
+        .check_ptr()?
+        .set_content(content.as_ptr() as *mut c_void, content.len() as c_int);
+    }
+    Ok(())
+  }
 }
 
 #[napi(object)]
@@ -402,6 +419,16 @@ fn c_array_to_string(array: &[c_char]) -> Result<String> {
 
 #[napi]
 impl BizMessage {
+  /// 设置错误信息使用的编码
+  #[napi]
+  pub fn set_encoding(&mut self, encoding: TextEncoding) {
+    self.encoding = encoding;
+  }
+  /// 获取错误信息使用的编码
+  #[napi]
+  pub fn get_encoding(&self) -> TextEncoding {
+    self.encoding
+  }
   /// 设置功能号
   #[napi]
   pub fn set_function(&self, function_no: i32) -> Result<()> {
@@ -588,25 +615,19 @@ impl BizMessage {
   /// 设置错误信息
   #[napi]
   pub fn set_error_info(&self, error_info: String) -> Result<()> {
-    let c_error_info = Self::to_c_string(error_info, "error_info")?;
+    let c_error_info = self.encoding.to_c_string(&error_info, "error_info")?;
     unsafe {
       self.check_ptr()?.set_error_info(c_error_info.as_ptr());
     }
     Ok(())
   }
-  /// 获取错误信息
+  /// 获取错误信息，合法的 UTF-8 原样返回，否则按当前编码解码，非法序列替换为 U+FFFD
   #[napi]
   pub fn get_error_info(&self) -> Result<String> {
-    let c_error_info = unsafe { self.check_ptr()?.get_error_info() };
-    let bytes = unsafe {
-      if c_error_info.is_null() {
-        &[]
-      } else {
-        CStr::from_ptr(c_error_info).to_bytes()
-      }
-    };
-    let utf8 = encoding::all::GBK.decode(bytes, encoding::DecoderTrap::Strict).unwrap();
-    Ok(utf8)
+    unsafe {
+      let c_error_info = self.check_ptr()?.get_error_info();
+      Ok(self.encoding.decode_c_str_lossy(c_error_info))
+    }
   }
   /// 设置返回码
   #[napi]
@@ -625,13 +646,7 @@ impl BizMessage {
   /// 设置业务内容
   #[napi]
   pub fn set_content(&self, content: Buffer) -> Result<()> {
-    let content = content.as_ref();
-    unsafe {
-      self
-        .check_ptr()?
-        .set_content(content.as_ptr() as *mut c_void, content.len() as c_int);
-    }
-    Ok(())
+    self.set_content_bytes(content.as_ref())
   }
   /// 获取业务内容
   #[napi]
@@ -811,7 +826,7 @@ impl BizMessage {
 impl Drop for BizMessage {
   fn drop(&mut self) {
     unsafe {
-      if !self.ptr.is_null() {
+      if !self.ptr.is_null() && self.handle.is_owned() {
         let ptr = &*self.ptr;
         ptr.release();
         self.ptr = std::ptr::null_mut();
@@ -65,17 +65,17 @@ pub struct CCallbackRust {
   sequence: i32,
 }
 
-extern "C" fn callback_query_interface(_this: *mut c_void, _riid: *const c_char, _ppvObject: *mut *mut c_void) -> c_ulong {
+pub(crate) extern "C" fn callback_query_interface(_this: *mut c_void, _riid: *const c_char, _ppv_object: *mut *mut c_void) -> c_ulong {
   // println!("CCallbackRust::QueryInterface called");
   0
 }
 
-extern "C" fn callback_add_ref(_this: *mut c_void) -> c_ulong {
+pub(crate) extern "C" fn callback_add_ref(_this: *mut c_void) -> c_ulong {
   // println!("CCallbackRust::AddRef called");
   1
 }
 
-extern "C" fn callback_release(_this: *mut c_void) -> c_ulong {
+pub(crate) extern "C" fn callback_release(_this: *mut c_void) -> c_ulong {
   // println!("CCallbackRust::Release called");
   0
 }
@@ -97,14 +97,15 @@ extern "C" fn on_close(_this: *mut CCallbackRust, _connection: *mut IConnectionR
 }
 
 extern "C" fn on_sent(
-  _this: *mut CCallbackRust,
+  this: *mut CCallbackRust,
   _connection: *mut IConnectionRust,
-  _h_send: c_int,
+  h_send: c_int,
   _reserved1: *mut c_void,
   _reserved2: *mut c_void,
   _queuing_data: c_int,
 ) {
-  // println!("OnSent called");
+  let sequence = unsafe { (*this).sequence };
+  CallbackRegistry::instance().on_sent((sequence, h_send));
 }
 
 extern "C" fn reserved1(_this: *mut CCallbackRust, _a: *mut c_void, _b: *mut c_void, _c: *mut c_void, _d: *mut c_void) {
@@ -157,12 +158,12 @@ extern "C" fn on_received_biz_ex(
   // println!("OnReceivedBizEx called");
 }
 
-extern "C" fn on_received_biz_msg(this: *mut CCallbackRust, _connection: *mut IConnectionRust, h_send: c_int, msg: *mut IBizMessageRust) {
+extern "C" fn on_received_biz_msg(this: *mut CCallbackRust, connection: *mut IConnectionRust, h_send: c_int, msg: *mut IBizMessageRust) {
   unsafe {
     let sequence = (*this).sequence;
     let combined_key = (sequence, h_send);
     let registry = CallbackRegistry::instance();
-    registry.invoke_callback(combined_key, msg);
+    registry.invoke_callback(combined_key, connection, msg);
   }
 }
 
@@ -190,8 +191,5 @@ pub fn create_callback(sequence: i32) -> Box<CCallbackRust> {
     on_received_biz_msg,
   };
 
-  Box::new(CCallbackRust {
-    vtable: &VTABLE,
-    sequence: sequence,
-  })
+  Box::new(CCallbackRust { vtable: &VTABLE, sequence })
 }
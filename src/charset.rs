@@ -0,0 +1,113 @@
+use encoding::Encoding;
+use napi::Result;
+use napi_derive::napi;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+
+/// 与柜台交互时使用的文本编码，默认为 GBK
+#[napi]
+#[derive(Debug, Default, PartialEq)]
+pub enum TextEncoding {
+  #[default]
+  Gbk,
+  Gb18030,
+  Utf8,
+}
+
+impl TextEncoding {
+  fn name(self) -> &'static str {
+    match self {
+      TextEncoding::Gbk => "GBK",
+      TextEncoding::Gb18030 => "GB18030",
+      TextEncoding::Utf8 => "UTF-8",
+    }
+  }
+
+  /// 编码字符串，遇到无法表示的字符时报错
+  pub(crate) fn encode(self, s: &str) -> Result<Vec<u8>> {
+    let result = match self {
+      TextEncoding::Gbk => encoding::all::GBK.encode(s, encoding::EncoderTrap::Strict),
+      TextEncoding::Gb18030 => encoding::all::GB18030.encode(s, encoding::EncoderTrap::Strict),
+      TextEncoding::Utf8 => return Ok(s.as_bytes().to_vec()),
+    };
+    result.map_err(|e| napi::Error::from_reason(format!("Failed to encode {:?} as {}: {}", s, self.name(), e)))
+  }
+
+  /// 编码字符串，无法表示的字符替换为 '?'
+  pub(crate) fn encode_lossy(self, s: &str) -> Vec<u8> {
+    let result = match self {
+      TextEncoding::Gbk => encoding::all::GBK.encode(s, encoding::EncoderTrap::Replace),
+      TextEncoding::Gb18030 => encoding::all::GB18030.encode(s, encoding::EncoderTrap::Replace),
+      TextEncoding::Utf8 => return s.as_bytes().to_vec(),
+    };
+    result.unwrap_or_default()
+  }
+
+  /// 解码字节，遇到非法序列时报错
+  pub(crate) fn decode(self, bytes: &[u8]) -> Result<String> {
+    let result = match self {
+      TextEncoding::Gbk => encoding::all::GBK.decode(bytes, encoding::DecoderTrap::Strict),
+      TextEncoding::Gb18030 => encoding::all::GB18030.decode(bytes, encoding::DecoderTrap::Strict),
+      TextEncoding::Utf8 => std::str::from_utf8(bytes).map(|s| s.to_string()).map_err(|e| e.to_string().into()),
+    };
+    result.map_err(|e| napi::Error::from_reason(format!("Failed to decode {:?} as {}: {}", bytes, self.name(), e)))
+  }
+
+  /// 解码 C 字符串，空指针视为空字符串
+  pub(crate) unsafe fn decode_c_str(self, ptr: *const c_char) -> Result<String> {
+    if ptr.is_null() {
+      return Ok(String::new());
+    }
+    self.decode(CStr::from_ptr(ptr).to_bytes())
+  }
+
+  /**
+   * 解码错误信息等诊断文本，不会失败
+   * 合法的 UTF-8 原样返回，否则按当前编码解码（UTF-8 时按 GBK），非法序列替换为 U+FFFD
+   */
+  pub(crate) fn decode_lossy(self, bytes: &[u8]) -> String {
+    if let Ok(s) = std::str::from_utf8(bytes) {
+      return s.to_string();
+    }
+    let result = match self {
+      TextEncoding::Gb18030 => encoding::all::GB18030.decode(bytes, encoding::DecoderTrap::Replace),
+      TextEncoding::Gbk | TextEncoding::Utf8 => encoding::all::GBK.decode(bytes, encoding::DecoderTrap::Replace),
+    };
+    result.unwrap_or_else(|_| String::from_utf8_lossy(bytes).into_owned())
+  }
+
+  /// 按 decode_lossy 解码 C 字符串，空指针视为空字符串
+  pub(crate) unsafe fn decode_c_str_lossy(self, ptr: *const c_char) -> String {
+    if ptr.is_null() {
+      return String::new();
+    }
+    self.decode_lossy(CStr::from_ptr(ptr).to_bytes())
+  }
+
+  /// 编码为 C 字符串，field 用于错误提示
+  pub(crate) fn to_c_string(self, s: &str, field: &str) -> Result<CString> {
+    CString::new(self.encode(s)?).map_err(|e| napi::Error::from_reason(format!("Invalid {}: {}", field, e)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn strict_decode_rejects_invalid_bytes() {
+    let gbk = TextEncoding::Gbk.encode("错误").unwrap();
+    assert_eq!(TextEncoding::Gbk.decode(&gbk).unwrap(), "错误");
+    assert!(TextEncoding::Utf8.decode(&gbk).is_err());
+    assert!(TextEncoding::Gbk.encode("😀").is_err());
+    assert_eq!(TextEncoding::Gbk.encode_lossy("a😀"), b"a?");
+  }
+
+  #[test]
+  fn lossy_decode_falls_back_from_utf8_to_gbk() {
+    let gbk = TextEncoding::Gbk.encode("连接失败").unwrap();
+    assert_eq!(TextEncoding::Utf8.decode_lossy(&gbk), "连接失败");
+    assert_eq!(TextEncoding::Gbk.decode_lossy("连接失败".as_bytes()), "连接失败");
+    assert_eq!(TextEncoding::Gbk.decode_lossy(&[b'a', 0xff]), "a\u{fffd}");
+  }
+}
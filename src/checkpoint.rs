@@ -0,0 +1,210 @@
+use napi::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::f2_unpacker::UnPacker;
+
+/// 某个订阅项（主题 + 过滤条件）最后收到的消息序号
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Checkpoint {
+  pub topic: String,
+  pub filters: BTreeMap<String, String>,
+  pub msg_no: i64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct CheckpointFile {
+  checkpoints: BTreeMap<String, Checkpoint>,
+}
+
+/// 收到的消息序号与上一条的关系
+pub(crate) enum Sequence {
+  /// 第一条，或紧接上一条
+  InOrder,
+  /// 中间有缺失，expected 为期望的序号
+  Gap { expected: i64 },
+  /// 序号不大于上一条，且在重置阈值以内
+  Duplicate { last: i64 },
+  /// 序号回退到 1（或更小）或回退达到重置阈值，视为服务端重启或序号重置，检查点改为该序号
+  Reset { last: i64 },
+}
+
+/// 订阅项的唯一键，过滤条件按字段名排序，如 `topic?a=1&b=2`
+pub(crate) fn subscription_key(topic: &str, filters: Option<&HashMap<String, String>>) -> String {
+  let filters: BTreeMap<&String, &String> = filters.into_iter().flatten().collect();
+  if filters.is_empty() {
+    return topic.to_string();
+  }
+  let filters: Vec<String> = filters.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+  format!("{}?{}", topic, filters.join("&"))
+}
+
+/// 按订阅项跟踪消息序号，检测缺失和重复，并可持久化到本地文件
+pub(crate) struct MsgNoTracker {
+  field: String,
+  file: Option<String>,
+  pub drop_duplicates: bool,
+  reset_threshold: i64,
+  checkpoints: Mutex<BTreeMap<String, Checkpoint>>,
+  dirty: AtomicBool,
+}
+
+impl MsgNoTracker {
+  /// 文件不存在时从空的检查点开始
+  pub fn new(field: String, file: Option<String>, drop_duplicates: bool, reset_threshold: i64) -> Result<Self> {
+    let checkpoints = match &file {
+      Some(path) if std::path::Path::new(path).exists() => {
+        let text =
+          std::fs::read_to_string(path).map_err(|e| napi::Error::from_reason(format!("Failed to read checkpoint file {}: {}", path, e)))?;
+        serde_json::from_str::<CheckpointFile>(&text)
+          .map_err(|e| napi::Error::from_reason(format!("Invalid checkpoint file {}: {}", path, e)))?
+          .checkpoints
+      }
+      _ => BTreeMap::new(),
+    };
+    Ok(MsgNoTracker {
+      field,
+      file,
+      drop_duplicates,
+      reset_threshold,
+      checkpoints: Mutex::new(checkpoints),
+      dirty: AtomicBool::new(false),
+    })
+  }
+
+  pub fn has_checkpoint(&self, key: &str) -> bool {
+    self.checkpoints.lock().unwrap().contains_key(key)
+  }
+
+  pub fn checkpoints(&self) -> Vec<(String, Checkpoint)> {
+    let checkpoints = self.checkpoints.lock().unwrap();
+    checkpoints.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+  }
+
+  /// 记录收到的序号，重复的序号不更新检查点
+  pub fn record(&self, key: &str, topic: &str, filters: Option<&HashMap<String, String>>, msg_no: i64) -> Sequence {
+    let mut checkpoints = self.checkpoints.lock().unwrap();
+    let sequence = match checkpoints.get(key) {
+      Some(checkpoint) if msg_no <= checkpoint.msg_no => {
+        let last = checkpoint.msg_no;
+        // 服务端重启或每日重置后序号从头开始，继续按重复丢弃会丢掉之后的所有消息
+        if msg_no > 1 && last - msg_no < self.reset_threshold {
+          return Sequence::Duplicate { last };
+        }
+        Sequence::Reset { last }
+      }
+      Some(checkpoint) if msg_no > checkpoint.msg_no + 1 => Sequence::Gap {
+        expected: checkpoint.msg_no + 1,
+      },
+      _ => Sequence::InOrder,
+    };
+    checkpoints.insert(
+      key.to_string(),
+      Checkpoint {
+        topic: topic.to_string(),
+        filters: filters.into_iter().flatten().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        msg_no,
+      },
+    );
+    self.dirty.store(true, Ordering::Release);
+    sequence
+  }
+
+  /// 有变化时写入检查点文件，先写临时文件再重命名
+  pub fn save(&self) -> Result<()> {
+    let Some(path) = &self.file else { return Ok(()) };
+    if !self.dirty.swap(false, Ordering::AcqRel) {
+      return Ok(());
+    }
+    let file = CheckpointFile {
+      checkpoints: self.checkpoints.lock().unwrap().clone(),
+    };
+    let text = serde_json::to_string_pretty(&file).map_err(|e| napi::Error::from_reason(e.to_string()))?;
+    let tmp = format!("{}.tmp", path);
+    std::fs::write(&tmp, text).and_then(|_| std::fs::rename(&tmp, path)).map_err(|e| {
+      self.dirty.store(true, Ordering::Release);
+      napi::Error::from_reason(format!("Failed to write checkpoint file {}: {}", path, e))
+    })
+  }
+
+  /// 从推送业务体的解包器中读取消息序号字段，没有该字段时返回 None
+  pub fn read_msg_no(&self, unpacker: &UnPacker) -> Option<i64> {
+    if unpacker.find_col_index(self.field.clone()).ok()? < 0 {
+      return None;
+    }
+    match unpacker.get_col_type_by_name(self.field.clone()).ok()? as u8 {
+      b'I' => unpacker.get_int(self.field.clone()).ok().map(i64::from),
+      b'D' => unpacker.get_double(self.field.clone()).ok().map(|d| d as i64),
+      _ => unpacker.get_str(self.field.clone()).ok()?.trim().parse().ok(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn tracker() -> MsgNoTracker {
+    MsgNoTracker::new("msg_no".to_string(), None, true, 100).unwrap()
+  }
+
+  fn record(tracker: &MsgNoTracker, msg_no: i64) -> Sequence {
+    tracker.record("t", "t", None, msg_no)
+  }
+
+  fn last(tracker: &MsgNoTracker) -> i64 {
+    tracker.checkpoints()[0].1.msg_no
+  }
+
+  #[test]
+  fn subscription_key_sorts_filters() {
+    let filters = HashMap::from([("b".to_string(), "2".to_string()), ("a".to_string(), "1".to_string())]);
+    assert_eq!(subscription_key("t", Some(&filters)), "t?a=1&b=2");
+    assert_eq!(subscription_key("t", None), "t");
+  }
+
+  #[test]
+  fn detects_gaps_and_duplicates() {
+    let tracker = tracker();
+    assert!(matches!(record(&tracker, 5), Sequence::InOrder));
+    assert!(matches!(record(&tracker, 6), Sequence::InOrder));
+    assert!(matches!(record(&tracker, 9), Sequence::Gap { expected: 7 }));
+    assert!(matches!(record(&tracker, 8), Sequence::Duplicate { last: 9 }));
+    assert!(matches!(record(&tracker, 9), Sequence::Duplicate { last: 9 }));
+    assert_eq!(last(&tracker), 9);
+  }
+
+  #[test]
+  fn restart_from_one_resets_the_checkpoint() {
+    let tracker = tracker();
+    record(&tracker, 50);
+    assert!(matches!(record(&tracker, 1), Sequence::Reset { last: 50 }));
+    assert_eq!(last(&tracker), 1);
+    assert!(matches!(record(&tracker, 2), Sequence::InOrder));
+  }
+
+  #[test]
+  fn large_backward_jump_resets_the_checkpoint() {
+    let tracker = tracker();
+    record(&tracker, 1000);
+    assert!(matches!(record(&tracker, 901), Sequence::Duplicate { last: 1000 }));
+    assert!(matches!(record(&tracker, 900), Sequence::Reset { last: 1000 }));
+    assert!(matches!(record(&tracker, 901), Sequence::InOrder));
+  }
+
+  #[test]
+  fn saves_and_loads_checkpoints() {
+    let path = std::env::temp_dir().join(format!("t2sdk-checkpoint-{}.json", std::process::id()));
+    let path = path.to_string_lossy().into_owned();
+    let tracker = MsgNoTracker::new("msg_no".to_string(), Some(path.clone()), true, 100).unwrap();
+    record(&tracker, 7);
+    tracker.save().unwrap();
+    let loaded = MsgNoTracker::new("msg_no".to_string(), Some(path.clone()), true, 100).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(loaded.has_checkpoint("t"));
+    assert_eq!(last(&loaded), 7);
+  }
+}
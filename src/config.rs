@@ -1,9 +1,13 @@
 use napi::Result;
 use napi_derive::napi;
-use std::ffi::{c_void, CStr, CString};
+use std::ffi::{c_void, CString};
 use std::os::raw::{c_char, c_int, c_ulong};
 
+use crate::charset::TextEncoding;
+use crate::config_schema::{self, ConfigEntry, T2Config};
+use crate::handles::{Handle, HandleKind};
 use crate::iknown::{IKnown, IKnownVTable};
+use crate::log::{log, LogLevel, Target};
 
 pub(crate) trait CConfigInterface: IKnown {
   unsafe fn load(&self, sz_file_name: *const c_char) -> c_int;
@@ -73,6 +77,9 @@ impl CConfigInterface for CConfigInterfaceRust {
 #[napi]
 pub struct Config {
   config_ptr: *mut CConfigInterfaceRust,
+  encoding: TextEncoding,
+  unknown_keys: Vec<String>,
+  handle: Handle,
 }
 
 impl Config {
@@ -85,7 +92,12 @@ impl Config {
         return Err(napi::Error::from_reason("Failed to create config instance"));
       }
 
-      Ok(Config { config_ptr })
+      Ok(Config {
+        config_ptr,
+        encoding: TextEncoding::default(),
+        unknown_keys: Vec::new(),
+        handle: Handle::owned(HandleKind::Config),
+      })
     }
   }
 
@@ -101,6 +113,39 @@ impl Config {
   fn to_c_string(s: String, field: &str) -> Result<CString> {
     CString::new(s).map_err(|e| napi::Error::from_reason(format!("Invalid {}: {}", field, e)))
   }
+  pub(crate) fn encoding(&self) -> TextEncoding {
+    self.encoding
+  }
+
+  /// 写入配置项，整数类型的已知配置项使用 SetInt；未知配置项照常写入，记录并输出警告
+  pub(crate) fn apply_entries(&mut self, entries: &[ConfigEntry]) -> Result<()> {
+    for key in config_schema::unknown_keys(entries) {
+      log(LogLevel::Warn, Target::Connect, || format!("Unknown config key {}", key));
+      if !self.unknown_keys.contains(&key) {
+        self.unknown_keys.push(key);
+      }
+    }
+    for entry in entries {
+      match entry.value.parse::<i32>() {
+        Ok(value) if entry.is_int() => self.set_int(entry.section.clone(), entry.key.clone(), value)?,
+        _ => self.set_string(entry.section.clone(), entry.key.clone(), entry.value.clone())?,
+      };
+    }
+    Ok(())
+  }
+
+  /// 读回所有已知配置项
+  fn known_entries(&self) -> Result<Vec<ConfigEntry>> {
+    config_schema::known_entries()
+      .map(|(section, key)| {
+        Ok(ConfigEntry {
+          section: section.to_string(),
+          key: key.to_string(),
+          value: self.get_string(section.to_string(), key.to_string(), String::new())?,
+        })
+      })
+      .collect()
+  }
   // 获取原始指针
   pub fn get_ptr(&self) -> *mut CConfigInterfaceRust {
     self.config_ptr
@@ -109,6 +154,48 @@ impl Config {
 
 #[napi]
 impl Config {
+  /**
+   * 解析 ini 配置文件并校验必填项和许可证文件，未知的配置项同样写入配置并输出警告，可通过 getUnknownKeys 获取
+   * @param path ini 文件路径
+   * @param encoding 文件编码，默认为 GBK
+   */
+  #[napi(factory)]
+  pub fn from_file(path: String, encoding: Option<TextEncoding>) -> Result<Config> {
+    let encoding = encoding.unwrap_or_default();
+    let entries = config_schema::read_ini(&path, encoding)?;
+    config_schema::validate(&entries)?;
+
+    let mut config = Config::new()?;
+    config.encoding = encoding;
+    config.apply_entries(&entries)?;
+    Ok(config)
+  }
+  /// 获取 fromFile 解析时遇到的未知配置项（section.key）
+  #[napi]
+  pub fn get_unknown_keys(&self) -> Vec<String> {
+    self.unknown_keys.clone()
+  }
+  /**
+   * 校验当前配置并返回类型化配置
+   * 检查 servers、license_file 等必填项以及许可证、证书文件是否存在
+   */
+  #[napi]
+  pub fn validate(&self) -> Result<T2Config> {
+    config_schema::validate(&self.known_entries()?)
+  }
+  /**
+   * 设置文本编码，作用于字符串配置值；通过 T2SDK 使用时同时作用于请求打包、应答解包和错误信息
+   * @param encoding 默认为 GBK
+   */
+  #[napi]
+  pub fn set_encoding(&mut self, encoding: TextEncoding) {
+    self.encoding = encoding;
+  }
+  /// 获取文本编码
+  #[napi]
+  pub fn get_encoding(&self) -> TextEncoding {
+    self.encoding
+  }
   /**
    * 从文件加载
    * @param szFileName 文件名，格式类似ini，具体参考开发包示例
@@ -145,7 +232,7 @@ impl Config {
     let config = self.check_ptr()?;
     let c_section = Self::to_c_string(section, "section")?;
     let c_entry = Self::to_c_string(entry, "entry")?;
-    let c_default = Self::to_c_string(default, "default")?;
+    let c_default = self.encoding.to_c_string(&default, "default")?;
 
     unsafe {
       let value_ptr = config.get_string(c_section.as_ptr(), c_entry.as_ptr(), c_default.as_ptr());
@@ -154,10 +241,7 @@ impl Config {
         return Err(napi::Error::from_reason("Null string returned from get_string"));
       }
 
-      CStr::from_ptr(value_ptr)
-        .to_str()
-        .map_err(|e| napi::Error::from_reason(format!("Invalid string encoding: {}", e)))
-        .map(String::from)
+      self.encoding.decode_c_str(value_ptr)
     }
   }
   /**
@@ -187,7 +271,7 @@ impl Config {
     let config = self.check_ptr()?;
     let c_section = Self::to_c_string(section, "section")?;
     let c_entry = Self::to_c_string(entry, "entry")?;
-    let c_value = Self::to_c_string(value, "value")?;
+    let c_value = self.encoding.to_c_string(&value, "value")?;
 
     unsafe { Ok(config.set_string(c_section.as_ptr(), c_entry.as_ptr(), c_value.as_ptr())) }
   }
@@ -214,8 +298,8 @@ unsafe impl Sync for Config {}
 impl Drop for Config {
   fn drop(&mut self) {
     unsafe {
-      if !self.config_ptr.is_null() {
-        println!("Config Release");
+      if !self.config_ptr.is_null() && self.handle.is_owned() {
+        log(LogLevel::Debug, Target::Drop, || "Config release".to_string());
         let config = &*self.config_ptr;
         config.release();
         self.config_ptr = std::ptr::null_mut();
@@ -0,0 +1,424 @@
+use napi::bindgen_prelude::Object;
+use napi::{JsUnknown, Result, ValueType};
+use napi_derive::napi;
+
+use crate::charset::TextEncoding;
+
+#[derive(Clone, Copy, PartialEq)]
+enum EntryType {
+  String,
+  Int,
+}
+
+/// t2sdk.ini 中已知的配置项
+const KNOWN_ENTRIES: &[(&str, &str, EntryType)] = &[
+  ("t2sdk", "servers", EntryType::String),
+  ("t2sdk", "license_file", EntryType::String),
+  ("t2sdk", "license_pwd", EntryType::String),
+  ("t2sdk", "lang", EntryType::Int),
+  ("t2sdk", "send_queue_size", EntryType::Int),
+  ("t2sdk", "login_name", EntryType::String),
+  ("t2sdk", "init_recv_load", EntryType::Int),
+  ("t2sdk", "auto_reconnect", EntryType::Int),
+  ("t2sdk", "heartbeat_time", EntryType::Int),
+  ("t2sdk", "logdir", EntryType::String),
+  ("t2sdk", "writedata", EntryType::Int),
+  ("safe", "safe_level", EntryType::String),
+  ("safe", "client_id", EntryType::String),
+  ("safe", "comm_pwd", EntryType::String),
+  ("safe", "cert_file", EntryType::String),
+  ("safe", "cert_pwd", EntryType::String),
+];
+
+/// [t2sdk] 节
+#[napi(object)]
+pub struct T2SdkSection {
+  /** 服务器地址，多个地址用分号分隔，如 `127.0.0.1:9999;127.0.0.2:9999` */
+  pub servers: String,
+  /** 许可证文件路径 */
+  #[napi(js_name = "license_file")]
+  pub license_file: String,
+  /** 许可证密码 */
+  #[napi(js_name = "license_pwd")]
+  pub license_pwd: Option<String>,
+  /** 语言，1033 英文，2052 中文 */
+  pub lang: Option<i32>,
+  /** 发送队列大小 */
+  #[napi(js_name = "send_queue_size")]
+  pub send_queue_size: Option<i32>,
+  /** 登录名 */
+  #[napi(js_name = "login_name")]
+  pub login_name: Option<String>,
+  /** 是否在连接时获取服务器负载 */
+  #[napi(js_name = "init_recv_load")]
+  pub init_recv_load: Option<i32>,
+  /** 是否自动重连 */
+  #[napi(js_name = "auto_reconnect")]
+  pub auto_reconnect: Option<i32>,
+  /** 心跳间隔（秒） */
+  #[napi(js_name = "heartbeat_time")]
+  pub heartbeat_time: Option<i32>,
+  /** 日志目录 */
+  pub logdir: Option<String>,
+  /** 是否记录收发数据 */
+  pub writedata: Option<i32>,
+}
+
+/// [safe] 节
+#[napi(object)]
+pub struct SafeSection {
+  /** 安全级别：none、pwd、ssl */
+  #[napi(js_name = "safe_level")]
+  pub safe_level: Option<String>,
+  #[napi(js_name = "client_id")]
+  pub client_id: Option<String>,
+  /** 通信密码，safe_level 为 pwd 时使用 */
+  #[napi(js_name = "comm_pwd")]
+  pub comm_pwd: Option<String>,
+  /** 证书文件，safe_level 为 ssl 时必填 */
+  #[napi(js_name = "cert_file")]
+  pub cert_file: Option<String>,
+  #[napi(js_name = "cert_pwd")]
+  pub cert_pwd: Option<String>,
+}
+
+/// t2sdk.ini 的类型化配置，键名与 ini 文件一致
+#[napi(object)]
+pub struct T2Config {
+  pub t2sdk: T2SdkSection,
+  pub safe: Option<SafeSection>,
+}
+
+/// 配置项的原始值
+pub(crate) struct ConfigEntry {
+  pub section: String,
+  pub key: String,
+  pub value: String,
+}
+
+impl ConfigEntry {
+  fn path(&self) -> String {
+    format!("{}.{}", self.section, self.key)
+  }
+
+  fn entry_type(&self) -> Option<EntryType> {
+    KNOWN_ENTRIES
+      .iter()
+      .find(|(section, key, _)| *section == self.section && *key == self.key)
+      .map(|(_, _, t)| *t)
+  }
+
+  pub fn is_int(&self) -> bool {
+    self.entry_type() == Some(EntryType::Int)
+  }
+}
+
+/// 已知配置项列表，用于从 CConfigInterface 读回配置
+pub(crate) fn known_entries() -> impl Iterator<Item = (&'static str, &'static str)> {
+  KNOWN_ENTRIES.iter().map(|(section, key, _)| (*section, *key))
+}
+
+fn invalid_config(issues: Vec<String>) -> napi::Error {
+  napi::Error::from_reason(format!("Invalid config ({} issues):\n  {}", issues.len(), issues.join("\n  ")))
+}
+
+/**
+ * 读取 JS 配置对象 `{ t2sdk: { servers, license_file, ... }, safe: { ... } }`
+ * 字符串、整数原样保留，布尔转换为 1/0；嵌套对象等其他类型报错
+ */
+pub(crate) fn entries_from_object(config: &Object) -> Result<Vec<ConfigEntry>> {
+  let mut entries = Vec::new();
+  let mut issues = Vec::new();
+  for section in Object::keys(config)? {
+    let section_obj = match config.get::<_, JsUnknown>(&section)? {
+      Some(value) if value.get_type()? == ValueType::Object => unsafe { value.cast::<Object>() },
+      _ => {
+        issues.push(format!("{}: section must be an object", section));
+        continue;
+      }
+    };
+    for key in Object::keys(&section_obj)? {
+      let path = format!("{}.{}", section, key);
+      let value = match section_obj.get::<_, JsUnknown>(&key)? {
+        Some(value) => value,
+        None => continue,
+      };
+      let value = match value.get_type()? {
+        ValueType::Undefined | ValueType::Null => continue,
+        ValueType::String => value.coerce_to_string()?.into_utf8()?.into_owned()?,
+        ValueType::Boolean => (value.coerce_to_bool()?.get_value()? as i32).to_string(),
+        ValueType::Number => {
+          let n = value.coerce_to_number()?.get_double()?;
+          if n.fract() != 0.0 || n < i32::MIN as f64 || n > i32::MAX as f64 {
+            issues.push(format!("{}: {} is not an int32", path, n));
+            continue;
+          }
+          (n as i32).to_string()
+        }
+        other => {
+          issues.push(format!("{}: unsupported value type {:?}", path, other));
+          continue;
+        }
+      };
+      entries.push(ConfigEntry {
+        section: section.clone(),
+        key,
+        value,
+      });
+    }
+  }
+  if !issues.is_empty() {
+    return Err(invalid_config(issues));
+  }
+  Ok(entries)
+}
+
+/**
+ * 解析 ini 文本，支持以 `;` 或 `#` 开头的整行注释
+ * 不识别行内注释，等号后的内容原样作为值，避免截断含 ` ;`、` #` 的密码和路径
+ */
+pub(crate) fn parse_ini(text: &str) -> Result<Vec<ConfigEntry>> {
+  let mut entries = Vec::new();
+  let mut section: Option<String> = None;
+  for (index, line) in text.lines().enumerate() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+      continue;
+    }
+    if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+      section = Some(name.trim().to_string());
+      continue;
+    }
+    let (key, value) = line
+      .split_once('=')
+      .ok_or_else(|| napi::Error::from_reason(format!("Invalid ini at line {}: expected key=value", index + 1)))?;
+    let section = section
+      .clone()
+      .ok_or_else(|| napi::Error::from_reason(format!("Invalid ini at line {}: entry outside of a section", index + 1)))?;
+    entries.push(ConfigEntry {
+      section,
+      key: key.trim().to_string(),
+      value: value.trim().to_string(),
+    });
+  }
+  Ok(entries)
+}
+
+/// 返回未知配置项的路径（section.key）
+pub(crate) fn unknown_keys(entries: &[ConfigEntry]) -> Vec<String> {
+  entries.iter().filter(|e| e.entry_type().is_none()).map(|e| e.path()).collect()
+}
+
+fn validate_servers(servers: &str, issues: &mut Vec<String>) {
+  for server in servers.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+    let valid = match server.rsplit_once(':') {
+      Some((host, port)) => !host.is_empty() && port.parse::<u16>().is_ok(),
+      None => false,
+    };
+    if !valid {
+      issues.push(format!("t2sdk.servers: {:?} is not a host:port address", server));
+    }
+  }
+}
+
+fn validate_file(path: &str, name: &str, issues: &mut Vec<String>) {
+  if !std::path::Path::new(path).is_file() {
+    issues.push(format!("{}: file {:?} does not exist", name, path));
+  }
+}
+
+/// 校验单个配置项的取值，不涉及其他配置项
+fn check_value(entry: &ConfigEntry, issues: &mut Vec<String>) {
+  if entry.value.is_empty() {
+    return;
+  }
+  if entry.is_int() {
+    match entry.value.parse::<i32>() {
+      Ok(size) if entry.section == "t2sdk" && entry.key == "send_queue_size" && size <= 0 => {
+        issues.push(format!("t2sdk.send_queue_size: {} must be positive", size));
+      }
+      Ok(_) => {}
+      Err(_) => issues.push(format!("{}: {:?} is not an integer", entry.path(), entry.value)),
+    }
+    return;
+  }
+  match (entry.section.as_str(), entry.key.as_str()) {
+    ("t2sdk", "servers") => validate_servers(&entry.value, issues),
+    ("t2sdk", "license_file") => validate_file(&entry.value, "t2sdk.license_file", issues),
+    ("safe", "safe_level") if !matches!(entry.value.as_str(), "none" | "pwd" | "ssl") => {
+      issues.push(format!("safe.safe_level: {:?} must be one of none, pwd, ssl", entry.value));
+    }
+    _ => {}
+  }
+}
+
+/// 逐项校验配置取值，不检查必填项，用于 setConfig 的增量设置
+pub(crate) fn check_entries(entries: &[ConfigEntry]) -> Result<()> {
+  let mut issues = Vec::new();
+  for entry in entries {
+    check_value(entry, &mut issues);
+  }
+  if !issues.is_empty() {
+    return Err(invalid_config(issues));
+  }
+  Ok(())
+}
+
+/// 将配置项转换为类型化配置，并校验必填项、取值和文件是否存在
+pub(crate) fn validate(entries: &[ConfigEntry]) -> Result<T2Config> {
+  let mut issues = Vec::new();
+  for entry in entries {
+    check_value(entry, &mut issues);
+  }
+  let get = |section: &str, key: &str| {
+    entries
+      .iter()
+      .rev()
+      .find(|e| e.section == section && e.key == key && !e.value.is_empty())
+      .map(|e| e.value.clone())
+  };
+  let get_int = |section: &str, key: &str| get(section, key).and_then(|value| value.parse::<i32>().ok());
+
+  let t2sdk = T2SdkSection {
+    servers: get("t2sdk", "servers").unwrap_or_default(),
+    license_file: get("t2sdk", "license_file").unwrap_or_default(),
+    license_pwd: get("t2sdk", "license_pwd"),
+    lang: get_int("t2sdk", "lang"),
+    send_queue_size: get_int("t2sdk", "send_queue_size"),
+    login_name: get("t2sdk", "login_name"),
+    init_recv_load: get_int("t2sdk", "init_recv_load"),
+    auto_reconnect: get_int("t2sdk", "auto_reconnect"),
+    heartbeat_time: get_int("t2sdk", "heartbeat_time"),
+    logdir: get("t2sdk", "logdir"),
+    writedata: get_int("t2sdk", "writedata"),
+  };
+  let safe = SafeSection {
+    safe_level: get("safe", "safe_level"),
+    client_id: get("safe", "client_id"),
+    comm_pwd: get("safe", "comm_pwd"),
+    cert_file: get("safe", "cert_file"),
+    cert_pwd: get("safe", "cert_pwd"),
+  };
+
+  if t2sdk.servers.is_empty() {
+    issues.push("t2sdk.servers: is required".to_string());
+  }
+  if t2sdk.license_file.is_empty() {
+    issues.push("t2sdk.license_file: is required".to_string());
+  }
+  if safe.safe_level.as_deref() == Some("ssl") {
+    match &safe.cert_file {
+      Some(cert_file) => validate_file(cert_file, "safe.cert_file", &mut issues),
+      None => issues.push("safe.cert_file: is required when safe_level is ssl".to_string()),
+    }
+  }
+
+  if !issues.is_empty() {
+    return Err(invalid_config(issues));
+  }
+
+  let has_safe =
+    safe.safe_level.is_some() || safe.client_id.is_some() || safe.comm_pwd.is_some() || safe.cert_file.is_some() || safe.cert_pwd.is_some();
+  Ok(T2Config {
+    t2sdk,
+    safe: if has_safe { Some(safe) } else { None },
+  })
+}
+
+/// 按默认编码读取 ini 文件
+pub(crate) fn read_ini(path: &str, encoding: TextEncoding) -> Result<Vec<ConfigEntry>> {
+  let bytes = std::fs::read(path).map_err(|e| napi::Error::from_reason(format!("Failed to read config file {}: {}", path, e)))?;
+  parse_ini(&encoding.decode(&bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn entry(section: &str, key: &str, value: &str) -> ConfigEntry {
+    ConfigEntry {
+      section: section.to_string(),
+      key: key.to_string(),
+      value: value.to_string(),
+    }
+  }
+
+  fn reason<T>(result: Result<T>) -> String {
+    match result {
+      Ok(_) => panic!("expected an error"),
+      Err(e) => e.reason,
+    }
+  }
+
+  fn license_file() -> String {
+    let path = std::env::temp_dir().join(format!("t2sdk-license-{}.dat", std::process::id()));
+    std::fs::write(&path, b"license").unwrap();
+    path.to_string_lossy().into_owned()
+  }
+
+  #[test]
+  fn parse_ini_skips_comment_lines_and_keeps_values_verbatim() {
+    let text = "; header\n[t2sdk]\nservers=127.0.0.1:9999;127.0.0.2:9999\n  # lang\nlang = 2052\n\n[safe]\ncomm_pwd=a #b ;c\ncert_file=/opt/t2 #1/cert.pfx\n";
+    let entries = parse_ini(text).unwrap();
+    let values: Vec<_> = entries.iter().map(|e| (e.path(), e.value.as_str())).collect();
+    assert_eq!(
+      values,
+      [
+        ("t2sdk.servers".to_string(), "127.0.0.1:9999;127.0.0.2:9999"),
+        ("t2sdk.lang".to_string(), "2052"),
+        ("safe.comm_pwd".to_string(), "a #b ;c"),
+        ("safe.cert_file".to_string(), "/opt/t2 #1/cert.pfx"),
+      ]
+    );
+  }
+
+  #[test]
+  fn parse_ini_rejects_malformed_lines() {
+    assert!(reason(parse_ini("servers=127.0.0.1:9999")).contains("outside of a section"));
+    assert!(reason(parse_ini("[t2sdk]\nservers")).contains("line 2"));
+  }
+
+  #[test]
+  fn check_entries_skips_required_keys() {
+    assert!(check_entries(&[entry("t2sdk", "lang", "2052")]).is_ok());
+    let error = reason(check_entries(&[
+      entry("t2sdk", "lang", "zh"),
+      entry("t2sdk", "servers", "127.0.0.1"),
+      entry("t2sdk", "send_queue_size", "0"),
+      entry("safe", "safe_level", "tls"),
+    ]));
+    assert!(error.starts_with("Invalid config (4 issues)"), "{}", error);
+  }
+
+  #[test]
+  fn validate_requires_servers_and_license() {
+    let error = reason(validate(&[entry("t2sdk", "lang", "2052")]));
+    assert!(error.contains("t2sdk.servers: is required"));
+    assert!(error.contains("t2sdk.license_file: is required"));
+
+    let error = reason(validate(&[
+      entry("t2sdk", "servers", "127.0.0.1:9999"),
+      entry("t2sdk", "license_file", "/nonexistent/license.dat"),
+      entry("safe", "safe_level", "ssl"),
+    ]));
+    assert!(error.contains("does not exist"));
+    assert!(error.contains("safe.cert_file: is required when safe_level is ssl"));
+  }
+
+  #[test]
+  fn validate_returns_typed_config() {
+    let license = license_file();
+    let config = validate(&[
+      entry("t2sdk", "servers", "127.0.0.1:9999"),
+      entry("t2sdk", "license_file", &license),
+      entry("t2sdk", "lang", "1033"),
+      entry("t2sdk", "lang", "2052"),
+      entry("t2sdk", "unknown", "1"),
+    ]);
+    std::fs::remove_file(&license).unwrap();
+    let config = config.unwrap();
+    assert_eq!(config.t2sdk.lang, Some(2052));
+    assert!(config.safe.is_none());
+    assert_eq!(unknown_keys(&[entry("t2sdk", "unknown", "1")]), ["t2sdk.unknown"]);
+  }
+}
@@ -1,26 +1,39 @@
 use dashmap::DashMap;
-use encoding::Encoding;
 use napi::Result;
 use std::any::Any;
 use std::ffi::{c_void, CStr, CString};
+use std::future::Future;
 use std::os::raw::{c_char, c_int, c_ulong};
 use std::sync::atomic::{AtomicI32, Ordering};
-use std::sync::OnceLock;
+use std::sync::{Arc, Mutex, OnceLock, RwLock, RwLockReadGuard};
+use std::time::{Duration, Instant};
 
+use crate::charset::TextEncoding;
 use crate::config::CConfigInterfaceRust;
+use crate::def::REQUEST_PACKET;
+use crate::handles::{Handle, HandleKind};
 use crate::iknown::{IKnown, IKnownVTable};
+use crate::log::{log, LogLevel, Target};
+use crate::metrics::Metrics;
 
-use crate::biz_message::{IBizMessage, IBizMessageRust, RouteInfo};
+use crate::biz_message::{BizMessage, IBizMessage, IBizMessageRust, RouteInfo};
 use crate::callback::{create_callback, CCallbackRust};
 use crate::f2_packer::IF2Packer;
-use crate::f2_unpacker::IF2UnPackerRust;
+use crate::f2_unpacker::{IF2UnPackerRust, UnPacker};
+use crate::file_update::CFileUpdateRust;
+use crate::subscribe::CSubscribeRust;
+use crate::topic::{decode_topics, TopicInfo};
+use crate::trace::Trace;
+use crate::unsolicited::{classify, UnsolicitedReason};
 
+#[allow(dead_code)]
 pub enum RecvBizResult {
   UnPacker(IF2UnPackerRust), // 成功情况下的解包器
   ErrorString(String),       // 错误信息字符串
   None,                      // 对应解包失败的情况
 }
 
+#[allow(dead_code)]
 #[repr(u32)]
 pub enum ConnectionStatus {
   Disconnected = 0x0000,   // 未连接
@@ -47,11 +60,13 @@ pub struct ReqData {
   company_id: i32,
 }
 
+#[allow(dead_code)]
 #[repr(u32)]
 pub enum RecvFlags {
   JustRemoveHandle = 0x0001,
 }
 
+#[allow(dead_code)]
 pub(crate) trait CConnectionInterface: IKnown {
   // 初始化连接对象
   unsafe fn create(&mut self, callback: Option<*mut c_void>) -> i32;
@@ -72,13 +87,14 @@ pub(crate) trait CConnectionInterface: IKnown {
   // 获取服务器负载
   unsafe fn get_server_load(&self) -> i32;
   // 获取错误信息
-  unsafe fn get_error_msg(&self, error_code: i32) -> String;
+  unsafe fn get_error_msg(&self, error_code: i32) -> *const c_char;
   // 获取连接错误号
   unsafe fn get_connect_error(&self) -> i32;
   // 发送业务数据
   unsafe fn send_biz(&mut self, fun_id: i32, packer: &dyn IF2Packer, asy: i32, system_no: i32, compress_id: i32) -> i32;
   // 接收业务数据
   unsafe fn recv_biz(&mut self, handle: i32, unpacker_or_str: &mut Option<Box<dyn Any>>, timeout: u32, flag: u32) -> i32;
+  #[allow(clippy::too_many_arguments)]
   unsafe fn send_biz_ex(
     &self,
     fun_id: i32,
@@ -90,6 +106,7 @@ pub(crate) trait CConnectionInterface: IKnown {
     branch_no: i32,
     request: Option<&ReqData>,
   ) -> i32;
+  #[allow(clippy::too_many_arguments)]
   unsafe fn send_biz_ex2(
     &self,
     fun_id: i32,
@@ -115,17 +132,17 @@ pub(crate) trait CConnectionInterface: IKnown {
   unsafe fn new_subscriber(
     &self,
     callback: *mut c_void,
-    subscribe_name: &str,
+    subscribe_name: *const c_char,
     timeout: i32,
     init_recv_q_len: i32,
     step_recv_q_len: i32,
-  ) -> i32;
+  ) -> *mut CSubscribeRust;
   // virtual CPublishInterface* FUNCTION_CALL_MODE NewPublisher(char* PublishName,int msgCount,int iTimeOut,bool bResetNo = false) = 0;
   unsafe fn new_publisher(&self, publish_name: &str, msg_count: i32, timeout: i32, reset_no: bool) -> i32;
   // virtual IF2UnPacker* FUNCTION_CALL_MODE GetTopic(bool byForce,int iTimeOut) = 0;
-  unsafe fn get_topic(&self, by_force: bool, timeout: i32) -> i32;
+  unsafe fn get_topic(&self, by_force: bool, timeout: i32) -> *mut IF2UnPackerRust;
   // virtual const char* FUNCTION_CALL_MODE GetMCLastError() = 0;
-  unsafe fn get_mc_last_error(&self) -> &str;
+  unsafe fn get_mc_last_error(&self) -> *const c_char;
   // virtual int FUNCTION_CALL_MODE Create2BizMsg(CCallbackRust *lpCallback) = 0;
   unsafe fn create_2_biz_msg(&self, callback: *mut CCallbackRust) -> i32;
   // virtual int FUNCTION_CALL_MODE SendBizMsg(IBizMessage* lpMsg,int nAsy = 0) = 0;
@@ -135,15 +152,15 @@ pub(crate) trait CConnectionInterface: IKnown {
   // virtual CFileUpdateInterface* FUNCTION_CALL_MODE NewFileUpdate(const char* szTopicName,CFileUpdateCallbackInterface* lpCallBack ,const char* szScanDir,const char* szUpdateDir,unsigned int uiTimeOut = 5000, const char * szDirFilter = NULL) = 0;
   unsafe fn new_file_update(
     &self,
-    topic_name: &str,
+    topic_name: *const c_char,
     callback: *mut c_void,
-    scan_dir: &str,
-    update_dir: &str,
+    scan_dir: *const c_char,
+    update_dir: *const c_char,
     timeout: u32,
-    dir_filter: Option<&str>,
-  ) -> i32;
+    dir_filter: *const c_char,
+  ) -> *mut CFileUpdateRust;
   // virtual const char* FUNCTION_CALL_MODE GetFileUpdateLastError() = 0;
-  unsafe fn get_file_update_last_error(&self) -> &str;
+  unsafe fn get_file_update_last_error(&self) -> *const c_char;
   // virtual const char * FUNCTION_CALL_MODE GetLastAnsError(bool bAsyError = 0) = 0;
   unsafe fn get_last_ans_error(&self, asy_error: bool) -> &str;
   // virtual CSubscribeInterface* FUNCTION_CALL_MODE NewSubscriberEx(CSubCallbackInterface *lpCallback,char* SubScribeName,SUB_ROUTER_INFO &subRoterInfo,int iTimeOut,
@@ -182,6 +199,7 @@ pub(crate) trait CConnectionInterface: IKnown {
 }
 
 #[repr(C)]
+#[allow(clippy::type_complexity)]
 pub(crate) struct VTable {
   pub iknown: IKnownVTable,
   create: unsafe fn(this: *mut c_void, callback: *const c_void) -> c_int,
@@ -237,9 +255,9 @@ pub(crate) struct VTable {
     timeout: c_int,
     init_recv_q_len: c_int,
     step_recv_q_len: c_int,
-  ) -> c_int,
+  ) -> *mut CSubscribeRust,
   new_publisher: unsafe fn(this: *const c_void, publish_name: *const c_char, msg_count: c_int, timeout: c_int, reset_no: c_int) -> c_int,
-  get_topic: unsafe fn(this: *const c_void, by_force: c_int, timeout: c_int) -> c_int,
+  get_topic: unsafe fn(this: *const c_void, by_force: c_int, timeout: c_int) -> *mut IF2UnPackerRust,
   get_mc_last_error: unsafe fn(this: *const c_void) -> *const c_char,
   create_2_biz_msg: unsafe fn(this: *const c_void, callback: *mut CCallbackRust) -> c_int,
   send_biz_msg: unsafe fn(this: *const c_void, msg: *const c_void, asy: c_int) -> c_int,
@@ -252,7 +270,7 @@ pub(crate) struct VTable {
     update_dir: *const c_char,
     timeout: u32,
     dir_filter: *const c_char,
-  ) -> c_int,
+  ) -> *mut CFileUpdateRust,
   get_file_update_last_error: unsafe fn(this: *const c_void) -> *const c_char,
   get_last_ans_error: unsafe fn(this: *const c_void, asy_error: c_int) -> *const c_char,
   new_subscriber_ex: unsafe fn(
@@ -345,14 +363,8 @@ impl CConnectionInterface for IConnectionRust {
   unsafe fn get_server_load(&self) -> i32 {
     ((*self.vtable).get_server_load)(self as *const _ as *mut c_void)
   }
-  unsafe fn get_error_msg(&self, error_code: i32) -> String {
-    let c_str = CStr::from_ptr(((*self.vtable).get_error_msg)(self as *const _ as *mut c_void, error_code));
-    let bytes = c_str.to_bytes();
-
-    match std::str::from_utf8(bytes) {
-      Ok(s) => s.to_string(),
-      Err(_) => encoding::all::GBK.decode(bytes, encoding::DecoderTrap::Strict).unwrap(),
-    }
+  unsafe fn get_error_msg(&self, error_code: i32) -> *const c_char {
+    ((*self.vtable).get_error_msg)(self as *const _ as *mut c_void, error_code)
   }
   unsafe fn get_connect_error(&self) -> i32 {
     ((*self.vtable).get_connect_error)(self as *const _ as *mut c_void)
@@ -460,15 +472,15 @@ impl CConnectionInterface for IConnectionRust {
   unsafe fn new_subscriber(
     &self,
     callback: *mut c_void,
-    subscribe_name: &str,
+    subscribe_name: *const c_char,
     timeout: i32,
     init_recv_q_len: i32,
     step_recv_q_len: i32,
-  ) -> i32 {
+  ) -> *mut CSubscribeRust {
     ((*self.vtable).new_subscriber)(
       self as *const _ as *mut c_void,
-      &callback as *const _ as *const c_void,
-      CString::new(subscribe_name).unwrap().as_ptr(),
+      callback as *const c_void,
+      subscribe_name,
       timeout,
       init_recv_q_len,
       step_recv_q_len,
@@ -484,13 +496,11 @@ impl CConnectionInterface for IConnectionRust {
       reset_no as i32,
     )
   }
-  unsafe fn get_topic(&self, by_force: bool, timeout: i32) -> i32 {
+  unsafe fn get_topic(&self, by_force: bool, timeout: i32) -> *mut IF2UnPackerRust {
     ((*self.vtable).get_topic)(self as *const _ as *mut c_void, by_force as i32, timeout)
   }
-  unsafe fn get_mc_last_error(&self) -> &str {
-    CStr::from_ptr(((*self.vtable).get_mc_last_error)(self as *const _ as *mut c_void))
-      .to_str()
-      .unwrap()
+  unsafe fn get_mc_last_error(&self) -> *const c_char {
+    ((*self.vtable).get_mc_last_error)(self as *const _ as *mut c_void)
   }
   unsafe fn create_2_biz_msg(&self, callback: *mut CCallbackRust) -> i32 {
     ((*self.vtable).create_2_biz_msg)(self as *const _ as *mut c_void, callback)
@@ -510,27 +520,25 @@ impl CConnectionInterface for IConnectionRust {
   }
   unsafe fn new_file_update(
     &self,
-    topic_name: &str,
+    topic_name: *const c_char,
     callback: *mut c_void,
-    scan_dir: &str,
-    update_dir: &str,
+    scan_dir: *const c_char,
+    update_dir: *const c_char,
     timeout: u32,
-    dir_filter: Option<&str>,
-  ) -> i32 {
+    dir_filter: *const c_char,
+  ) -> *mut CFileUpdateRust {
     ((*self.vtable).new_file_update)(
       self as *const _ as *mut c_void,
-      CString::new(topic_name).unwrap().as_ptr(),
-      &callback as *const _ as *const c_void,
-      CString::new(scan_dir).unwrap().as_ptr(),
-      CString::new(update_dir).unwrap().as_ptr(),
+      topic_name,
+      callback as *const c_void,
+      scan_dir,
+      update_dir,
       timeout,
-      dir_filter.map(|d| CString::new(d).unwrap().as_ptr()).unwrap_or(std::ptr::null()),
+      dir_filter,
     )
   }
-  unsafe fn get_file_update_last_error(&self) -> &str {
-    CStr::from_ptr(((*self.vtable).get_file_update_last_error)(self as *const _ as *mut c_void))
-      .to_str()
-      .unwrap()
+  unsafe fn get_file_update_last_error(&self) -> *const c_char {
+    ((*self.vtable).get_file_update_last_error)(self as *const _ as *mut c_void)
   }
   unsafe fn get_last_ans_error(&self, asy_error: bool) -> &str {
     CStr::from_ptr(((*self.vtable).get_last_ans_error)(
@@ -598,38 +606,198 @@ impl CConnectionInterface for IConnectionRust {
   }
 }
 
-type MessageCallback = Box<dyn FnOnce(*mut IBizMessageRust) + Send>;
+type MessageCallback = Box<dyn FnOnce(*mut IBizMessageRust) + Send + Sync>;
+/// 连接上没有请求回调的消息，在 SDK 回调线程中调用
+pub(crate) type UnsolicitedHandler = Box<dyn Fn(*mut IConnectionRust, *mut IBizMessageRust, c_int, UnsolicitedReason) + Send + Sync>;
+
+// 超时句柄的保留时间，超过后迟到的应答按 unknown 处理
+const EXPIRED_RETENTION: Duration = Duration::from_secs(600);
 
 pub struct CallbackRegistry {
   callbacks: DashMap<(i32, c_int), MessageCallback>,
+  handlers: DashMap<i32, UnsolicitedHandler>,
+  // 已超时的请求，用于识别迟到的应答
+  expired: DashMap<(i32, c_int), Instant>,
+  // 需要跟踪的请求，在 OnSent 时记录发出时间
+  traces: DashMap<(i32, c_int), Arc<Trace>>,
+  // 每个连接的发送锁，发送和登记回调在锁内完成
+  sending: DashMap<i32, Arc<Mutex<()>>>,
 }
 
 impl CallbackRegistry {
   pub fn instance() -> &'static Self {
-    static mut REGISTRY: OnceLock<CallbackRegistry> = OnceLock::new();
-    unsafe { REGISTRY.get_or_init(|| CallbackRegistry { callbacks: DashMap::new() }) }
+    static REGISTRY: OnceLock<CallbackRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(|| CallbackRegistry {
+      callbacks: DashMap::new(),
+      handlers: DashMap::new(),
+      expired: DashMap::new(),
+      traces: DashMap::new(),
+      sending: DashMap::new(),
+    })
+  }
+
+  /**
+   * 在连接的发送锁内发送请求并登记应答回调和跟踪记录，返回 hSend
+   * 应答可能在 send 返回前就到达 SDK 回调线程，回调线程查不到回调时会等待这把锁后再查一次
+   * 跟踪记录先于回调写入，避免应答先到达时遗留跟踪记录
+   */
+  pub(crate) fn send(&self, sequence: i32, send: impl FnOnce() -> c_int, callback: MessageCallback, trace: Option<&Arc<Trace>>) -> c_int {
+    let lock = self.sending.entry(sequence).or_default().clone();
+    let _sending = lock.lock().unwrap();
+    let msg_id = send();
+    if msg_id > 0 {
+      if let Some(trace) = trace {
+        trace.span("enqueue");
+        trace.set_h_send(msg_id);
+        self.traces.insert((sequence, msg_id), trace.clone());
+      }
+      self.callbacks.insert((sequence, msg_id), callback);
+    }
+    msg_id
   }
 
-  pub fn register(&self, sequence: i32, msg_id: i32, callback: MessageCallback) {
-    self.callbacks.insert((sequence, msg_id), callback);
+  /// 等待连接上进行中的发送登记完成，SDK 在自己的线程上回调，不会在发送线程内重入
+  fn wait_sending(&self, sequence: i32) {
+    let lock = self.sending.get(&sequence).map(|lock| lock.clone());
+    if let Some(lock) = lock {
+      drop(lock.lock().unwrap());
+    }
   }
 
-  pub fn invoke_callback(&self, key: (i32, c_int), msg_ptr: *mut IBizMessageRust) {
+  /// 取出应答回调，查不到时等待进行中的发送完成登记后再查一次
+  fn take_callback(&self, key: (i32, c_int)) -> Option<MessageCallback> {
     if let Some((_, callback)) = self.callbacks.remove(&key) {
-      callback(msg_ptr);
+      return Some(callback);
+    }
+    self.wait_sending(key.0);
+    self.callbacks.remove(&key).map(|(_, callback)| callback)
+  }
+
+  /// SDK 发出请求时调用，OnSent 可能先于跟踪记录登记到达，查不到时等待进行中的发送完成登记
+  pub fn on_sent(&self, key: (i32, c_int)) {
+    if !self.traces.contains_key(&key) {
+      self.wait_sending(key.0);
+    }
+    if let Some(trace) = self.traces.get(&key) {
+      trace.span("sent");
+    }
+  }
+
+  /// 设置连接的未匹配消息处理函数
+  pub(crate) fn set_handler(&self, sequence: i32, handler: UnsolicitedHandler) {
+    self.handlers.insert(sequence, handler);
+  }
+
+  /// 请求超时后移除回调，之后收到的应答标记为 late
+  pub fn expire(&self, sequence: i32, msg_id: i32) {
+    self.traces.remove(&(sequence, msg_id));
+    if self.callbacks.remove(&(sequence, msg_id)).is_none() {
+      return;
+    }
+    let now = Instant::now();
+    self.expired.retain(|_, at| now.duration_since(*at) < EXPIRED_RETENTION);
+    self.expired.insert((sequence, msg_id), now);
+  }
+
+  /// 移除连接的所有回调
+  pub fn unregister(&self, sequence: i32) {
+    self.callbacks.retain(|(s, _), _| *s != sequence);
+    self.expired.retain(|(s, _), _| *s != sequence);
+    self.traces.retain(|(s, _), _| *s != sequence);
+    self.handlers.remove(&sequence);
+    self.sending.remove(&sequence);
+  }
+
+  pub fn invoke_callback(&self, key: (i32, c_int), connection: *mut IConnectionRust, msg_ptr: *mut IBizMessageRust) {
+    // 服务端发来的请求不是本连接请求的应答
+    let is_request = unsafe { (*msg_ptr).get_packet_type() } == REQUEST_PACKET;
+    if !is_request {
+      if let Some(callback) = self.take_callback(key) {
+        self.traces.remove(&key);
+        callback(msg_ptr);
+        return;
+      }
+    }
+    let reason = match self.expired.remove(&key) {
+      Some(_) => UnsolicitedReason::Late,
+      None => classify(msg_ptr),
+    };
+    log(LogLevel::Debug, Target::Callback, || {
+      format!(
+        "Connection {} received unmatched message, hSend {}, function {}, reason {}",
+        key.0,
+        key.1,
+        unsafe { (*msg_ptr).get_function() },
+        reason.as_str()
+      )
+    });
+    if let Some(handler) = self.handlers.get(&key.0) {
+      handler(connection, msg_ptr, key.1, reason);
     }
   }
 }
 
 static SEQUENCE_COUNTER: AtomicI32 = AtomicI32::new(0);
 
+/// 连接是否仍未释放，供订阅者等由连接创建的对象共享
+#[derive(Clone)]
+pub(crate) struct ConnectionAlive(Arc<RwLock<bool>>);
+
+impl ConnectionAlive {
+  fn new() -> Self {
+    ConnectionAlive(Arc::new(RwLock::new(true)))
+  }
+
+  pub(crate) fn is_open(&self) -> bool {
+    *self.0.read().unwrap()
+  }
+
+  /// 连接未释放时返回读锁，持有期间 Connection 的释放会等待，可安全调用由连接创建的 SDK 对象
+  pub(crate) fn guard(&self) -> Result<RwLockReadGuard<'_, bool>> {
+    let guard = self.0.read().unwrap();
+    if !*guard {
+      return Err(napi::Error::from_reason("Connection closed"));
+    }
+    Ok(guard)
+  }
+
+  fn close(&self) {
+    *self.0.write().unwrap() = false;
+  }
+}
+
+// 在阻塞线程池中调用 SDK 时使用的连接指针，调用方需持有 ConnectionAlive 的读锁
+struct RawConnection(*mut IConnectionRust);
+
+unsafe impl Send for RawConnection {}
+
+impl RawConnection {
+  fn get_topics(&self, force: bool, timeout: i32, encoding: TextEncoding) -> Result<Vec<TopicInfo>> {
+    let ptr = unsafe { (*self.0).get_topic(force, timeout) };
+    if ptr.is_null() {
+      let error = unsafe { encoding.decode_c_str_lossy((*self.0).get_mc_last_error()) };
+      return Err(napi::Error::from_reason(format!("Failed to get topics: {}", error)));
+    }
+    // GetTopic 返回的解包器需由调用方释放
+    let mut unpacker = UnPacker::from_owned_ptr(ptr);
+    unpacker.set_encoding(encoding);
+    unpacker.set_coerce(true);
+    decode_topics(&unpacker)
+  }
+}
+
 pub struct Connection {
   ptr: *mut IConnectionRust,
   sequence: i32,
+  alive: ConnectionAlive,
+  // GetTopic 的结果，随连接一起释放
+  topics: Arc<Mutex<Option<Vec<TopicInfo>>>>,
+  metrics: Arc<Metrics>,
+  handle: Handle,
 }
 
 impl Connection {
-  pub fn new(config: *mut CConfigInterfaceRust) -> Result<Self> {
+  pub(crate) fn new(config: *mut CConfigInterfaceRust, metrics: Arc<Metrics>) -> Result<Self> {
     let lib = crate::get_library()?;
     let sequence = SEQUENCE_COUNTER.fetch_add(1, Ordering::SeqCst);
 
@@ -638,7 +806,14 @@ impl Connection {
       if ptr.is_null() {
         return Err(napi::Error::from_reason("Failed to create config instance"));
       }
-      Ok(Connection { ptr, sequence })
+      Ok(Connection {
+        ptr,
+        sequence,
+        alive: ConnectionAlive::new(),
+        topics: Arc::new(Mutex::new(None)),
+        metrics,
+        handle: Handle::owned(HandleKind::Connection),
+      })
     }
   }
 
@@ -646,17 +821,126 @@ impl Connection {
     unsafe {
       let conn = &mut *self.ptr;
       let callback = Box::into_raw(create_callback(self.sequence));
-      let result = conn.create_2_biz_msg(callback);
-      Ok(conn.connect(timeout))
+      let ret = conn.create_2_biz_msg(callback);
+      if ret != 0 {
+        log(LogLevel::Error, Target::Connect, || {
+          format!("Connection {} Create2BizMsg failed: {}", self.sequence, ret)
+        });
+        return Ok(ret);
+      }
+      log(LogLevel::Info, Target::Connect, || {
+        format!("Connection {} connecting, timeout {}ms", self.sequence, timeout)
+      });
+      let ret = conn.connect(timeout);
+      if ret == 0 {
+        log(LogLevel::Info, Target::Connect, || {
+          format!("Connection {} connected", self.sequence)
+        });
+      } else {
+        log(LogLevel::Warn, Target::Connect, || {
+          format!("Connection {} connect failed: {}", self.sequence, ret)
+        });
+      }
+      Ok(ret)
     }
   }
 
   // GetErrorMsg
-  pub fn get_error_msg(&self, error_code: i32) -> Result<String> {
+  pub fn get_error_msg(&self, error_code: i32, encoding: TextEncoding) -> String {
+    unsafe {
+      let conn = &*self.ptr;
+      encoding.decode_c_str_lossy(conn.get_error_msg(error_code))
+    }
+  }
+
+  /**
+   * 创建文件更新对象，callback 需在返回对象释放前一直有效
+   * 失败时返回 GetFileUpdateLastError 的错误信息
+   */
+  #[allow(clippy::too_many_arguments)]
+  pub(crate) fn new_file_update(
+    &self,
+    topic_name: &str,
+    callback: *mut c_void,
+    scan_dir: &str,
+    update_dir: &str,
+    timeout: u32,
+    dir_filter: Option<&str>,
+    encoding: TextEncoding,
+  ) -> Result<*mut CFileUpdateRust> {
+    let topic_name = encoding.to_c_string(topic_name, "topic name")?;
+    let scan_dir = encoding.to_c_string(scan_dir, "scan dir")?;
+    let update_dir = encoding.to_c_string(update_dir, "update dir")?;
+    let dir_filter = dir_filter.map(|d| encoding.to_c_string(d, "dir filter")).transpose()?;
     unsafe {
       let conn = &*self.ptr;
-      let msg = conn.get_error_msg(error_code);
-      Ok(msg.to_string())
+      let ptr = conn.new_file_update(
+        topic_name.as_ptr(),
+        callback,
+        scan_dir.as_ptr(),
+        update_dir.as_ptr(),
+        timeout,
+        dir_filter.as_ref().map(|d| d.as_ptr()).unwrap_or(std::ptr::null()),
+      );
+      if ptr.is_null() {
+        let error = encoding.decode_c_str_lossy(conn.get_file_update_last_error());
+        return Err(napi::Error::from_reason(format!("Failed to create file updater: {}", error)));
+      }
+      Ok(ptr)
+    }
+  }
+
+  /**
+   * 获取服务端的所有主题信息，force 为 false 时优先使用缓存，返回的 future 不借用连接
+   * GetTopic 同步等待应答，在阻塞线程池中执行，期间持有连接的读锁；缓存只在读写时加锁
+   */
+  pub(crate) fn get_topics(
+    &self,
+    force: bool,
+    timeout: i32,
+    encoding: TextEncoding,
+  ) -> impl Future<Output = Result<Vec<TopicInfo>>> + Send + 'static {
+    let cached = if force { None } else { self.topics.lock().unwrap().clone() };
+    let conn = RawConnection(self.ptr);
+    let alive = self.alive.clone();
+    let cache = self.topics.clone();
+    async move {
+      if let Some(topics) = cached {
+        return Ok(topics);
+      }
+      tokio::task::spawn_blocking(move || {
+        let _alive = alive.guard()?;
+        let topics = conn.get_topics(force, timeout, encoding)?;
+        *cache.lock().unwrap() = Some(topics.clone());
+        Ok(topics)
+      })
+      .await
+      .map_err(|e| napi::Error::from_reason(format!("Task join error: {}", e)))?
+    }
+  }
+
+  /**
+   * 创建订阅者，callback 需在返回对象释放前一直有效
+   * 失败时返回 GetMCLastError 的错误信息
+   */
+  pub(crate) fn new_subscriber(
+    &self,
+    callback: *mut c_void,
+    subscribe_name: &str,
+    timeout: i32,
+    init_recv_q_len: i32,
+    step_recv_q_len: i32,
+    encoding: TextEncoding,
+  ) -> Result<*mut CSubscribeRust> {
+    let subscribe_name = encoding.to_c_string(subscribe_name, "subscribe name")?;
+    unsafe {
+      let conn = &*self.ptr;
+      let ptr = conn.new_subscriber(callback, subscribe_name.as_ptr(), timeout, init_recv_q_len, step_recv_q_len);
+      if ptr.is_null() {
+        let error = encoding.decode_c_str_lossy(conn.get_mc_last_error());
+        return Err(napi::Error::from_reason(format!("Failed to create subscriber: {}", error)));
+      }
+      Ok(ptr)
     }
   }
 
@@ -667,23 +951,125 @@ impl Connection {
     }
   }
 
-  pub fn send_biz_msg<F>(&self, message: *const IBizMessageRust, callback: F) -> Result<i32>
+  /// 异步发送消息，发送和登记应答回调、跟踪记录在连接的发送锁内完成
+  pub(crate) fn send_biz_msg<F>(&self, message: *const IBizMessageRust, trace: Option<&Arc<Trace>>, callback: F) -> Result<i32>
   where
-    F: FnOnce(*mut IBizMessageRust) + Send + 'static,
+    F: FnOnce(*mut IBizMessageRust) + Send + Sync + 'static,
   {
     unsafe {
       let conn = &*self.ptr;
-      let msg_id = conn.send_biz_msg(message, 1);
+      let registry = CallbackRegistry::instance();
+      let msg_id = registry.send(self.sequence, || conn.send_biz_msg(message, 1), Box::new(callback), trace);
       if msg_id > 0 {
-        let registry = CallbackRegistry::instance();
-        registry.register(self.sequence, msg_id, Box::new(callback));
+        log(LogLevel::Debug, Target::Send, || {
+          format!(
+            "Connection {} sent function {}, hSend {}",
+            self.sequence,
+            (*message).get_function(),
+            msg_id
+          )
+        });
         Ok(msg_id)
       } else {
+        log(LogLevel::Warn, Target::Send, || {
+          format!(
+            "Connection {} failed to send function {}: {}",
+            self.sequence,
+            (*message).get_function(),
+            msg_id
+          )
+        });
         Err(napi::Error::from_reason("Failed to send message"))
       }
     }
   }
 
+  /**
+   * 异步发送请求，返回等待应答的 future，不借用连接
+   * 超时后放弃等待，之后收到的应答交给未匹配消息的处理函数
+   */
+  pub(crate) fn call(
+    &self,
+    message: *const IBizMessageRust,
+    encoding: TextEncoding,
+    timeout: Duration,
+    trace: Option<Arc<Trace>>,
+  ) -> Result<impl Future<Output = Result<BizMessage>> + Send + 'static> {
+    let (tx, rx) = tokio::sync::oneshot::channel::<BizMessage>();
+    let function_no = unsafe { (*message).get_function() };
+    let started = Instant::now();
+    let metrics = self.metrics.clone();
+    metrics.started(function_no);
+    let answer_metrics = metrics.clone();
+    let answer_trace = trace.clone();
+    let msg_id = self
+      .send_biz_msg(message, trace.as_ref(), move |response_msg| {
+        let message = BizMessage::new_form_ptr(response_msg, encoding);
+        let return_code = message.get_return_code().unwrap_or_default();
+        log(LogLevel::Debug, Target::Receive, || {
+          format!(
+            "Function {} answered in {:?}, return code {}",
+            function_no,
+            started.elapsed(),
+            return_code
+          )
+        });
+        answer_metrics.answered(function_no, started.elapsed(), return_code);
+        if let Some(trace) = answer_trace {
+          trace.span("answered");
+          trace.finish("ok", Some(&message));
+        }
+        let _ = tx.send(message);
+      })
+      .inspect_err(|_| {
+        metrics.send_failed(function_no);
+        if let Some(trace) = &trace {
+          trace.span("enqueue");
+          trace.finish("sendFailed", None);
+        }
+      })?;
+    metrics.sent(function_no);
+    let sequence = self.sequence;
+    Ok(async move {
+      match tokio::time::timeout(timeout, rx).await {
+        Ok(Ok(message)) => Ok(message),
+        Ok(Err(_)) => Err(napi::Error::from_reason("Callback channel closed".to_string())),
+        Err(_) => {
+          log(LogLevel::Warn, Target::Send, || {
+            format!(
+              "Connection {} function {} hSend {} timed out after {:?}",
+              sequence, function_no, msg_id, timeout
+            )
+          });
+          metrics.timed_out(function_no);
+          CallbackRegistry::instance().expire(sequence, msg_id);
+          if let Some(trace) = trace {
+            trace.finish("timeout", None);
+          }
+          Err(napi::Error::from_reason("Callback timeout".to_string()))
+        }
+      }
+    })
+  }
+
+  pub fn sequence(&self) -> i32 {
+    self.sequence
+  }
+
+  pub(crate) fn alive(&self) -> ConnectionAlive {
+    self.alive.clone()
+  }
+
+  /// 连接状态，见 ConnectionStatus
+  pub fn get_status(&self) -> i32 {
+    unsafe { (*self.ptr).get_status() }
+  }
+
+  /// 设置未匹配到请求的消息的处理函数，随连接释放而移除
+  pub(crate) fn set_unsolicited_handler(&self, handler: UnsolicitedHandler) {
+    CallbackRegistry::instance().set_handler(self.sequence, handler);
+  }
+
   // return Ok(0);
 }
 
@@ -693,8 +1079,10 @@ unsafe impl Sync for Connection {}
 impl Drop for Connection {
   fn drop(&mut self) {
     unsafe {
-      if !self.ptr.is_null() {
-        println!("Connection Release");
+      if !self.ptr.is_null() && self.handle.is_owned() {
+        log(LogLevel::Debug, Target::Drop, || format!("Connection {} release", self.sequence));
+        self.alive.close();
+        CallbackRegistry::instance().unregister(self.sequence);
         let conn = &*self.ptr;
         conn.release();
         self.ptr = std::ptr::null_mut();
@@ -702,3 +1090,36 @@ impl Drop for Connection {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::atomic::AtomicBool;
+
+  #[test]
+  fn answer_before_registration_reaches_callback() {
+    let registry = CallbackRegistry::instance();
+    let sequence = -100;
+    let called = Arc::new(AtomicBool::new(false));
+    let flag = called.clone();
+    let mut receiver = None;
+    let msg_id = registry.send(
+      sequence,
+      || {
+        // 模拟应答在 SendBizMsg 返回前到达 SDK 回调线程
+        receiver = Some(std::thread::spawn(move || {
+          CallbackRegistry::instance().take_callback((sequence, 7))
+        }));
+        std::thread::sleep(Duration::from_millis(50));
+        7
+      },
+      Box::new(move |_| flag.store(true, Ordering::SeqCst)),
+      None,
+    );
+    assert_eq!(msg_id, 7);
+    let callback = receiver.unwrap().join().unwrap().expect("callback registered");
+    callback(std::ptr::null_mut());
+    assert!(called.load(Ordering::SeqCst));
+    registry.unregister(sequence);
+  }
+}
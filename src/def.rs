@@ -27,3 +27,13 @@ pub const REQUEST_PACKET: i32 = 0; // 请求
 pub const ANSWER_PACKET: i32 = 1; // 应答
 
 pub const PACKER_VERSION_V2: i32 = 0x20; // 打包器版本号
+
+// 消息中心 1.0 功能号
+#[napi]
+pub const MSGCENTER_FUNC_HEART: i32 = 620000; // 心跳
+#[napi]
+pub const MSGCENTER_FUNC_REG: i32 = 620001; // 订阅
+#[napi]
+pub const MSGCENTER_FUNC_REG_CANCEL: i32 = 620002; // 取消订阅
+#[napi]
+pub const MSGCENTER_FUNC_SENDED: i32 = 620003; // 推送
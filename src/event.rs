@@ -0,0 +1,42 @@
+use napi::bindgen_prelude::ToNapiValue;
+use napi::threadsafe_function::{ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::{Env, JsFunction, Result};
+use std::sync::Mutex;
+
+/**
+ * 某一事件的 JS 监听器列表，可在 SDK 回调线程中派发
+ * 监听器不会阻止 Node 进程退出
+ */
+pub(crate) struct Listeners<T: 'static> {
+  handlers: Mutex<Vec<ThreadsafeFunction<T, ErrorStrategy::Fatal>>>,
+}
+
+impl<T: ToNapiValue + Clone + Send + 'static> Listeners<T> {
+  pub fn new() -> Self {
+    Listeners {
+      handlers: Mutex::new(Vec::new()),
+    }
+  }
+
+  pub fn add(&self, env: &Env, callback: JsFunction) -> Result<()> {
+    let mut handler: ThreadsafeFunction<T, ErrorStrategy::Fatal> =
+      callback.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<T>| Ok(vec![ctx.value]))?;
+    handler.unref(env)?;
+    self.handlers.lock().unwrap().push(handler);
+    Ok(())
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.handlers.lock().unwrap().is_empty()
+  }
+
+  pub fn clear(&self) {
+    self.handlers.lock().unwrap().clear();
+  }
+
+  pub fn emit(&self, value: T) {
+    for handler in self.handlers.lock().unwrap().iter() {
+      handler.call(value.clone(), ThreadsafeFunctionCallMode::NonBlocking);
+    }
+  }
+}
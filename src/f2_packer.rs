@@ -3,9 +3,14 @@ use napi::{bindgen_prelude::Buffer, Result};
 use napi_derive::napi;
 use std::ffi::{c_void, CString};
 use std::os::raw::{c_char, c_int, c_ulong};
+use std::sync::Mutex;
 
+use crate::charset::TextEncoding;
+use crate::def::PACKER_VERSION_V2;
 use crate::f2_unpacker::{IF2UnPackerRust, UnPacker};
+use crate::handles::{Handle, HandleKind};
 use crate::iknown::{IKnown, IKnownVTable};
+use crate::pack::{self, FieldDef, PackInput};
 
 pub(crate) trait IF2Packer: IKnown {
   unsafe fn set_buffer(&self, p_buf: *mut c_void, i_buf_size: c_int, i_data_len: c_int);
@@ -146,6 +151,35 @@ impl IF2Packer for IF2PackerRust {
 #[napi]
 pub struct Packer {
   packer_ptr: *mut IF2PackerRust,
+  encoding: TextEncoding,
+  handle: Handle,
+  // set_buffer 设置的缓冲区，SDK 直接写入，在打包器之后释放
+  buffer: Option<Buffer>,
+  // 当前结果集已添加的字段，供 append_rows 使用
+  fields: Mutex<Vec<FieldDef>>,
+}
+
+#[napi(object)]
+pub struct FromRowsOptions {
+  /** 打包器版本，默认 0x20 */
+  pub version: Option<i32>,
+  /** 结果集名，设置 datasetName 或 returnCode 时调用 newDataset */
+  pub dataset_name: Option<String>,
+  pub return_code: Option<i32>,
+  /** 字符串和字段名使用的编码 */
+  pub encoding: Option<TextEncoding>,
+  /** 严格模式，有损或不支持的值直接报错，默认 false */
+  pub strict: Option<bool>,
+  /** 声明为 'D' 的字段接受十进制字符串，按字段的 scale 打包，最多 15 位有效数字，默认 false */
+  pub decimal: Option<bool>,
+}
+
+#[napi(object)]
+pub struct AppendRowsOptions {
+  /** 严格模式，有损或不支持的值直接报错，默认 false */
+  pub strict: Option<bool>,
+  /** 声明为 'D' 的字段接受十进制字符串，按字段的 scale 打包，最多 15 位有效数字，默认 false */
+  pub decimal: Option<bool>,
 }
 impl Packer {
   pub fn new(version: i32) -> Result<Self> {
@@ -157,10 +191,30 @@ impl Packer {
         return Err(napi::Error::from_reason("Failed to create packer instance"));
       }
 
-      Ok(Packer { packer_ptr })
+      Ok(Packer {
+        packer_ptr,
+        encoding: TextEncoding::default(),
+        handle: Handle::owned(HandleKind::Packer),
+        buffer: None,
+        fields: Mutex::new(Vec::new()),
+      })
     }
   }
 
+  /// 对同一打包器再持有一个引用，用于让借用其解包器的对象保持打包器存活
+  fn share(&self) -> Result<Packer> {
+    unsafe {
+      self.check_ptr()?.add_ref();
+    }
+    Ok(Packer {
+      packer_ptr: self.packer_ptr,
+      encoding: self.encoding,
+      handle: Handle::owned(HandleKind::Packer),
+      buffer: self.buffer.clone(),
+      fields: Mutex::new(Vec::new()),
+    })
+  }
+
   fn check_ptr(&self) -> Result<&IF2PackerRust> {
     unsafe {
       if self.packer_ptr.is_null() {
@@ -170,28 +224,89 @@ impl Packer {
     }
   }
 
-  fn to_c_string(s: String, field: &str) -> Result<CString> {
-    CString::new(s).map_err(|e| napi::Error::from_reason(format!("Invalid {}: {}", field, e)))
+  fn to_c_string(&self, s: String, field: &str) -> Result<CString> {
+    self.encoding.to_c_string(&s, field)
+  }
+
+  pub(crate) fn add_field_def(&self, name: &str, field_type: u8, width: i32, scale: i32) -> Result<i32> {
+    let c_name = self.to_c_string(name.to_string(), "field name")?;
+    let result = unsafe { self.check_ptr()?.add_field(c_name.as_ptr(), field_type as c_char, width, scale) };
+    self.track_field(result, name, field_type, width, scale);
+    Ok(result)
+  }
+
+  /// 打包结果，在下一次 begin_pack 之前有效
+  pub(crate) fn pack_bytes(&self) -> Result<&[u8]> {
+    unsafe {
+      let pack_buf = self.check_ptr()?.get_pack_buf();
+      let pack_len = self.check_ptr()?.get_pack_len();
+      Ok(std::slice::from_raw_parts(pack_buf as *const u8, pack_len as usize))
+    }
+  }
+
+  /// 记录添加成功的字段
+  fn track_field(&self, result: i32, name: &str, field_type: u8, width: i32, scale: i32) {
+    if result >= 0 {
+      self.fields.lock().unwrap().push(FieldDef {
+        name: name.to_string(),
+        field_type,
+        width,
+        scale,
+      });
+    }
+  }
+
+  /// 开始新的结果集或新的包时清空已记录的字段
+  fn reset_fields(&self) {
+    self.fields.lock().unwrap().clear();
+  }
+
+  // 字符串已按目标编码转换为字节
+  pub(crate) fn add_str_bytes(&self, value: &[u8]) -> Result<i32> {
+    let value = CString::new(value).map_err(|e| napi::Error::from_reason(format!("Invalid string value: {}", e)))?;
+    Ok(unsafe { self.check_ptr()?.add_str(value.as_ptr()) })
+  }
+
+  pub(crate) fn add_raw_bytes(&self, value: &[u8]) -> Result<i32> {
+    Ok(unsafe { self.check_ptr()?.add_raw(value.as_ptr() as *const c_void, value.len() as c_int) })
+  }
+
+  pub(crate) fn get_ptr(&self) -> *mut IF2PackerRust {
+    self.packer_ptr
   }
 }
 
 #[napi]
 impl Packer {
+  /// 设置字符串和字段名使用的编码
+  #[napi]
+  pub fn set_encoding(&mut self, encoding: TextEncoding) {
+    self.encoding = encoding;
+  }
+
+  /// 获取字符串和字段名使用的编码
+  #[napi]
+  pub fn get_encoding(&self) -> TextEncoding {
+    self.encoding
+  }
+
   /**
    * 打包器初始化(使用调用者的缓存区)
    * 第一次使用打包器时，可先使用本方法设置好缓冲区(数据长度被置为iDataLen)
    * @param  char * pBuf  缓冲区地址
    * @param  int iBufSize  缓冲区空间
    * @param  int iDataLen  已有数据长度，新增数据加在已有数据之后（只对V1.0格式的包有效）
+   * 打包器持有 pBuf 的引用直到释放，打包期间不要修改 pBuf
    */
   #[napi]
-  pub fn set_buffer(&self, p_buf: Buffer, i_data_len: Option<i32>) -> Result<()> {
+  pub fn set_buffer(&mut self, p_buf: Buffer, i_data_len: Option<i32>) -> Result<()> {
     let data_len = i_data_len.unwrap_or(0);
     unsafe {
       let p_buf_ptr = p_buf.as_ptr() as *mut c_void;
       let i_buf_size = p_buf.len() as i32;
       self.check_ptr()?.set_buffer(p_buf_ptr, i_buf_size, data_len);
     }
+    self.buffer = Some(p_buf);
     Ok(())
   }
   /**
@@ -204,6 +319,7 @@ impl Packer {
     unsafe {
       self.check_ptr()?.begin_pack();
     }
+    self.reset_fields();
     Ok(())
   }
   /**
@@ -214,8 +330,9 @@ impl Packer {
    */
   #[napi]
   pub fn new_dataset(&self, sz_dataset_name: String, i_return_code: i32) -> Result<i32> {
-    let sz_dataset_name = Self::to_c_string(sz_dataset_name, "sz_dataset_name")?;
+    let sz_dataset_name = self.to_c_string(sz_dataset_name, "sz_dataset_name")?;
     let result = unsafe { self.check_ptr()?.new_dataset(sz_dataset_name.as_ptr(), i_return_code) };
+    self.reset_fields();
     Ok(result)
   }
   /**
@@ -233,15 +350,16 @@ impl Packer {
   pub fn add_field(&self, sz_field_name: String, c_field_type: i8, i_field_width: i32, i_field_scale: i32) -> Result<i32> {
     use std::os::raw::{c_char, c_int};
 
-    let sz_field_name = Self::to_c_string(sz_field_name, "sz_field_name")?;
+    let name = self.to_c_string(sz_field_name.clone(), "sz_field_name")?;
     // 将 char 转换为 c_char
     let c_field_type = c_field_type as c_char;
 
     let result = unsafe {
       self
         .check_ptr()?
-        .add_field(sz_field_name.as_ptr(), c_field_type, i_field_width as c_int, i_field_scale as c_int)
+        .add_field(name.as_ptr(), c_field_type, i_field_width as c_int, i_field_scale as c_int)
     };
+    self.track_field(result, &sz_field_name, c_field_type as u8, i_field_width, i_field_scale);
 
     Ok(result)
   }
@@ -253,7 +371,7 @@ impl Packer {
    */
   #[napi]
   pub fn add_str(&self, sz_value: String) -> Result<i32> {
-    let sz_value = Self::to_c_string(sz_value, "sz_value")?;
+    let sz_value = self.to_c_string(sz_value, "sz_value")?;
     let result = unsafe { self.check_ptr()?.add_str(sz_value.as_ptr()) };
     Ok(result)
   }
@@ -316,12 +434,7 @@ impl Packer {
    */
   #[napi]
   pub fn get_pack_buf(&self) -> Result<Buffer> {
-    let result = unsafe {
-      let pack_buf = self.check_ptr()?.get_pack_buf();
-      let pack_len = self.check_ptr()?.get_pack_len();
-      Buffer::from(std::slice::from_raw_parts(pack_buf as *const u8, pack_len as usize))
-    };
-    Ok(result)
+    Ok(Buffer::from(self.pack_bytes()?))
   }
   /**
    * 功能：取打包结果长度
@@ -366,6 +479,7 @@ impl Packer {
 
   /**
    * 直接返回当前打包结果的解包接口,必须在EndPack()之后才能调用,在打包器释放时相应的解包器实例也释放
+   * 返回的解包器持有打包器的引用，两者都释放后打包器才释放
    * @return 解包器接口，此解包接口不能调用 destroy()来释放
    */
   #[napi]
@@ -374,7 +488,10 @@ impl Packer {
     if raw_ptr.is_null() {
       return Err(napi::Error::from_reason("Failed to unpack, please call end_pack() first"));
     }
-    Ok(UnPacker::from_ptr(raw_ptr))
+    // 解包器归打包器所有，借用期间持有打包器的引用
+    let mut unpacker = UnPacker::from_ptr(raw_ptr).with_owner(self.share()?);
+    unpacker.set_encoding(self.encoding);
+    Ok(unpacker)
   }
 
   #[napi]
@@ -402,10 +519,11 @@ impl Packer {
    */
   #[napi]
   pub fn begin_pack_ex(&self, sz_name: String) -> Result<()> {
-    let sz_name = Self::to_c_string(sz_name, "sz_name")?;
+    let sz_name = self.to_c_string(sz_name, "sz_name")?;
     unsafe {
       self.check_ptr()?.begin_pack_ex(sz_name.as_ptr());
     }
+    self.reset_fields();
     Ok(())
   }
   /// 20110324 dongpf 增加一个接口函数，用来复位当前结果集
@@ -419,14 +537,71 @@ impl Packer {
     unsafe {
       self.check_ptr()?.clear_data_set();
     }
+    self.reset_fields();
     Ok(())
   }
+
+  /**
+   * 按字段列表和多行数据打包，在一次调用中完成 addField 和所有取值，返回打包结果
+   * @param fields 字段列表，元素为字段名或 { name, type, width, scale }，未声明类型时按各行的值推断
+   * @param rows 行数据，元素为对象（按字段名取值）或数组（按字段顺序取值），转换规则与 send 相同
+   */
+  #[napi(
+    ts_args_type = "fields: Array<string | { name: string, type?: 'I' | 'D' | 'C' | 'S' | 'R', width?: number, scale?: number }>, rows: Array<object | Array<unknown>>, options?: FromRowsOptions"
+  )]
+  pub fn from_rows(fields: PackInput, rows: PackInput, options: Option<FromRowsOptions>) -> Result<Buffer> {
+    let options = options.unwrap_or(FromRowsOptions {
+      version: None,
+      dataset_name: None,
+      return_code: None,
+      encoding: None,
+      strict: None,
+      decimal: None,
+    });
+    let encoding = options.encoding.unwrap_or_default();
+    let fields = pack::parse_field_list(&fields.0)?;
+    let mut dataset = pack::parse_rows(
+      fields,
+      rows.0,
+      options.strict.unwrap_or(false),
+      options.decimal.unwrap_or(false),
+      encoding,
+    )?;
+    if options.dataset_name.is_some() || options.return_code.is_some() {
+      dataset.name = Some(options.dataset_name.unwrap_or_default());
+      dataset.return_code = options.return_code.unwrap_or(0);
+    }
+
+    let mut packer = Packer::new(options.version.unwrap_or(PACKER_VERSION_V2))?;
+    packer.set_encoding(encoding);
+    packer.begin_pack()?;
+    pack::write_datasets(&packer, &[dataset])?;
+    packer.end_pack()?;
+    packer.get_pack_buf()
+  }
+
+  /**
+   * 向当前结果集追加多行，在一次调用中完成所有取值，字段需已通过 addField 添加
+   * @param rows 行数据，元素为对象（按字段名取值）或数组（按字段顺序取值），转换规则与 send 相同
+   * @returns 追加的行数
+   */
+  #[napi(ts_args_type = "rows: Array<object | Array<unknown>>, options?: AppendRowsOptions")]
+  pub fn append_rows(&self, rows: PackInput, options: Option<AppendRowsOptions>) -> Result<u32> {
+    let fields = self.fields.lock().unwrap().clone();
+    if fields.is_empty() {
+      return Err(napi::Error::from_reason("No fields in the current dataset, call addField first"));
+    }
+    let (strict, decimal) = options.map_or((None, None), |o| (o.strict, o.decimal));
+    let dataset = pack::parse_rows(fields, rows.0, strict.unwrap_or(false), decimal.unwrap_or(false), self.encoding)?;
+    pack::write_rows(self, &dataset)?;
+    Ok(dataset.rows.len() as u32)
+  }
 }
 
 impl Drop for Packer {
   fn drop(&mut self) {
     unsafe {
-      if !self.packer_ptr.is_null() {
+      if !self.packer_ptr.is_null() && self.handle.is_owned() {
         let ptr = &*self.packer_ptr;
         ptr.release();
         self.packer_ptr = std::ptr::null_mut();
@@ -11,14 +11,19 @@ pub(crate) trait F2ResultSetInterface: IKnown {
   unsafe fn get_col_width(&self, column: c_int) -> c_int;
   unsafe fn find_col_index(&self, column_name: *const c_char) -> c_int;
   unsafe fn get_str_by_index(&self, column: c_int) -> *const c_char;
+  #[allow(dead_code)]
   unsafe fn get_str(&self, column_name: *const c_char) -> *const c_char;
   unsafe fn get_char_by_index(&self, column: c_int) -> c_char;
+  #[allow(dead_code)]
   unsafe fn get_char(&self, column_name: *const c_char) -> c_char;
   unsafe fn get_double_by_index(&self, column: c_int) -> f64;
+  #[allow(dead_code)]
   unsafe fn get_double(&self, column_name: *const c_char) -> f64;
   unsafe fn get_int_by_index(&self, column: c_int) -> c_int;
+  #[allow(dead_code)]
   unsafe fn get_int(&self, column_name: *const c_char) -> c_int;
   unsafe fn get_raw_by_index(&self, column: c_int, raw_len: *mut c_int) -> *mut c_void;
+  #[allow(dead_code)]
   unsafe fn get_raw(&self, column_name: *const c_char, raw_len: *mut c_int) -> *mut c_void;
   unsafe fn was_null(&self) -> c_int;
   unsafe fn next(&self);
@@ -1,9 +1,15 @@
-use napi::{bindgen_prelude::Buffer, Result};
+use napi::{
+  bindgen_prelude::{BigInt, Buffer},
+  Result, Status,
+};
 use napi_derive::napi;
 use std::ffi::{CStr, CString};
 use std::os::raw::{c_char, c_int, c_ulong, c_void};
 
+use crate::charset::TextEncoding;
+use crate::f2_packer::Packer;
 use crate::f2_result_set::{F2ResultSetInterface, F2ResultSetVTable};
+use crate::handles::{Handle, HandleKind};
 use crate::iknown::IKnown;
 
 pub(crate) trait IF2UnPacker: F2ResultSetInterface {
@@ -20,8 +26,11 @@ pub(crate) trait IF2UnPacker: F2ResultSetInterface {
   unsafe fn go(&self, row: c_int);
   unsafe fn get_dataset_name(&self) -> *const c_char;
   unsafe fn open_and_copy(&self, buffer: *mut c_void, len: c_int) -> c_int;
+  #[allow(dead_code)]
   unsafe fn get_col_type_by_name(&self, column_name: *const c_char) -> c_char;
+  #[allow(dead_code)]
   unsafe fn get_col_scale_by_name(&self, column_name: *const c_char) -> c_int;
+  #[allow(dead_code)]
   unsafe fn get_col_width_by_name(&self, column_name: *const c_char) -> c_int;
 }
 
@@ -196,9 +205,87 @@ impl IF2UnPacker for IF2UnPackerRust {
   }
 }
 
+/// 'D' 字段在 rows()、datasets()、toColumns() 中的返回形式
+#[napi]
+pub enum DecimalMode {
+  /// number，默认
+  Number,
+  /// 按字段精度格式化的字符串，如 '123.45'
+  String,
+  /// { units, scale }，值为 units / 10^scale
+  Units,
+}
+
+/// 定点数，值为 units / 10^scale
+#[napi(object)]
+pub struct Decimal {
+  pub units: BigInt,
+  pub scale: i32,
+}
+
+#[napi(object)]
+pub struct UnpackOptions {
+  /**
+   * 不复制输入，解包器持有 buffer 的引用直到释放，默认为 false
+   * 解包器释放之前不要修改 buffer 的内容
+   */
+  pub zero_copy: Option<bool>,
+  /**
+   * 字段类型与取值方法不符时转换，默认为 false，不符时抛出错误
+   * 例如对 'S' 字段调用 getInt 时解析字符串，对 'D' 字段调用 getStr 时按精度格式化
+   */
+  pub coerce: Option<bool>,
+  /** 'D' 字段在 rows()、datasets()、toColumns() 中的返回形式，默认 DecimalMode.Number */
+  pub decimal: Option<DecimalMode>,
+}
+
+/// 解包器读取的输入，在解包器释放之前保持有效
+enum Input {
+  /// 复制的输入
+  Copied(Vec<u8>),
+  /// 持有 JS Buffer 的引用，V8 不会回收
+  Pinned(Buffer),
+}
+
+impl Input {
+  fn new(buffer: Buffer, zero_copy: bool) -> Self {
+    if zero_copy {
+      Input::Pinned(buffer)
+    } else {
+      Input::Copied(buffer.to_vec())
+    }
+  }
+
+  fn as_ptr(&self) -> (*mut c_void, c_int) {
+    let data: &[u8] = match self {
+      Input::Copied(data) => data,
+      Input::Pinned(buffer) => buffer,
+    };
+    (data.as_ptr() as *mut c_void, data.len() as c_int)
+  }
+}
+
+/// 按精度（限制在 0 ~ 18）格式化 double，不输出 -0
+fn decimal_text(value: f64, scale: i32) -> String {
+  let text = format!("{:.*}", scale.clamp(0, 18) as usize, value);
+  match text.strip_prefix('-') {
+    Some(abs) if abs.bytes().all(|b| b == b'0' || b == b'.') => abs.to_string(),
+    _ => text,
+  }
+}
+
 #[napi]
 pub struct UnPacker {
   unpacker_ptr: *mut IF2UnPackerRust,
+  encoding: TextEncoding,
+  handle: Handle,
+  // 借用打包器的解包器时持有打包器，在解包器之后释放
+  owner: Option<Packer>,
+  // SDK 直接读取的输入，在解包器之后释放
+  input: Option<Input>,
+  // 字段类型与取值方法不符时是否转换
+  coerce: bool,
+  decimal: DecimalMode,
 }
 
 impl UnPacker {
@@ -217,11 +304,62 @@ impl UnPacker {
       return Err(napi::Error::from_reason("Failed to create unpacker instance"));
     }
 
-    Ok(UnPacker { unpacker_ptr })
+    Ok(UnPacker {
+      unpacker_ptr,
+      encoding: TextEncoding::default(),
+      handle: Handle::owned(HandleKind::UnPacker),
+      owner: None,
+      input: None,
+      coerce: false,
+      decimal: DecimalMode::Number,
+    })
+  }
+
+  /// 从 JS Buffer 创建解包器，按 options 复制输入或持有 buffer 的引用
+  pub(crate) fn from_buffer(buffer: Buffer, version: i32, options: Option<UnpackOptions>) -> Result<UnPacker> {
+    let options = options.unwrap_or(UnpackOptions {
+      zero_copy: None,
+      coerce: None,
+      decimal: None,
+    });
+    let input = Input::new(buffer, options.zero_copy.unwrap_or(false));
+    let (ptr, len) = input.as_ptr();
+    let mut unpacker = UnPacker::new(ptr, len, version)?;
+    unpacker.input = Some(input);
+    unpacker.coerce = options.coerce.unwrap_or(false);
+    unpacker.decimal = options.decimal.unwrap_or(DecimalMode::Number);
+    Ok(unpacker)
+  }
+
+  /// 包装 SDK 或打包器所有的解包器，释放时不调用 release()
+  pub(crate) fn from_ptr(ptr: *mut IF2UnPackerRust) -> Self {
+    Self::wrap(ptr, Handle::borrowed(HandleKind::UnPacker))
   }
 
-  pub(crate) fn from_ptr(ptr: *mut IF2UnPackerRust) -> Self {
-    UnPacker { unpacker_ptr: ptr }
+  /// 包装 SDK 返回、由调用方负责 release() 的解包器（如 GetTopic 的结果），释放时调用 release()
+  pub(crate) fn from_owned_ptr(ptr: *mut IF2UnPackerRust) -> Self {
+    Self::wrap(ptr, Handle::owned(HandleKind::UnPacker))
+  }
+
+  fn wrap(ptr: *mut IF2UnPackerRust, handle: Handle) -> Self {
+    UnPacker {
+      unpacker_ptr: ptr,
+      encoding: TextEncoding::default(),
+      handle,
+      owner: None,
+      input: None,
+      coerce: false,
+      decimal: DecimalMode::Number,
+    }
+  }
+
+  pub(crate) fn with_owner(mut self, owner: Packer) -> Self {
+    self.owner = Some(owner);
+    self
+  }
+
+  pub(crate) fn get_ptr(&self) -> *mut IF2UnPackerRust {
+    self.unpacker_ptr
   }
 
   fn check_ptr(&self) -> Result<&IF2UnPackerRust> {
@@ -233,15 +371,135 @@ impl UnPacker {
     }
   }
 
-  fn to_c_string(s: String, field: &str) -> Result<CString> {
-    CString::new(s).map_err(|e| napi::Error::from_reason(format!("Invalid {}: {}", field, e)))
+  fn to_c_string(&self, s: String, field: &str) -> Result<CString> {
+    self.encoding.to_c_string(&s, field)
+  }
+
+  /// 检查字段序号，返回 SDK 接口
+  fn check_column(&self, column: i32) -> Result<&IF2UnPackerRust> {
+    let rs = self.check_ptr()?;
+    let count = unsafe { rs.get_col_count() };
+    if column < 0 || column >= count {
+      return Err(napi::Error::new(
+        Status::InvalidArg,
+        format!("Column index {} out of range, dataset has {} columns", column, count),
+      ));
+    }
+    Ok(rs)
+  }
+
+  /// 按字段名查找字段序号，字段不存在时返回错误
+  fn column_index(&self, column_name: String) -> Result<i32> {
+    let index = self.find_col_index(column_name.clone())?;
+    if index < 0 {
+      return Err(napi::Error::new(Status::InvalidArg, format!("Column '{}' not found", column_name)));
+    }
+    Ok(index)
+  }
+
+  /// 检查字段序号和当前记录，返回 SDK 接口和字段类型
+  fn check_cell(&self, column: i32) -> Result<(&IF2UnPackerRust, u8)> {
+    let rs = self.check_column(column)?;
+    if unsafe { rs.is_eof() } != 0 {
+      return Err(napi::Error::from_reason("No current row, the dataset is at EOF"));
+    }
+    Ok((rs, unsafe { rs.get_col_type(column) } as u8))
+  }
+
+  fn type_error(&self, column: i32, col_type: u8, getter: &str) -> napi::Error {
+    let name = self.get_col_name(column).unwrap_or_default();
+    napi::Error::new(
+      Status::InvalidArg,
+      format!(
+        "Cannot call {} on column {} '{}' of type '{}', enable coerce to convert",
+        getter, column, name, col_type as char
+      ),
+    )
+  }
+
+  /// 字段的原始字符串内容，未解码
+  fn str_bytes(rs: &IF2UnPackerRust, column: i32) -> &[u8] {
+    unsafe {
+      let ptr = rs.get_str_by_index(column);
+      if ptr.is_null() {
+        return &[];
+      }
+      CStr::from_ptr(ptr).to_bytes()
+    }
+  }
+
+  /// 按字段精度格式化 'D' 字段
+  fn format_decimal(rs: &IF2UnPackerRust, column: i32) -> String {
+    decimal_text(unsafe { rs.get_double_by_index(column) }, unsafe { rs.get_col_scale(column) })
+  }
+
+  pub(crate) fn decimal_mode(&self) -> DecimalMode {
+    self.decimal
+  }
+
+  /// 'D' 字段的定点数形式
+  pub(crate) fn get_decimal_units_by_index(&self, column: i32) -> Result<Decimal> {
+    let text = self.get_decimal_by_index(column)?;
+    let (int_part, frac_part) = text.split_once('.').unwrap_or((&text, ""));
+    let units: i64 = format!("{}{}", int_part, frac_part)
+      .parse()
+      .map_err(|_| napi::Error::from_reason(format!("Column {} value {} overflows int64", column, text)))?;
+    Ok(Decimal {
+      units: BigInt::from(units),
+      scale: frac_part.len() as i32,
+    })
+  }
+
+  /// 将字符串字段解析为数字，空字符串为 0
+  fn parse_number<T: std::str::FromStr + Default>(&self, rs: &IF2UnPackerRust, column: i32) -> Result<T> {
+    let value = self.encoding.decode(Self::str_bytes(rs, column))?;
+    let value = value.trim();
+    if value.is_empty() {
+      return Ok(T::default());
+    }
+    value
+      .parse()
+      .map_err(|_| napi::Error::new(Status::InvalidArg, format!("Column {} value '{}' is not a number", column, value)))
   }
 }
 
 #[napi]
 impl UnPacker {
+  /// 设置字符串、字段名和结果集名使用的编码
+  #[napi]
+  pub fn set_encoding(&mut self, encoding: TextEncoding) {
+    self.encoding = encoding;
+  }
+
+  /// 获取字符串、字段名和结果集名使用的编码
   #[napi]
+  pub fn get_encoding(&self) -> TextEncoding {
+    self.encoding
+  }
+
+  /// 字段类型与取值方法不符时是否转换，见 UnpackOptions.coerce
+  #[napi]
+  pub fn set_coerce(&mut self, coerce: bool) {
+    self.coerce = coerce;
+  }
 
+  #[napi]
+  pub fn get_coerce(&self) -> bool {
+    self.coerce
+  }
+
+  /// 'D' 字段在 rows()、datasets()、toColumns() 中的返回形式，见 UnpackOptions.decimal
+  #[napi]
+  pub fn set_decimal_mode(&mut self, mode: DecimalMode) {
+    self.decimal = mode;
+  }
+
+  #[napi]
+  pub fn get_decimal_mode(&self) -> DecimalMode {
+    self.decimal
+  }
+
+  #[napi]
   pub fn get_col_count(&self) -> Result<i32> {
     let rs = self.check_ptr()?;
     Ok(unsafe { rs.get_col_count() })
@@ -249,107 +507,145 @@ impl UnPacker {
 
   #[napi]
   pub fn get_col_name(&self, column: i32) -> Result<String> {
-    let rs = self.check_ptr()?;
-    let col_name = unsafe { CStr::from_ptr(rs.get_col_name(column)).to_string_lossy().into_owned() };
-    Ok(col_name)
+    let rs = self.check_column(column)?;
+    unsafe { self.encoding.decode_c_str(rs.get_col_name(column)) }
   }
 
   #[napi]
   pub fn get_col_type(&self, column: i32) -> Result<i8> {
-    let rs = self.check_ptr()?;
+    let rs = self.check_column(column)?;
     Ok(unsafe { rs.get_col_type(column) })
   }
 
   #[napi]
   pub fn get_col_scale(&self, column: i32) -> Result<i32> {
-    let rs = self.check_ptr()?;
+    let rs = self.check_column(column)?;
     Ok(unsafe { rs.get_col_scale(column) })
   }
 
   #[napi]
   pub fn get_col_width(&self, column: i32) -> Result<i32> {
-    let rs = self.check_ptr()?;
+    let rs = self.check_column(column)?;
     Ok(unsafe { rs.get_col_width(column) })
   }
 
+  /// 字段不存在时返回 -1
   #[napi]
   pub fn find_col_index(&self, column_name: String) -> Result<i32> {
     let rs = self.check_ptr()?;
-    let column_name = UnPacker::to_c_string(column_name, "column_name")?;
+    let column_name = self.to_c_string(column_name, "column_name")?;
     Ok(unsafe { rs.find_col_index(column_name.as_ptr()) })
   }
 
+  /// 'S'、'C' 字段，转换时也接受 'I'、'D'（按精度格式化）和 'R'（按编码解码）字段
   #[napi]
   pub fn get_str_by_index(&self, column: i32) -> Result<String> {
-    let rs = self.check_ptr()?;
-    let str_val = unsafe { CStr::from_ptr(rs.get_str_by_index(column)).to_string_lossy().into_owned() };
-    Ok(str_val)
+    let (rs, col_type) = self.check_cell(column)?;
+    match col_type {
+      b'S' | b'C' => self.encoding.decode(Self::str_bytes(rs, column)),
+      b'I' if self.coerce => Ok(unsafe { rs.get_int_by_index(column) }.to_string()),
+      b'D' | b'F' if self.coerce => Ok(Self::format_decimal(rs, column)),
+      b'R' if self.coerce => self.encoding.decode(&self.get_raw_by_index(column)?),
+      _ => Err(self.type_error(column, col_type, "getStr")),
+    }
   }
 
   #[napi]
   pub fn get_str(&self, column_name: String) -> Result<String> {
-    let rs = self.check_ptr()?;
-    let column_name = UnPacker::to_c_string(column_name, "column_name")?;
-    let str_val = unsafe { CStr::from_ptr(rs.get_str(column_name.as_ptr())).to_string_lossy().into_owned() };
-    Ok(str_val)
+    self.get_str_by_index(self.column_index(column_name)?)
+  }
+
+  /**
+   * 'D' 字段按 getColScale 的精度格式化为十进制字符串，避免 double 的舍入误差
+   * 'I' 字段返回整数，转换时也接受 'S'、'C' 字段，原样返回
+   */
+  #[napi]
+  pub fn get_decimal_by_index(&self, column: i32) -> Result<String> {
+    let (rs, col_type) = self.check_cell(column)?;
+    match col_type {
+      b'D' | b'F' => Ok(Self::format_decimal(rs, column)),
+      b'I' => Ok(unsafe { rs.get_int_by_index(column) }.to_string()),
+      b'S' | b'C' if self.coerce => Ok(self.encoding.decode(Self::str_bytes(rs, column))?.trim().to_string()),
+      _ => Err(self.type_error(column, col_type, "getDecimal")),
+    }
   }
 
+  #[napi]
+  pub fn get_decimal(&self, column_name: String) -> Result<String> {
+    self.get_decimal_by_index(self.column_index(column_name)?)
+  }
+
+  /// 'C' 字段，转换时也接受 'S' 字段，取第一个字节
   #[napi]
   pub fn get_char_by_index(&self, column: i32) -> Result<i8> {
-    let rs = self.check_ptr()?;
-    Ok(unsafe { rs.get_char_by_index(column) })
+    let (rs, col_type) = self.check_cell(column)?;
+    match col_type {
+      b'C' => Ok(unsafe { rs.get_char_by_index(column) }),
+      b'S' if self.coerce => Ok(Self::str_bytes(rs, column).first().copied().unwrap_or(0) as i8),
+      _ => Err(self.type_error(column, col_type, "getChar")),
+    }
   }
 
   #[napi]
   pub fn get_char(&self, column_name: String) -> Result<i8> {
-    let rs = self.check_ptr()?;
-    let column_name = UnPacker::to_c_string(column_name, "column_name")?;
-    Ok(unsafe { rs.get_char(column_name.as_ptr()) })
+    self.get_char_by_index(self.column_index(column_name)?)
   }
 
+  /// 'D'、'I' 字段，转换时也接受 'S'、'C' 字段
   #[napi]
   pub fn get_double_by_index(&self, column: i32) -> Result<f64> {
-    let rs = self.check_ptr()?;
-    Ok(unsafe { rs.get_double_by_index(column) })
+    let (rs, col_type) = self.check_cell(column)?;
+    match col_type {
+      b'D' | b'F' => Ok(unsafe { rs.get_double_by_index(column) }),
+      b'I' => Ok(unsafe { rs.get_int_by_index(column) } as f64),
+      b'S' | b'C' if self.coerce => self.parse_number(rs, column),
+      _ => Err(self.type_error(column, col_type, "getDouble")),
+    }
   }
 
   #[napi]
   pub fn get_double(&self, column_name: String) -> Result<f64> {
-    let rs = self.check_ptr()?;
-    let column_name = UnPacker::to_c_string(column_name, "column_name")?;
-    Ok(unsafe { rs.get_double(column_name.as_ptr()) })
+    self.get_double_by_index(self.column_index(column_name)?)
   }
 
+  /// 'I' 字段，转换时也接受 'S'、'C' 字段和 'D' 字段（截断小数）
   #[napi]
   pub fn get_int_by_index(&self, column: i32) -> Result<i32> {
-    let rs = self.check_ptr()?;
-    Ok(unsafe { rs.get_int_by_index(column) })
+    let (rs, col_type) = self.check_cell(column)?;
+    match col_type {
+      b'I' => Ok(unsafe { rs.get_int_by_index(column) }),
+      b'D' | b'F' if self.coerce => Ok(unsafe { rs.get_double_by_index(column) } as i32),
+      b'S' | b'C' if self.coerce => self.parse_number(rs, column),
+      _ => Err(self.type_error(column, col_type, "getInt")),
+    }
   }
 
   #[napi]
   pub fn get_int(&self, column_name: String) -> Result<i32> {
-    let rs = self.check_ptr()?;
-    let column_name = UnPacker::to_c_string(column_name, "column_name")?;
-    Ok(unsafe { rs.get_int(column_name.as_ptr()) })
+    self.get_int_by_index(self.column_index(column_name)?)
   }
+
+  /// 'R' 字段，转换时也接受 'S'、'C' 字段，返回未解码的字符串内容
   #[napi]
   pub fn get_raw_by_index(&self, column: i32) -> Result<Buffer> {
-    let rs = self.check_ptr()?;
-    let mut raw_len = 0;
-    let ptr = unsafe { rs.get_raw_by_index(column, &mut raw_len) };
-    let buffer = unsafe { Buffer::from(std::slice::from_raw_parts(ptr as *const u8, raw_len as usize)) };
-    Ok(buffer)
+    let (rs, col_type) = self.check_cell(column)?;
+    match col_type {
+      b'R' => {
+        let mut raw_len = 0;
+        let ptr = unsafe { rs.get_raw_by_index(column, &mut raw_len) };
+        if ptr.is_null() || raw_len <= 0 {
+          return Ok(Buffer::from(Vec::new()));
+        }
+        Ok(unsafe { Buffer::from(std::slice::from_raw_parts(ptr as *const u8, raw_len as usize)) })
+      }
+      b'S' | b'C' if self.coerce => Ok(Buffer::from(Self::str_bytes(rs, column))),
+      _ => Err(self.type_error(column, col_type, "getRaw")),
+    }
   }
+
   #[napi]
   pub fn get_raw(&self, column_name: String) -> Result<Buffer> {
-    let rs = self.check_ptr()?;
-    let column_name = UnPacker::to_c_string(column_name, "column_name")?;
-    let mut raw_len = 0;
-    unsafe {
-      let ptr = rs.get_raw(column_name.as_ptr(), &mut raw_len);
-      let buffer = Buffer::from(std::slice::from_raw_parts(ptr as *const u8, raw_len as usize));
-      Ok(buffer)
-    }
+    self.get_raw_by_index(self.column_index(column_name)?)
   }
   #[napi]
   pub fn was_null(&self) -> Result<i32> {
@@ -378,6 +674,9 @@ impl UnPacker {
   }
   #[napi]
   pub fn destroy(&self) -> Result<()> {
+    if !self.handle.is_owned() {
+      return Err(napi::Error::from_reason("Borrowed unpacker cannot be destroyed"));
+    }
     let rs = self.check_ptr()?;
     unsafe {
       rs.destroy();
@@ -389,13 +688,17 @@ impl UnPacker {
     let rs = self.check_ptr()?;
     Ok(unsafe { rs.get_version() })
   }
+  /**
+   * 打开新的输入，默认复制 buffer；options.zeroCopy 为 true 时持有 buffer 的引用
+   * 之前的输入在解包器不再读取后释放
+   */
   #[napi]
-  pub fn open(&self, buffer: Buffer) -> Result<i32> {
-    let rs = self.check_ptr()?;
-    let buffer = buffer.as_ref();
-    let len = buffer.len() as i32;
-    let ptr = buffer.as_ptr() as *mut c_void;
-    Ok(unsafe { rs.open(ptr, len) })
+  pub fn open(&mut self, buffer: Buffer, options: Option<UnpackOptions>) -> Result<i32> {
+    let input = Input::new(buffer, options.and_then(|o| o.zero_copy).unwrap_or(false));
+    let (ptr, len) = input.as_ptr();
+    let result = unsafe { self.check_ptr()?.open(ptr, len) };
+    self.input = Some(input);
+    Ok(result)
   }
   #[napi]
   pub fn get_dataset_count(&self) -> Result<i32> {
@@ -410,7 +713,7 @@ impl UnPacker {
   #[napi]
   pub fn set_current_dataset(&self, dataset_name: String) -> Result<i32> {
     let rs = self.check_ptr()?;
-    let dataset_name = UnPacker::to_c_string(dataset_name, "dataset_name")?;
+    let dataset_name = self.to_c_string(dataset_name, "dataset_name")?;
     Ok(unsafe { rs.set_current_dataset(dataset_name.as_ptr()) })
   }
 
@@ -454,9 +757,9 @@ impl UnPacker {
   #[napi]
   pub fn get_dataset_name(&self) -> Result<String> {
     let rs = self.check_ptr()?;
-    let dataset_name = unsafe { CStr::from_ptr(rs.get_dataset_name()).to_string_lossy().into_owned() };
-    Ok(dataset_name)
+    unsafe { self.encoding.decode_c_str(rs.get_dataset_name()) }
   }
+  /// 由 SDK 复制 buffer 后打开
   #[napi]
   pub fn open_and_copy(&self, buffer: Buffer) -> Result<i32> {
     let rs = self.check_ptr()?;
@@ -467,28 +770,22 @@ impl UnPacker {
   }
   #[napi]
   pub fn get_col_type_by_name(&self, column_name: String) -> Result<i8> {
-    let rs = self.check_ptr()?;
-    let column_name = UnPacker::to_c_string(column_name, "column_name")?;
-    Ok(unsafe { rs.get_col_type_by_name(column_name.as_ptr()) })
+    self.get_col_type(self.column_index(column_name)?)
   }
   #[napi]
   pub fn get_col_scale_by_name(&self, column_name: String) -> Result<i32> {
-    let rs = self.check_ptr()?;
-    let column_name = UnPacker::to_c_string(column_name, "column_name")?;
-    Ok(unsafe { rs.get_col_scale_by_name(column_name.as_ptr()) })
+    self.get_col_scale(self.column_index(column_name)?)
   }
   #[napi]
   pub fn get_col_width_by_name(&self, column_name: String) -> Result<i32> {
-    let rs = self.check_ptr()?;
-    let column_name = UnPacker::to_c_string(column_name, "column_name")?;
-    Ok(unsafe { rs.get_col_width_by_name(column_name.as_ptr()) })
+    self.get_col_width(self.column_index(column_name)?)
   }
 }
 
 impl Drop for UnPacker {
   fn drop(&mut self) {
     unsafe {
-      if !self.unpacker_ptr.is_null() {
+      if !self.unpacker_ptr.is_null() && self.handle.is_owned() {
         let ptr = &*self.unpacker_ptr;
         ptr.release();
         self.unpacker_ptr = std::ptr::null_mut();
@@ -496,3 +793,17 @@ impl Drop for UnPacker {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn decimal_text_uses_column_scale() {
+    assert_eq!(decimal_text(0.1 + 0.2, 2), "0.30");
+    assert_eq!(decimal_text(123.456, 0), "123");
+    assert_eq!(decimal_text(1.5, -1), "2");
+    assert_eq!(decimal_text(-0.0001, 2), "0.00");
+    assert_eq!(decimal_text(-1.25, 1), "-1.2");
+  }
+}
@@ -0,0 +1,453 @@
+use napi::{Env, JsFunction, Result};
+use napi_derive::napi;
+use std::os::raw::{c_char, c_int, c_ulong, c_void};
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+use crate::callback::{callback_add_ref, callback_query_interface, callback_release};
+use crate::charset::TextEncoding;
+use crate::connection::ConnectionAlive;
+use crate::def::*;
+use crate::event::Listeners;
+use crate::f2_packer::{IF2PackerRust, Packer};
+use crate::f2_unpacker::{IF2UnPackerRust, UnPacker};
+use crate::iknown::{IKnown, IKnownVTable};
+
+pub(crate) trait CFileUpdateInterface: IKnown {
+  unsafe fn update_file(&self, pack: *mut IF2PackerRust) -> c_int;
+  unsafe fn cancel_update_file(&self) -> c_int;
+  #[allow(dead_code)]
+  unsafe fn get_file_update_list(&self) -> *mut IF2UnPackerRust;
+}
+
+#[repr(C)]
+pub(crate) struct FileUpdateVTable {
+  pub iknown: IKnownVTable,
+  update_file: unsafe extern "C" fn(this: *mut c_void, pack: *mut IF2PackerRust) -> c_int,
+  cancel_update_file: unsafe extern "C" fn(this: *mut c_void) -> c_int,
+  get_file_update_list: unsafe extern "C" fn(this: *mut c_void) -> *mut IF2UnPackerRust,
+}
+
+#[repr(C)]
+pub(crate) struct CFileUpdateRust {
+  vtable: *const FileUpdateVTable,
+}
+
+impl IKnown for CFileUpdateRust {
+  unsafe fn query_interface(&self, iid: *const c_char, ppv: *mut *mut c_void) -> c_ulong {
+    ((*self.vtable).iknown.query_interface)(self as *const _ as *mut c_void, iid, ppv)
+  }
+
+  unsafe fn add_ref(&self) -> c_ulong {
+    ((*self.vtable).iknown.add_ref)(self as *const _ as *mut c_void)
+  }
+
+  unsafe fn release(&self) -> c_ulong {
+    ((*self.vtable).iknown.release)(self as *const _ as *mut c_void)
+  }
+}
+
+impl CFileUpdateInterface for CFileUpdateRust {
+  unsafe fn update_file(&self, pack: *mut IF2PackerRust) -> c_int {
+    ((*self.vtable).update_file)(self as *const _ as *mut c_void, pack)
+  }
+
+  unsafe fn cancel_update_file(&self) -> c_int {
+    ((*self.vtable).cancel_update_file)(self as *const _ as *mut c_void)
+  }
+
+  unsafe fn get_file_update_list(&self) -> *mut IF2UnPackerRust {
+    ((*self.vtable).get_file_update_list)(self as *const _ as *mut c_void)
+  }
+}
+
+/// 远端可更新的文件
+#[napi(object)]
+#[derive(Clone)]
+pub struct RemoteFile {
+  pub id: i32,
+  pub name: String,
+  pub path: String,
+  pub local_path: Option<String>,
+  pub size: i32,
+  pub time: i32,
+  pub md5: String,
+  pub flag: String,
+}
+
+/// OnRecvFileList 事件
+#[napi(object)]
+#[derive(Clone)]
+pub struct FileListEvent {
+  pub files: Vec<RemoteFile>,
+  /** 错误码，nLength 为负数时返回 */
+  pub error_no: Option<i32>,
+  pub error_info: Option<String>,
+}
+
+/// OnShowProcessBar 事件
+#[napi(object)]
+#[derive(Clone)]
+pub struct ProgressEvent {
+  /** 当前文件进度 */
+  pub file_progress: i32,
+  /** 总进度，到达 100 表示更新完成 */
+  pub total_progress: i32,
+  pub error_info: Option<String>,
+}
+
+#[napi(object)]
+pub struct FileUpdaterOptions {
+  /** 超时时间（毫秒），默认 5000 */
+  pub timeout_ms: Option<u32>,
+  /** 目录过滤 */
+  pub dir_filter: Option<String>,
+}
+
+#[derive(Clone, PartialEq)]
+enum UpdateStatus {
+  Idle,
+  Running,
+  Done,
+  Failed(String),
+  Cancelled,
+}
+
+struct FileUpdateState {
+  encoding: TextEncoding,
+  timeout_ms: u32,
+  file_list: watch::Sender<Option<FileListEvent>>,
+  // 文件列表的原始包，更新时从中复制选中的行
+  file_list_buf: Mutex<Vec<u8>>,
+  status: watch::Sender<UpdateStatus>,
+  on_file_list: Listeners<FileListEvent>,
+  on_progress: Listeners<ProgressEvent>,
+  on_cancel: Listeners<()>,
+}
+
+impl FileUpdateState {
+  unsafe fn error_info(&self, ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() || *ptr == 0 {
+      return None;
+    }
+    Some(self.encoding.decode_c_str_lossy(ptr))
+  }
+}
+
+#[repr(C)]
+pub(crate) struct FileUpdateCallbackRust {
+  vtable: *const FileUpdateCallbackVTable,
+  state: Arc<FileUpdateState>,
+}
+
+#[repr(C)]
+struct FileUpdateCallbackVTable {
+  iknown: IKnownVTable,
+  on_recv_file_list: extern "C" fn(this: *mut FileUpdateCallbackRust, data: *const c_void, len: c_int, error_info: *const c_char),
+  on_show_process_bar: extern "C" fn(this: *mut FileUpdateCallbackRust, one: c_int, total: c_int, error_info: *const c_char),
+  on_cancel: extern "C" fn(this: *mut FileUpdateCallbackRust),
+}
+
+fn optional_str(unpacker: &UnPacker, column: &str) -> Result<Option<String>> {
+  if unpacker.find_col_index(column.to_string())? < 0 {
+    return Ok(None);
+  }
+  unpacker.get_str(column.to_string()).map(Some)
+}
+
+fn optional_int(unpacker: &UnPacker, column: &str) -> Result<i32> {
+  if unpacker.find_col_index(column.to_string())? < 0 {
+    return Ok(0);
+  }
+  unpacker.get_int(column.to_string())
+}
+
+fn decode_file_list(data: &mut [u8], encoding: TextEncoding) -> Result<Vec<RemoteFile>> {
+  let mut unpacker = UnPacker::new(data.as_mut_ptr() as *mut c_void, data.len() as c_int, 0)?;
+  unpacker.set_encoding(encoding);
+  unpacker.set_coerce(true);
+  let mut files = Vec::new();
+  unpacker.first()?;
+  while unpacker.is_eof()? == 0 {
+    files.push(RemoteFile {
+      id: optional_int(&unpacker, PACKER_INT_FILE_ID)?,
+      name: optional_str(&unpacker, PACKER_STRING_FILE_NAME)?.unwrap_or_default(),
+      path: optional_str(&unpacker, PACKER_STRING_FILE_PATH)?.unwrap_or_default(),
+      local_path: optional_str(&unpacker, PACKER_STRING_LOCAL_PATH)?,
+      size: optional_int(&unpacker, PACKER_INT_FILE_SIZE)?,
+      time: optional_int(&unpacker, PACKER_INT_TIME)?,
+      md5: optional_str(&unpacker, PACKER_STRING_MD5_CODE)?.unwrap_or_default(),
+      flag: optional_str(&unpacker, PACKER_STRING_FILE_FLAG)?.unwrap_or_default(),
+    });
+    unpacker.next()?;
+  }
+  Ok(files)
+}
+
+extern "C" fn on_recv_file_list(this: *mut FileUpdateCallbackRust, data: *const c_void, len: c_int, error_info: *const c_char) {
+  let state = unsafe { &(*this).state };
+  let mut event = FileListEvent {
+    files: Vec::new(),
+    error_no: None,
+    error_info: unsafe { state.error_info(error_info) },
+  };
+  if len < 0 {
+    event.error_no = Some(len);
+  } else if event.error_info.is_none() && !data.is_null() {
+    let mut buf = unsafe { std::slice::from_raw_parts(data as *const u8, len as usize) }.to_vec();
+    match decode_file_list(&mut buf, state.encoding) {
+      Ok(files) => event.files = files,
+      Err(e) => event.error_info = Some(e.reason),
+    }
+    *state.file_list_buf.lock().unwrap() = buf;
+  }
+  state.file_list.send_replace(Some(event.clone()));
+  state.on_file_list.emit(event);
+}
+
+extern "C" fn on_show_process_bar(this: *mut FileUpdateCallbackRust, one: c_int, total: c_int, error_info: *const c_char) {
+  let state = unsafe { &(*this).state };
+  let event = ProgressEvent {
+    file_progress: one,
+    total_progress: total,
+    error_info: unsafe { state.error_info(error_info) },
+  };
+  let status = match &event.error_info {
+    Some(error) => UpdateStatus::Failed(error.clone()),
+    None if total >= 100 => UpdateStatus::Done,
+    None => UpdateStatus::Running,
+  };
+  state.status.send_replace(status);
+  state.on_progress.emit(event);
+}
+
+extern "C" fn on_cancel(this: *mut FileUpdateCallbackRust) {
+  let state = unsafe { &(*this).state };
+  state.status.send_replace(UpdateStatus::Cancelled);
+  state.on_cancel.emit(());
+}
+
+fn create_file_update_callback(state: Arc<FileUpdateState>) -> Box<FileUpdateCallbackRust> {
+  static VTABLE: FileUpdateCallbackVTable = FileUpdateCallbackVTable {
+    iknown: IKnownVTable {
+      query_interface: callback_query_interface,
+      add_ref: callback_add_ref,
+      release: callback_release,
+    },
+    on_recv_file_list,
+    on_show_process_bar,
+    on_cancel,
+  };
+
+  Box::new(FileUpdateCallbackRust { vtable: &VTABLE, state })
+}
+
+/**
+ * 文件更新，创建后服务端推送可更新的文件列表
+ * 事件：fileList（收到文件列表）、progress（更新进度）、cancel（取消成功）
+ */
+#[napi]
+pub struct FileUpdater {
+  ptr: *mut CFileUpdateRust,
+  // 创建文件更新对象的连接，释放后不再调用 SDK
+  alive: ConnectionAlive,
+  // 回调对象需要在 ptr 释放前一直有效
+  callback: Box<FileUpdateCallbackRust>,
+}
+
+impl FileUpdater {
+  /// 创建回调对象后调用 create 取得 CFileUpdateInterface
+  pub(crate) fn new<F>(encoding: TextEncoding, alive: ConnectionAlive, options: Option<FileUpdaterOptions>, create: F) -> Result<Self>
+  where
+    F: FnOnce(*mut c_void, u32, Option<&str>) -> Result<*mut CFileUpdateRust>,
+  {
+    let timeout_ms = options.as_ref().and_then(|o| o.timeout_ms).unwrap_or(5000);
+    let dir_filter = options.and_then(|o| o.dir_filter);
+    let state = Arc::new(FileUpdateState {
+      encoding,
+      timeout_ms,
+      file_list: watch::channel(None).0,
+      file_list_buf: Mutex::new(Vec::new()),
+      status: watch::channel(UpdateStatus::Idle).0,
+      on_file_list: Listeners::new(),
+      on_progress: Listeners::new(),
+      on_cancel: Listeners::new(),
+    });
+    let mut callback = create_file_update_callback(state);
+    let ptr = create(
+      &mut *callback as *mut FileUpdateCallbackRust as *mut c_void,
+      timeout_ms,
+      dir_filter.as_deref(),
+    )?;
+    Ok(FileUpdater { ptr, alive, callback })
+  }
+
+  fn state(&self) -> &FileUpdateState {
+    &self.callback.state
+  }
+
+  /// 在连接未释放时调用 SDK，调用期间持有连接的读锁
+  fn with_ptr<R>(&self, f: impl FnOnce(&CFileUpdateRust) -> R) -> Result<R> {
+    let _guard = self.alive.guard()?;
+    if self.ptr.is_null() {
+      return Err(napi::Error::from_reason("File updater pointer is null"));
+    }
+    Ok(f(unsafe { &*self.ptr }))
+  }
+
+  /// 从文件列表原始包中复制选中的行，字段与服务端下发的一致
+  fn pack_selection(&self, ids: &[i32]) -> Result<Packer> {
+    let state = self.state();
+    let mut buf = state.file_list_buf.lock().unwrap().clone();
+    if buf.is_empty() {
+      return Err(napi::Error::from_reason("File list has not been received yet"));
+    }
+    let mut unpacker = UnPacker::new(buf.as_mut_ptr() as *mut c_void, buf.len() as c_int, 0)?;
+    unpacker.set_encoding(state.encoding);
+    unpacker.set_coerce(true);
+    let id_column = unpacker.find_col_index(PACKER_INT_FILE_ID.to_string())?;
+    if id_column < 0 {
+      return Err(napi::Error::from_reason("File list has no file_id column"));
+    }
+
+    let mut packer = Packer::new(PACKER_VERSION_V2)?;
+    packer.set_encoding(state.encoding);
+    packer.begin_pack()?;
+    let col_count = unpacker.get_col_count()?;
+    for column in 0..col_count {
+      packer.add_field(
+        unpacker.get_col_name(column)?,
+        unpacker.get_col_type(column)?,
+        unpacker.get_col_width(column)?,
+        unpacker.get_col_scale(column)?,
+      )?;
+    }
+
+    let mut matched = 0;
+    unpacker.first()?;
+    while unpacker.is_eof()? == 0 {
+      if ids.contains(&unpacker.get_int_by_index(id_column)?) {
+        matched += 1;
+        for column in 0..col_count {
+          match unpacker.get_col_type(column)? as u8 {
+            b'I' => packer.add_int(unpacker.get_int_by_index(column)?)?,
+            b'D' => packer.add_double(unpacker.get_double_by_index(column)?)?,
+            b'C' => packer.add_char(unpacker.get_char_by_index(column)?)?,
+            b'R' => packer.add_raw(unpacker.get_raw_by_index(column)?)?,
+            _ => packer.add_str(unpacker.get_str_by_index(column)?)?,
+          };
+        }
+      }
+      unpacker.next()?;
+    }
+    packer.end_pack()?;
+
+    if matched == 0 {
+      return Err(napi::Error::from_reason("None of the given file ids are in the file list"));
+    }
+    Ok(packer)
+  }
+}
+
+#[napi]
+impl FileUpdater {
+  /**
+   * 注册事件监听
+   * - fileList: `(event: FileListEvent) => void`
+   * - progress: `(event: ProgressEvent) => void`
+   * - cancel: `() => void`
+   */
+  #[napi(ts_args_type = "event: 'fileList' | 'progress' | 'cancel', callback: (event: any) => void")]
+  pub fn on(&self, env: Env, event: String, callback: JsFunction) -> Result<()> {
+    let state = self.state();
+    match event.as_str() {
+      "fileList" => state.on_file_list.add(&env, callback),
+      "progress" => state.on_progress.add(&env, callback),
+      "cancel" => state.on_cancel.add(&env, callback),
+      _ => Err(napi::Error::from_reason(format!("Unknown event: {}", event))),
+    }
+  }
+
+  /// 移除所有事件监听
+  #[napi]
+  pub fn remove_all_listeners(&self) {
+    let state = self.state();
+    state.on_file_list.clear();
+    state.on_progress.clear();
+    state.on_cancel.clear();
+  }
+
+  /**
+   * 获取远端文件列表，尚未收到时等待 OnRecvFileList
+   * @param timeoutMs 等待时间，默认为创建时的超时时间
+   */
+  #[napi]
+  pub async fn list_files(&self, timeout_ms: Option<u32>) -> Result<Vec<RemoteFile>> {
+    let timeout = std::time::Duration::from_millis(timeout_ms.unwrap_or(self.state().timeout_ms) as u64);
+    let mut rx = self.state().file_list.subscribe();
+    let event = match tokio::time::timeout(timeout, rx.wait_for(|list| list.is_some())).await {
+      Ok(Ok(list)) => list.clone().unwrap(),
+      Ok(Err(_)) => return Err(napi::Error::from_reason("File updater closed")),
+      Err(_) => return Err(napi::Error::from_reason("Timed out waiting for file list")),
+    };
+    match (event.error_info, event.error_no) {
+      (Some(info), _) => Err(napi::Error::from_reason(info)),
+      (None, Some(no)) => Err(napi::Error::from_reason(format!("Failed to receive file list: {}", no))),
+      (None, None) => Ok(event.files),
+    }
+  }
+
+  /**
+   * 下载选中的文件到更新目录，总进度到达 100 时完成
+   * @param ids 文件 id 列表，来自 listFiles
+   */
+  #[napi]
+  pub async fn update(&self, ids: Vec<i32>) -> Result<()> {
+    let packer = self.pack_selection(&ids)?;
+    let state = self.state();
+    state.status.send_replace(UpdateStatus::Running);
+    let ret = self
+      .with_ptr(|updater| unsafe { updater.update_file(packer.get_ptr()) })
+      .and_then(|ret| match ret {
+        0 => Ok(()),
+        ret => Err(napi::Error::from_reason(format!("Failed to update files: {}", ret))),
+      });
+    if let Err(e) = ret {
+      state.status.send_replace(UpdateStatus::Idle);
+      return Err(e);
+    }
+
+    let mut rx = state.status.subscribe();
+    let status = match rx.wait_for(|status| *status != UpdateStatus::Running).await {
+      Ok(status) => status.clone(),
+      Err(_) => return Err(napi::Error::from_reason("File updater closed")),
+    };
+    match status {
+      UpdateStatus::Done | UpdateStatus::Idle => Ok(()),
+      UpdateStatus::Failed(error) => Err(napi::Error::from_reason(error)),
+      UpdateStatus::Cancelled => Err(napi::Error::from_reason("File update cancelled")),
+      UpdateStatus::Running => Err(napi::Error::from_reason("File update is still running")),
+    }
+  }
+
+  /// 取消正在进行的更新，成功后触发 cancel 事件
+  #[napi]
+  pub fn cancel(&self) -> Result<i32> {
+    self.with_ptr(|updater| unsafe { updater.cancel_update_file() })
+  }
+}
+
+unsafe impl Send for FileUpdater {}
+unsafe impl Sync for FileUpdater {}
+
+impl Drop for FileUpdater {
+  fn drop(&mut self) {
+    // 连接已释放时不再调用 SDK
+    if let Ok(_guard) = self.alive.guard() {
+      if !self.ptr.is_null() {
+        unsafe { (*self.ptr).release() };
+      }
+    }
+    self.ptr = std::ptr::null_mut();
+  }
+}
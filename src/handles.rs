@@ -0,0 +1,179 @@
+use dashmap::DashMap;
+use napi_derive::napi;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+/// 包装 SDK 对象的类型
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum HandleKind {
+  Packer,
+  UnPacker,
+  BizMessage,
+  Config,
+  Connection,
+}
+
+const KINDS: [(HandleKind, &str); 5] = [
+  (HandleKind::Packer, "Packer"),
+  (HandleKind::UnPacker, "UnPacker"),
+  (HandleKind::BizMessage, "BizMessage"),
+  (HandleKind::Config, "Config"),
+  (HandleKind::Connection, "Connection"),
+];
+
+/// 包装对象与 SDK 指针的所有权关系
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Ownership {
+  /// 持有一个引用，释放包装对象时调用 release()
+  Owned,
+  /// 指针归 SDK 或其他对象所有，不能 release()
+  Borrowed,
+}
+
+struct LiveHandle {
+  kind: HandleKind,
+  created_at: Instant,
+}
+
+/// 按类型的计数始终维护；逐个对象的登记只在开启跟踪后进行，用于计算存活时间
+struct Registry {
+  tracking: AtomicBool,
+  next_id: AtomicU64,
+  live: DashMap<u64, LiveHandle>,
+  created: [AtomicUsize; KINDS.len()],
+  dropped: [AtomicUsize; KINDS.len()],
+  owned: [AtomicUsize; KINDS.len()],
+  borrowed: [AtomicUsize; KINDS.len()],
+}
+
+fn registry() -> &'static Registry {
+  static REGISTRY: OnceLock<Registry> = OnceLock::new();
+  REGISTRY.get_or_init(|| Registry {
+    tracking: AtomicBool::new(false),
+    next_id: AtomicU64::new(1),
+    live: DashMap::new(),
+    created: Default::default(),
+    dropped: Default::default(),
+    owned: Default::default(),
+    borrowed: Default::default(),
+  })
+}
+
+impl Registry {
+  fn live_count(&self, ownership: Ownership) -> &[AtomicUsize; KINDS.len()] {
+    match ownership {
+      Ownership::Owned => &self.owned,
+      Ownership::Borrowed => &self.borrowed,
+    }
+  }
+}
+
+/// 开启或关闭逐个对象的跟踪，关闭时清空已登记的对象
+pub(crate) fn set_tracking(enabled: bool) {
+  let registry = registry();
+  registry.tracking.store(enabled, Ordering::Relaxed);
+  if !enabled {
+    registry.live.clear();
+  }
+}
+
+/**
+ * 包装对象持有的计数凭证，创建时计数，随包装对象一起释放
+ * 包装对象在 Drop 中根据 is_owned() 决定是否 release() 指针
+ */
+pub(crate) struct Handle {
+  // 开启跟踪时登记的编号
+  id: Option<u64>,
+  kind: HandleKind,
+  ownership: Ownership,
+}
+
+impl Handle {
+  pub fn new(kind: HandleKind, ownership: Ownership) -> Self {
+    let registry = registry();
+    registry.created[kind as usize].fetch_add(1, Ordering::Relaxed);
+    registry.live_count(ownership)[kind as usize].fetch_add(1, Ordering::Relaxed);
+    let id = registry.tracking.load(Ordering::Relaxed).then(|| {
+      let id = registry.next_id.fetch_add(1, Ordering::Relaxed);
+      registry.live.insert(
+        id,
+        LiveHandle {
+          kind,
+          created_at: Instant::now(),
+        },
+      );
+      id
+    });
+    Handle { id, kind, ownership }
+  }
+
+  pub fn owned(kind: HandleKind) -> Self {
+    Handle::new(kind, Ownership::Owned)
+  }
+
+  pub fn borrowed(kind: HandleKind) -> Self {
+    Handle::new(kind, Ownership::Borrowed)
+  }
+
+  pub fn is_owned(&self) -> bool {
+    self.ownership == Ownership::Owned
+  }
+}
+
+impl Drop for Handle {
+  fn drop(&mut self) {
+    let registry = registry();
+    if let Some(id) = self.id {
+      registry.live.remove(&id);
+    }
+    registry.live_count(self.ownership)[self.kind as usize].fetch_sub(1, Ordering::Relaxed);
+    registry.dropped[self.kind as usize].fetch_add(1, Ordering::Relaxed);
+  }
+}
+
+#[napi(object)]
+pub struct HandleStats {
+  /** 'Packer' | 'UnPacker' | 'BizMessage' | 'Config' | 'Connection' */
+  pub kind: String,
+  /** 未释放的包装对象数 */
+  pub live: u32,
+  /** 其中持有引用、释放时 release() 的 */
+  pub owned: u32,
+  /** 其中借用 SDK 或其他对象指针的 */
+  pub borrowed: u32,
+  /** 累计创建数 */
+  pub created: i64,
+  /** 累计释放数 */
+  pub dropped: i64,
+  /** 最早创建的未释放对象的存活时间，毫秒，只统计 setHandleTracking(true) 之后创建的对象 */
+  pub oldest_age_ms: Option<f64>,
+}
+
+/// 按类型统计当前的包装对象
+pub(crate) fn report() -> Vec<HandleStats> {
+  let registry = registry();
+  KINDS
+    .iter()
+    .map(|(kind, name)| {
+      let index = *kind as usize;
+      let owned = registry.owned[index].load(Ordering::Relaxed);
+      let borrowed = registry.borrowed[index].load(Ordering::Relaxed);
+      let oldest_age_ms = registry
+        .live
+        .iter()
+        .filter(|h| h.kind == *kind)
+        .map(|h| h.created_at.elapsed().as_secs_f64() * 1000.0)
+        .reduce(f64::max);
+      HandleStats {
+        kind: name.to_string(),
+        live: (owned + borrowed) as u32,
+        owned: owned as u32,
+        borrowed: borrowed as u32,
+        created: registry.created[index].load(Ordering::Relaxed) as i64,
+        dropped: registry.dropped[index].load(Ordering::Relaxed) as i64,
+        oldest_age_ms,
+      }
+    })
+    .collect()
+}
@@ -1,28 +1,62 @@
-use encoding::Encoding;
-use napi::{bindgen_prelude::Buffer, bindgen_prelude::Object, Result};
+use napi::{bindgen_prelude::Buffer, bindgen_prelude::Object, bindgen_prelude::Reference, Env, JsFunction, JsObject, Result};
 use napi_derive::napi;
-use std::os::raw::{c_int, c_void};
+use std::os::raw::c_void;
 use std::ptr;
 use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::Arc;
 
 mod biz_message;
 mod callback;
+mod charset;
+mod checkpoint;
 mod config;
+mod config_schema;
 mod connection;
+#[allow(dead_code)]
 mod def;
 mod dylib;
+mod event;
 mod f2_packer;
 mod f2_result_set;
 mod f2_unpacker;
+mod file_update;
+mod handles;
 mod iknown;
+mod log;
+mod metrics;
+mod msg_center;
+#[cfg(test)]
+mod napi_stubs;
+mod pack;
+mod queue;
+mod rows;
+mod serve;
+mod subscribe;
+mod template;
+mod topic;
+mod trace;
+mod unsolicited;
 
 use biz_message::*;
+use charset::*;
 use config::*;
 use connection::*;
 use def::*;
 use dylib::*;
+use event::Listeners;
 use f2_packer::*;
 use f2_unpacker::*;
+use file_update::*;
+use handles::HandleStats;
+use log::*;
+use metrics::*;
+use msg_center::*;
+use serve::*;
+use subscribe::*;
+use template::*;
+use topic::*;
+use trace::*;
+use unsolicited::*;
 
 static LIBRARY: AtomicPtr<LoadedLibrary> = AtomicPtr::new(ptr::null_mut());
 static mut ALLOCATED_LIBRARY: Option<Box<LoadedLibrary>> = None;
@@ -42,10 +76,15 @@ pub async fn init(lib_path: String) -> Result<()> {
     return Ok(());
   }
 
-  let loaded_lib = tokio::task::spawn_blocking(move || LoadedLibrary::new(&lib_path))
+  let path = lib_path.clone();
+  let loaded_lib = tokio::task::spawn_blocking(move || LoadedLibrary::new(&path))
     .await
     .map_err(|e| napi::Error::from_reason(format!("Task join error: {}", e)))?
-    .map_err(|e| napi::Error::from_reason(format!("Failed to load library: {}", e)))?;
+    .map_err(|e| {
+      log(LogLevel::Error, Target::Library, || format!("Failed to load {}: {}", lib_path, e));
+      napi::Error::from_reason(format!("Failed to load library: {}", e))
+    })?;
+  log(LogLevel::Info, Target::Library, || format!("Loaded {}", lib_path));
 
   let boxed_lib = Box::new(loaded_lib);
   let lib_ptr = Box::into_raw(boxed_lib);
@@ -66,6 +105,33 @@ pub async fn init(lib_path: String) -> Result<()> {
   }
 }
 
+/**
+ * 设置日志级别，默认为 Warn
+ * @param target 只设置某一来源的级别：library、connect、send、receive、callback、drop
+ */
+#[napi]
+pub fn set_log_level(level: LogLevel, target: Option<String>) -> Result<()> {
+  log::set_level(level, target)
+}
+
+/// 设置日志回调，替换之前的回调；传入 null 时恢复写到 stderr
+#[napi(ts_args_type = "sink: ((record: LogRecord) => void) | null")]
+pub fn set_log_sink(env: Env, sink: Option<JsFunction>) -> Result<()> {
+  log::set_sink(&env, sink)
+}
+
+/// 按类型统计未释放的 SDK 包装对象，用于排查长时间运行时的泄漏
+#[napi]
+pub fn debug_handles() -> Vec<HandleStats> {
+  handles::report()
+}
+
+/// 开启后逐个记录包装对象的创建时间，debugHandles 返回 oldestAgeMs；默认关闭，只维护按类型的计数
+#[napi]
+pub fn set_handle_tracking(enabled: bool) {
+  handles::set_tracking(enabled)
+}
+
 #[napi]
 pub fn get_version() -> Result<i32> {
   let lib = get_library()?;
@@ -73,22 +139,28 @@ pub fn get_version() -> Result<i32> {
 }
 
 #[napi]
-pub fn new_packer(version: i32) -> Result<Packer> {
-  Packer::new(version)
+pub fn new_packer(version: i32, encoding: Option<TextEncoding>) -> Result<Packer> {
+  let mut packer = Packer::new(version)?;
+  packer.set_encoding(encoding.unwrap_or_default());
+  Ok(packer)
 }
 
+/**
+ * 创建解包器，默认复制 buffer
+ * options.zeroCopy 为 true 时不复制，解包器持有 buffer 的引用直到释放
+ */
 #[napi]
-pub fn new_unpacker(buffer: Buffer) -> Result<UnPacker> {
-  let len = buffer.len() as c_int;
-  let ptr = buffer.as_ptr() as *mut c_void;
-  UnPacker::new(ptr, len, 0x20)
+pub fn new_unpacker(buffer: Buffer, encoding: Option<TextEncoding>, options: Option<UnpackOptions>) -> Result<UnPacker> {
+  let mut unpacker = UnPacker::from_buffer(buffer, 0x20, options)?;
+  unpacker.set_encoding(encoding.unwrap_or_default());
+  Ok(unpacker)
 }
 
 #[napi]
-pub fn new_unpacker_v1(buffer: Buffer) -> Result<UnPacker> {
-  let len = buffer.len() as c_int;
-  let ptr = buffer.as_ptr() as *mut c_void;
-  UnPacker::new(ptr, len, 1)
+pub fn new_unpacker_v1(buffer: Buffer, encoding: Option<TextEncoding>, options: Option<UnpackOptions>) -> Result<UnPacker> {
+  let mut unpacker = UnPacker::from_buffer(buffer, 1, options)?;
+  unpacker.set_encoding(encoding.unwrap_or_default());
+  Ok(unpacker)
 }
 
 #[napi]
@@ -113,12 +185,30 @@ pub struct T2Header {
   pub company_id: Option<i32>,
 }
 
+#[napi(object)]
+pub struct SendOptions {
+  /** 严格模式，有损或不支持的值直接报错，默认 false */
+  pub strict: Option<bool>,
+  /** 关联 id，写入请求的 AppReserved，由后端在应答中带回，并出现在 on('trace') 的记录中 */
+  pub trace_id: Option<String>,
+  /**
+   * 声明为 'D' 的字段接受十进制字符串（如 '123.45'），按字段的 scale 打包，默认 false
+   * 'D' 字段以 double 传输，只能精确表示 15 位有效数字；小数位超过 scale 或有效数字超过 15 位时报错
+   */
+  pub decimal: Option<bool>,
+}
+
 #[napi]
 pub struct T2SDK {
   #[napi(skip)]
   pub config: Config,
   #[napi(skip)]
   pub connection: Option<Connection>,
+  on_message: Arc<Listeners<UnsolicitedMessage>>,
+  on_trace: Arc<Listeners<RequestTrace>>,
+  msg_center: Arc<MsgCenter>,
+  server: Arc<Server>,
+  metrics: Arc<Metrics>,
 }
 
 #[napi]
@@ -126,97 +216,67 @@ impl T2SDK {
   #[napi(constructor)]
   pub fn new() -> Result<Self> {
     let config = Config::new()?;
-    Ok(Self { config, connection: None })
+    Ok(Self {
+      config,
+      connection: None,
+      on_message: Arc::new(Listeners::new()),
+      on_trace: Arc::new(Listeners::new()),
+      msg_center: Arc::new(MsgCenter::new()),
+      server: Arc::new(Server::new()),
+      metrics: Arc::new(Metrics::new()),
+    })
   }
 
-  #[napi]
-  pub fn set_config(&self, config: Object) -> Result<()> {
-    let top_keys = Object::keys(&config)?;
-    for section in top_keys {
-      if let Some(section_obj) = config.get::<_, Object>(&section)? {
-        let keys = Object::keys(&section_obj)?;
-
-        for key in keys {
-          if let Some(string_val) = section_obj.get::<_, String>(&key)? {
-            self
-              .config
-              .set_string(section.to_string(), key.to_string(), string_val.to_string())?;
-          } else if let Some(int_val) = section_obj.get::<_, i32>(&key)? {
-            self.config.set_int(section.to_string(), key.to_string(), int_val)?;
-          }
-        }
-      }
-    }
-    Ok(())
+  /**
+   * 设置配置，格式与 t2sdk.ini 一致，如 `{ t2sdk: { servers, license_file }, safe: { safe_level } }`
+   * 可分多次设置，只校验本次传入的配置项（取值类型、地址格式、许可证文件是否存在等），必填项在 connect 时检查
+   * 未知的配置项与 Config.fromFile 一致：照常写入并输出警告
+   */
+  #[napi(ts_args_type = "config: { t2sdk?: Partial<T2SdkSection>, safe?: SafeSection }")]
+  pub fn set_config(&mut self, config: Object) -> Result<()> {
+    let entries = config_schema::entries_from_object(&config)?;
+    config_schema::check_entries(&entries)?;
+    self.config.apply_entries(&entries)
   }
 
-  fn pack_object(&self, obj: &serde_json::Map<String, serde_json::Value>, packer: &Packer, encoding: i8) -> Result<()> {
-    for (key, value) in obj {
-      if value.is_string() {
-        if encoding == 0 {
-          let str_val = value.as_str().unwrap();
-          let gbk_bytes = encoding::all::GBK.encode(&str_val, encoding::EncoderTrap::Strict).unwrap();
-          let buf = Buffer::from(gbk_bytes);
-          packer.add_field(key.to_string(), b'R' as i8, buf.len() as i32, 4)?;
-        } else {
-          let str_val = value.as_str().unwrap();
-          packer.add_field(key.to_string(), b'S' as i8, str_val.len() as i32, 4)?;
-        }
-      } else if value.is_number() {
-        if value.is_f64() {
-          packer.add_field(key.to_string(), b'D' as i8, 4, 4)?;
-        } else {
-          packer.add_field(key.to_string(), b'I' as i8, 4, 4)?;
-        }
-      } else if value.is_array() && value.as_array().unwrap().iter().all(|v| v.is_number()) {
-        let arr = value.as_array().unwrap();
-        packer.add_field(key.to_string(), b'R' as i8, arr.len() as i32, 4)?;
-      }
-    }
-
-    for (_, value) in obj {
-      if value.is_string() {
-        let str_val = value.as_str().unwrap();
-        if encoding == 0 {
-          let gbk_bytes = encoding::all::GBK.encode(&str_val, encoding::EncoderTrap::Strict).unwrap();
-          let buf = Buffer::from(gbk_bytes);
-          packer.add_raw(buf)?;
-        } else {
-          packer.add_str(str_val.to_string())?;
-        }
-      } else if value.is_number() {
-        if value.is_i64() {
-          packer.add_int(value.as_i64().unwrap() as i32)?;
-        } else if value.is_u64() {
-          packer.add_int(value.as_u64().unwrap() as i32)?;
-        } else if value.is_f64() {
-          packer.add_double(value.as_f64().unwrap() as f64)?;
-        }
-      } else if value.is_array() && value.as_array().unwrap().iter().all(|v| v.is_number()) {
-        let arr = value.as_array().unwrap();
-        let bytes: Vec<u8> = arr.iter().filter_map(|v| v.as_u64().map(|n| n as u8)).collect();
-        let buf: Buffer = Buffer::from(bytes);
-        packer.add_raw(buf)?;
-      }
+  /**
+   * 从 ini 文件加载配置，文件按当前文本编码读取
+   * @param path ini 文件路径
+   */
+  #[napi]
+  pub fn load_config(&mut self, path: String) -> Result<()> {
+    if self.is_connected() {
+      return Err(napi::Error::from_reason("Cannot load config while connected".to_string()));
     }
-
+    self.config = Config::from_file(path, Some(self.config.encoding()))?;
     Ok(())
   }
 
-  #[napi]
-  pub async fn send(&self, header: T2Header, body: String) -> Result<BizMessage> {
+  /**
+   * 发送业务请求，body 为 JS 对象（也兼容 JSON 字符串）
+   * - `{ datasets: [{ name, returnCode, fields?, rows: [...] }] }` 多结果集多行，未指定 fields 时取所有行键的并集
+   * - `{ ... }` 单结果集单行；值为对象的键各自作为一个结果集
+   * - `[{ ... }]` 每个元素为一个无名结果集
+   *
+   * `Buffer` 按 'R' 字段打包，`Date` 按字段类型转换
+   * （'I' 为 YYYYMMDD，'D' 为毫秒时间戳，'S' 为 `YYYY-MM-DD HH:mm:ss`，未声明类型时按 'I'）
+   *
+   * 默认为宽松模式：布尔按 'C' 打包为 '0'/'1'，null 打包为空值，超出 int32 的 `BigInt` 按字符串打包，
+   * 目标编码无法表示的字符替换为 '?'，嵌套对象/数组所在的列被忽略；
+   * 数字字符串写入 'I'/'D' 字段时按数值打包；其他与声明类型不符的值（如非数字字符串写入 'I' 字段）或声明为 'I' 的字段溢出仍会报错。
+   * `options.strict` 为 true 时不做上述转换，返回列出每个问题路径和原因的错误
+   * `options.decimal` 为 true 时声明为 'D' 的字段可传十进制字符串，避免 number 的舍入误差
+   */
+  #[napi(ts_args_type = "header: T2Header, body: object | string, options?: SendOptions")]
+  pub async fn send(&self, header: T2Header, body: pack::SendBody, options: Option<SendOptions>) -> Result<BizMessage> {
     if !self.is_connected() {
       return Err(napi::Error::from_reason("Not connected".to_string()));
     }
 
-    let json_value: serde_json::Value = match serde_json::from_str(&body) {
-      Ok(value) => value,
-      Err(_) => return Err(napi::Error::from_reason("Invalid JSON format".to_string())),
-    };
-
-    if !json_value.is_object() && !json_value.is_array() {
-      return Err(napi::Error::from_reason("JSON must be an object or array".to_string()));
-    }
+    let encoding = self.config.encoding();
+    let (strict, decimal, trace_id) = options.map_or((None, None, None), |o| (o.strict, o.decimal, o.trace_id));
+    let trace = self.new_trace(trace_id.clone(), header.function_no);
+    let datasets = pack::parse_body(&body.0, strict.unwrap_or(false), decimal.unwrap_or(false), encoding)?;
 
     let biz_message = BizMessage::new()?;
     biz_message.set_packet_type(REQUEST_PACKET)?;
@@ -235,75 +295,261 @@ impl T2SDK {
     }
     let packer = Packer::new(PACKER_VERSION_V2)?;
     packer.begin_pack()?;
-
-    // 处理JSON对象
-    if json_value.is_object() {
-      let obj = json_value.as_object().unwrap();
-
-      // 检查是否为简单包（不包含嵌套对象）
-      let is_simple_pack = obj.values().all(|v| !v.is_object() || v.is_null());
-
-      if is_simple_pack {
-        self.pack_object(obj, &packer, 0)?;
-      } else {
-        // 处理复杂包（包含嵌套对象）
-        for (key, value) in obj {
-          if value.is_object() {
-            packer.new_dataset(key.to_string(), 0)?;
-            if let Some(nested_obj) = value.as_object() {
-              self.pack_object(nested_obj, &packer, 0)?;
-            }
-          }
-        }
-      }
-    } else if json_value.is_array() {
-      // 处理JSON数组
-      let arr = json_value.as_array().unwrap();
-      for value in arr {
-        if let Some(obj) = value.as_object() {
-          packer.new_dataset("".to_string(), 0)?;
-          self.pack_object(obj, &packer, 0)?;
-        }
-      }
-    }
+    pack::write_datasets(&packer, &datasets)?;
     packer.end_pack()?;
     let content = packer.get_pack_buf()?;
     biz_message.set_content(content)?;
+    if let Some(trace_id) = trace_id {
+      biz_message.set_app_reserved(Buffer::from(trace_id.into_bytes()))?;
+    }
+    if let Some(trace) = &trace {
+      trace.span("pack");
+    }
     let message = biz_message.get_ptr();
 
-    let (tx, rx) = tokio::sync::oneshot::channel::<BizMessage>();
-
-    {
+    let response = {
       let conn_guard = self.connection.as_ref();
       let conn = conn_guard.as_ref().unwrap();
+      conn.call(message, encoding, std::time::Duration::from_secs(30), trace)?
+    };
+    response.await
+  }
 
-      conn.send_biz_msg(message, move |response_msg| {
-        let message = BizMessage::new_form_ptr(response_msg);
-        let _ = tx.send(message);
-      })?;
-    }
-
-    match tokio::time::timeout(std::time::Duration::from_secs(30), rx).await {
-      Ok(Ok(message)) => Ok(message),
-      Ok(Err(_)) => Err(napi::Error::from_reason("Callback channel closed".to_string())),
-      Err(_) => Err(napi::Error::from_reason("Callback timeout".to_string())),
-    }
+  /**
+   * 预编译 functionNo 请求的字段列表，返回可重复发送的模板
+   * 模板复用打包器和消息，send 时只按声明的类型写入取值；每个字段都需声明 type
+   * 未声明 width 的 'S'、'R' 字段宽度为 255，取值超过宽度时 send 报错
+   * 模板持有 SDK 对象，断开后 send 报错，重新连接后可继续使用
+   */
+  #[napi(
+    ts_args_type = "functionNo: number, fieldSchema: Array<{ name: string, type: 'I' | 'D' | 'C' | 'S' | 'R', width?: number, scale?: number }>, options?: TemplateOptions"
+  )]
+  pub fn compile_template(
+    &self,
+    reference: Reference<T2SDK>,
+    function_no: i32,
+    field_schema: pack::PackInput,
+    options: Option<TemplateOptions>,
+  ) -> Result<PackTemplate> {
+    PackTemplate::new(reference, function_no, &field_schema.0, options)
   }
 
+  /// 连接服务器，连接前校验配置的必填项（servers、license_file 等），校验不通过时只记录警告，以 SDK 的连接结果为准
   #[napi]
   pub fn connect(&mut self) -> Result<i32> {
+    // 相对路径由 SDK 解析，与本进程的工作目录不一定一致，校验结果不作为连接失败的依据
+    if let Err(e) = self.config.validate() {
+      log(LogLevel::Warn, Target::Connect, || {
+        format!("Config validation failed: {}", e.reason)
+      });
+    }
     let ptr = self.config.get_ptr();
-    let connection = Connection::new(ptr)?;
+    let connection = Connection::new(ptr, self.metrics.clone())?;
     let ret = connection.connect(5000)?;
     if ret != 0 {
-      let error_msg = connection.get_error_msg(ret)?;
+      let error_msg = connection.get_error_msg(ret, self.config.encoding());
       return Err(napi::Error::from_reason(error_msg));
     }
 
+    let listeners = self.on_message.clone();
+    let msg_center = self.msg_center.clone();
+    let server = self.server.clone();
+    let encoding = self.config.encoding();
+    let alive = connection.alive();
+    connection.set_unsolicited_handler(Box::new(move |conn, msg, h_send, reason| {
+      if msg_center.dispatch(conn, msg, encoding) || server.dispatch(conn, &alive, msg, encoding) {
+        return;
+      }
+      listeners.emit(UnsolicitedMessage::from_ptr(msg, h_send, reason, encoding));
+    }));
     self.connection = Some(connection);
     Ok(ret)
   }
 
+  /**
+   * 监听事件，在 connect 之前或之后调用均可
+   * - message: `(message: UnsolicitedMessage) => void`，连接上没有对应请求的业务消息，reason 为
+   *   'late'（请求超时后到达的应答）、'push'（消息中心 1.0 推送）、'request'（服务端发起的请求）或 'unknown'
+   * - trace: `(trace: RequestTrace) => void`，请求结束时的跟踪记录，有监听时才记录
+   */
+  #[napi(ts_args_type = "event: 'message' | 'trace', callback: (event: any) => void")]
+  pub fn on(&self, env: Env, event: String, callback: JsFunction) -> Result<()> {
+    match event.as_str() {
+      "message" => self.on_message.add(&env, callback),
+      "trace" => self.on_trace.add(&env, callback),
+      _ => Err(napi::Error::from_reason(format!("Unknown event: {}", event))),
+    }
+  }
+
+  /// 移除所有事件监听
+  #[napi]
+  pub fn remove_all_listeners(&self) {
+    self.on_message.clear();
+    self.on_trace.clear();
+  }
+
+  /**
+   * 消息中心 1.0 订阅，发送 620001 请求，订阅成功后 handler 收到该订阅类型的推送（620003）
+   * 连接期间自动应答消息中心的心跳（620000）
+   * @returns 订阅 id，用于 unsubscribeV1
+   */
+  #[napi(
+    ts_args_type = "params: SubscribeV1Params, handler: (message: PushMessageV1) => void",
+    ts_return_type = "Promise<number>"
+  )]
+  pub fn subscribe_v1(&self, env: Env, params: SubscribeV1Params, handler: JsFunction) -> Result<JsObject> {
+    let conn = self.connection.as_ref().ok_or_else(|| napi::Error::from_reason("Not connected"))?;
+    let encoding = self.config.encoding();
+    let timeout = std::time::Duration::from_millis(params.timeout_ms.unwrap_or(30000) as u64);
+    let listeners = Listeners::new();
+    listeners.add(&env, handler)?;
+    let subscription = self.msg_center.add(params, listeners, encoding)?;
+    let msg_center = self.msg_center.clone();
+    let answer = subscription
+      .request(MSGCENTER_FUNC_REG)
+      .and_then(|message| conn.call(message.get_ptr(), encoding, timeout, self.new_trace(None, MSGCENTER_FUNC_REG)));
+    let answer = match answer {
+      Ok(answer) => answer,
+      Err(e) => {
+        msg_center.remove(subscription.id());
+        return Err(e);
+      }
+    };
+    let id = subscription.id();
+    env.execute_tokio_future(
+      async move {
+        let result = answer.await.and_then(|answer| check_answer(&answer));
+        if result.is_err() {
+          msg_center.remove(id);
+        }
+        result.map(|_| id)
+      },
+      |env, id| env.create_uint32(id),
+    )
+  }
+
+  /// 取消消息中心 1.0 订阅，发送 620002 请求，本地的 handler 立即移除
+  #[napi]
+  pub async fn unsubscribe_v1(&self, id: u32, timeout_ms: Option<u32>) -> Result<()> {
+    let subscription = self
+      .msg_center
+      .remove(id)
+      .ok_or_else(|| napi::Error::from_reason(format!("Subscription {} not found", id)))?;
+    let conn = self.connection.as_ref().ok_or_else(|| napi::Error::from_reason("Not connected"))?;
+    let message = subscription.request(MSGCENTER_FUNC_REG_CANCEL)?;
+    let timeout = std::time::Duration::from_millis(timeout_ms.unwrap_or(30000) as u64);
+    let trace = self.new_trace(None, MSGCENTER_FUNC_REG_CANCEL);
+    let answer = conn.call(message.get_ptr(), self.config.encoding(), timeout, trace)?.await?;
+    check_answer(&answer)
+  }
+
+  /**
+   * 处理发给本进程的 functionNo 请求，同一功能号重复注册时替换之前的 handler
+   * handler 返回应答体（格式与 send 的 body 相同）或其 Promise，返回 undefined 时应答空业务体；
+   * handler 抛出异常或 Promise 被拒绝时以 errorNo、returnCode 为 -1 和异常信息应答
+   */
+  #[napi(
+    ts_args_type = "functionNo: number, handler: (request: ServeRequest) => object | string | undefined | Promise<object | string | undefined>"
+  )]
+  pub fn serve(&self, env: Env, function_no: i32, handler: JsFunction) -> Result<()> {
+    self.server.add(&env, function_no, handler)
+  }
+
+  /// 移除 functionNo 的请求处理函数，之后的请求交给 on('message')
+  #[napi]
+  pub fn unserve(&self, function_no: i32) -> bool {
+    self.server.remove(function_no)
+  }
+
+  /// 获取按功能号统计的请求计数、往返耗时和连接状态
+  #[napi]
+  pub fn get_metrics(&self) -> SdkMetrics {
+    self.metrics.snapshot(self.connection.as_ref().map_or(0, |c| c.get_status()))
+  }
+
+  /// 以 Prometheus 文本格式获取 getMetrics 的内容
+  #[napi]
+  pub fn get_prometheus_metrics(&self) -> String {
+    metrics::to_prometheus(&self.get_metrics())
+  }
+
+  /**
+   * 设置文本编码，作用于请求打包、应答的错误信息和连接错误信息
+   * @param encoding 默认为 GBK，可选 GB18030、UTF-8
+   */
+  #[napi]
+  pub fn set_encoding(&mut self, encoding: TextEncoding) {
+    self.config.set_encoding(encoding);
+  }
+
+  /// 获取文本编码
+  #[napi]
+  pub fn get_encoding(&self) -> TextEncoding {
+    self.config.encoding()
+  }
+
+  /**
+   * 创建文件更新对象，连接成功后服务端推送 topic 下可更新的文件列表
+   * 文件更新对象依附于当前连接，disconnect 后调用其方法报错 Connection closed
+   * @param topic 文件更新主题
+   * @param scanDir 本地扫描目录，用于比对已有文件
+   * @param updateDir 文件下载目录
+   */
+  #[napi]
+  pub fn new_file_updater(
+    &self,
+    topic: String,
+    scan_dir: String,
+    update_dir: String,
+    options: Option<FileUpdaterOptions>,
+  ) -> Result<FileUpdater> {
+    let conn = self
+      .connection
+      .as_ref()
+      .ok_or_else(|| napi::Error::from_reason("Not connected".to_string()))?;
+    let encoding = self.config.encoding();
+    FileUpdater::new(encoding, conn.alive(), options, |callback, timeout, dir_filter| {
+      conn.new_file_update(&topic, callback, &scan_dir, &update_dir, timeout, dir_filter, encoding)
+    })
+  }
+
+  /**
+   * 创建订阅者，订阅者依附于当前连接，disconnect 后调用其方法报错 Connection closed
+   * @param name 订阅者名字
+   */
+  #[napi]
+  pub fn new_subscriber(&self, name: String, options: Option<SubscriberOptions>) -> Result<Subscriber> {
+    let conn = self
+      .connection
+      .as_ref()
+      .ok_or_else(|| napi::Error::from_reason("Not connected".to_string()))?;
+    let encoding = self.config.encoding();
+    Subscriber::new(
+      encoding,
+      conn.alive(),
+      options,
+      |callback, timeout, init_recv_q_len, step_recv_q_len| {
+        conn.new_subscriber(callback, &name, timeout, init_recv_q_len, step_recv_q_len, encoding)
+      },
+    )
+  }
+
+  /**
+   * 获取消息中心的所有主题信息，结果按连接缓存
+   * @param options.force 为 true 时强制从服务端获取并刷新缓存
+   */
+  #[napi]
+  pub async fn get_topics(&self, options: Option<GetTopicsOptions>) -> Result<Vec<TopicInfo>> {
+    let conn = self
+      .connection
+      .as_ref()
+      .ok_or_else(|| napi::Error::from_reason("Not connected".to_string()))?;
+    let force = options.as_ref().and_then(|o| o.force).unwrap_or(false);
+    let timeout = options.and_then(|o| o.timeout_ms).unwrap_or(5000);
+    let encoding = self.config.encoding();
+    conn.get_topics(force, timeout, encoding).await
+  }
+
   #[napi(getter)]
   pub fn is_connected(&self) -> bool {
     self.connection.is_some()
@@ -317,3 +563,13 @@ impl T2SDK {
     Ok(())
   }
 }
+
+impl T2SDK {
+  /// 有 trace 监听时创建请求的跟踪记录
+  fn new_trace(&self, trace_id: Option<String>, function_no: i32) -> Option<Arc<Trace>> {
+    if self.on_trace.is_empty() {
+      return None;
+    }
+    Some(Arc::new(Trace::new(self.on_trace.clone(), trace_id, function_no)))
+  }
+}
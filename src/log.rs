@@ -0,0 +1,148 @@
+use napi::{Env, JsFunction, Result};
+use napi_derive::napi;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{OnceLock, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::event::Listeners;
+
+/// 日志级别，低于设置级别的日志不输出
+#[napi]
+#[derive(Debug, PartialEq, PartialOrd)]
+pub enum LogLevel {
+  Off,
+  Error,
+  Warn,
+  Info,
+  Debug,
+  Trace,
+}
+
+impl LogLevel {
+  fn from_u8(value: u8) -> Self {
+    match value {
+      0 => LogLevel::Off,
+      1 => LogLevel::Error,
+      2 => LogLevel::Warn,
+      3 => LogLevel::Info,
+      4 => LogLevel::Debug,
+      _ => LogLevel::Trace,
+    }
+  }
+
+  fn as_str(&self) -> &'static str {
+    match self {
+      LogLevel::Off => "OFF",
+      LogLevel::Error => "ERROR",
+      LogLevel::Warn => "WARN",
+      LogLevel::Info => "INFO",
+      LogLevel::Debug => "DEBUG",
+      LogLevel::Trace => "TRACE",
+    }
+  }
+}
+
+/// 日志来源
+#[derive(Clone, Copy)]
+pub(crate) enum Target {
+  /// 加载 t2sdk 动态库
+  Library,
+  Connect,
+  Send,
+  Receive,
+  /// SDK 回调
+  Callback,
+  /// 释放 SDK 对象
+  Drop,
+}
+
+const TARGETS: [&str; 6] = ["library", "connect", "send", "receive", "callback", "drop"];
+
+impl Target {
+  fn as_str(&self) -> &'static str {
+    TARGETS[*self as usize]
+  }
+}
+
+#[napi(object)]
+#[derive(Clone)]
+pub struct LogRecord {
+  pub level: LogLevel,
+  /** 'library' | 'connect' | 'send' | 'receive' | 'callback' | 'drop' */
+  pub target: String,
+  pub message: String,
+  /** 毫秒时间戳 */
+  pub timestamp: f64,
+}
+
+struct Logger {
+  level: AtomicU8,
+  // 按来源单独设置的级别
+  targets: RwLock<HashMap<&'static str, LogLevel>>,
+  sink: Listeners<LogRecord>,
+}
+
+fn logger() -> &'static Logger {
+  static LOGGER: OnceLock<Logger> = OnceLock::new();
+  LOGGER.get_or_init(|| Logger {
+    level: AtomicU8::new(LogLevel::Warn as u8),
+    targets: RwLock::new(HashMap::new()),
+    sink: Listeners::new(),
+  })
+}
+
+fn enabled(level: LogLevel, target: Target) -> bool {
+  let logger = logger();
+  let max = match logger.targets.read().unwrap().get(target.as_str()) {
+    Some(level) => *level,
+    None => LogLevel::from_u8(logger.level.load(Ordering::Relaxed)),
+  };
+  level != LogLevel::Off && level <= max
+}
+
+/**
+ * 输出一条日志，message 仅在级别启用时求值
+ * 设置了 setLogSink 时交给 JS 回调，否则写到 stderr
+ */
+pub(crate) fn log(level: LogLevel, target: Target, message: impl FnOnce() -> String) {
+  if !enabled(level, target) {
+    return;
+  }
+  let record = LogRecord {
+    level,
+    target: target.as_str().to_string(),
+    message: message(),
+    timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0.0, |d| d.as_millis() as f64),
+  };
+  let sink = &logger().sink;
+  if sink.is_empty() {
+    eprintln!("[t2sdk] {} {}: {}", record.level.as_str(), record.target, record.message);
+  } else {
+    sink.emit(record);
+  }
+}
+
+pub(crate) fn set_level(level: LogLevel, target: Option<String>) -> Result<()> {
+  let logger = logger();
+  match target {
+    None => logger.level.store(level as u8, Ordering::Relaxed),
+    Some(target) => {
+      let target = TARGETS
+        .iter()
+        .find(|t| **t == target)
+        .ok_or_else(|| napi::Error::from_reason(format!("Unknown log target: {}", target)))?;
+      logger.targets.write().unwrap().insert(target, level);
+    }
+  }
+  Ok(())
+}
+
+pub(crate) fn set_sink(env: &Env, sink: Option<JsFunction>) -> Result<()> {
+  let logger = logger();
+  logger.sink.clear();
+  if let Some(sink) = sink {
+    logger.sink.add(env, sink)?;
+  }
+  Ok(())
+}
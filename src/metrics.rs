@@ -0,0 +1,327 @@
+use napi_derive::napi;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// 往返耗时直方图的桶上界，单位毫秒
+const LATENCY_BUCKETS_MS: [f64; 13] = [
+  1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0, 30000.0,
+];
+
+#[derive(Default)]
+struct FunctionCounters {
+  sent: u64,
+  answered: u64,
+  timeouts: u64,
+  send_failures: u64,
+  in_flight: u64,
+  // 返回码非 0 的应答数，按返回码统计
+  business_errors: BTreeMap<i32, u64>,
+  // 各桶的计数，不累加
+  buckets: [u64; LATENCY_BUCKETS_MS.len()],
+  latency_count: u64,
+  latency_sum_ms: f64,
+}
+
+#[napi(object)]
+pub struct LatencyBucket {
+  /** 桶上界，单位毫秒 */
+  pub le: f64,
+  /** 耗时不大于 le 的应答数（累计） */
+  pub count: i64,
+}
+
+#[napi(object)]
+pub struct ReturnCodeCount {
+  pub return_code: i32,
+  pub count: i64,
+}
+
+#[napi(object)]
+pub struct FunctionMetrics {
+  pub function_no: i32,
+  /** 已发送的请求数 */
+  pub sent: i64,
+  /** 收到应答的请求数 */
+  pub answered: i64,
+  /** 等待应答超时的请求数 */
+  pub timeouts: i64,
+  /** 发送失败的请求数 */
+  pub send_failures: i64,
+  /** 等待应答中的请求数 */
+  pub in_flight: i64,
+  /** 返回码非 0 的应答 */
+  pub business_errors: Vec<ReturnCodeCount>,
+  /** 往返耗时，从发送到收到应答 */
+  pub latency_buckets: Vec<LatencyBucket>,
+  pub latency_count: i64,
+  pub latency_sum_ms: f64,
+}
+
+#[napi(object)]
+pub struct SdkMetrics {
+  /** 连接状态，见 ConnectionStatus，未连接时为 0 */
+  pub connection_status: i32,
+  /** 所有功能号等待应答中的请求数 */
+  pub in_flight: i64,
+  pub functions: Vec<FunctionMetrics>,
+}
+
+/// 按功能号统计请求的发送、应答和耗时
+pub(crate) struct Metrics {
+  functions: Mutex<BTreeMap<i32, FunctionCounters>>,
+}
+
+impl Metrics {
+  pub fn new() -> Self {
+    Metrics {
+      functions: Mutex::new(BTreeMap::new()),
+    }
+  }
+
+  fn update(&self, function_no: i32, f: impl FnOnce(&mut FunctionCounters)) {
+    f(self.functions.lock().unwrap().entry(function_no).or_default());
+  }
+
+  /// 在发送之前调用，避免应答先于 in_flight 计数到达
+  pub fn started(&self, function_no: i32) {
+    self.update(function_no, |c| c.in_flight += 1);
+  }
+
+  /// SDK 接受请求后调用
+  pub fn sent(&self, function_no: i32) {
+    self.update(function_no, |c| c.sent += 1);
+  }
+
+  /// 撤销 started 的 in_flight 计数
+  pub fn send_failed(&self, function_no: i32) {
+    self.update(function_no, |c| {
+      c.in_flight = c.in_flight.saturating_sub(1);
+      c.send_failures += 1;
+    });
+  }
+
+  pub fn answered(&self, function_no: i32, elapsed: Duration, return_code: i32) {
+    let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+    self.update(function_no, |c| {
+      c.answered += 1;
+      c.in_flight = c.in_flight.saturating_sub(1);
+      if return_code != 0 {
+        *c.business_errors.entry(return_code).or_default() += 1;
+      }
+      if let Some(bucket) = LATENCY_BUCKETS_MS.iter().position(|le| elapsed_ms <= *le) {
+        c.buckets[bucket] += 1;
+      }
+      c.latency_count += 1;
+      c.latency_sum_ms += elapsed_ms;
+    });
+  }
+
+  pub fn timed_out(&self, function_no: i32) {
+    self.update(function_no, |c| {
+      c.timeouts += 1;
+      c.in_flight = c.in_flight.saturating_sub(1);
+    });
+  }
+
+  pub fn snapshot(&self, connection_status: i32) -> SdkMetrics {
+    let functions = self.functions.lock().unwrap();
+    let functions: Vec<FunctionMetrics> = functions
+      .iter()
+      .map(|(function_no, c)| {
+        let mut cumulative = 0;
+        FunctionMetrics {
+          function_no: *function_no,
+          sent: c.sent as i64,
+          answered: c.answered as i64,
+          timeouts: c.timeouts as i64,
+          send_failures: c.send_failures as i64,
+          in_flight: c.in_flight as i64,
+          business_errors: c
+            .business_errors
+            .iter()
+            .map(|(return_code, count)| ReturnCodeCount {
+              return_code: *return_code,
+              count: *count as i64,
+            })
+            .collect(),
+          latency_buckets: LATENCY_BUCKETS_MS
+            .iter()
+            .zip(c.buckets.iter())
+            .map(|(le, count)| {
+              cumulative += count;
+              LatencyBucket {
+                le: *le,
+                count: cumulative as i64,
+              }
+            })
+            .collect(),
+          latency_count: c.latency_count as i64,
+          latency_sum_ms: c.latency_sum_ms,
+        }
+      })
+      .collect();
+    SdkMetrics {
+      connection_status,
+      in_flight: functions.iter().map(|f| f.in_flight).sum(),
+      functions,
+    }
+  }
+}
+
+/// 按 Prometheus 文本格式输出，耗时单位为秒
+pub(crate) fn to_prometheus(metrics: &SdkMetrics) -> String {
+  let mut out = String::new();
+  write_counter(&mut out, "t2sdk_requests_sent_total", "Requests sent", metrics, |f| f.sent);
+  write_counter(&mut out, "t2sdk_requests_answered_total", "Requests answered", metrics, |f| {
+    f.answered
+  });
+  write_counter(
+    &mut out,
+    "t2sdk_requests_timeout_total",
+    "Requests timed out waiting for an answer",
+    metrics,
+    |f| f.timeouts,
+  );
+  write_counter(
+    &mut out,
+    "t2sdk_requests_send_failed_total",
+    "Requests that failed to send",
+    metrics,
+    |f| f.send_failures,
+  );
+
+  let _ = writeln!(
+    out,
+    "# HELP t2sdk_business_errors_total Answers with a non-zero return code\n# TYPE t2sdk_business_errors_total counter"
+  );
+  for f in &metrics.functions {
+    for e in &f.business_errors {
+      let _ = writeln!(
+        out,
+        "t2sdk_business_errors_total{{function_no=\"{}\",return_code=\"{}\"}} {}",
+        f.function_no, e.return_code, e.count
+      );
+    }
+  }
+
+  let _ = writeln!(
+    out,
+    "# HELP t2sdk_request_duration_seconds Round-trip latency from send to answer\n# TYPE t2sdk_request_duration_seconds histogram"
+  );
+  for f in &metrics.functions {
+    for b in &f.latency_buckets {
+      let _ = writeln!(
+        out,
+        "t2sdk_request_duration_seconds_bucket{{function_no=\"{}\",le=\"{}\"}} {}",
+        f.function_no,
+        b.le / 1000.0,
+        b.count
+      );
+    }
+    let _ = writeln!(
+      out,
+      "t2sdk_request_duration_seconds_bucket{{function_no=\"{}\",le=\"+Inf\"}} {}",
+      f.function_no, f.latency_count
+    );
+    let _ = writeln!(
+      out,
+      "t2sdk_request_duration_seconds_sum{{function_no=\"{}\"}} {}",
+      f.function_no,
+      f.latency_sum_ms / 1000.0
+    );
+    let _ = writeln!(
+      out,
+      "t2sdk_request_duration_seconds_count{{function_no=\"{}\"}} {}",
+      f.function_no, f.latency_count
+    );
+  }
+
+  let _ = writeln!(
+    out,
+    "# HELP t2sdk_requests_in_flight Requests waiting for an answer\n# TYPE t2sdk_requests_in_flight gauge"
+  );
+  for f in &metrics.functions {
+    let _ = writeln!(out, "t2sdk_requests_in_flight{{function_no=\"{}\"}} {}", f.function_no, f.in_flight);
+  }
+
+  let _ = writeln!(
+    out,
+    "# HELP t2sdk_connection_status Connection status flags, 0 when not connected\n# TYPE t2sdk_connection_status gauge\nt2sdk_connection_status {}",
+    metrics.connection_status
+  );
+  out
+}
+
+fn write_counter(out: &mut String, name: &str, help: &str, metrics: &SdkMetrics, value: impl Fn(&FunctionMetrics) -> i64) {
+  let _ = writeln!(out, "# HELP {} {}\n# TYPE {} counter", name, help, name);
+  for f in &metrics.functions {
+    let _ = writeln!(out, "{}{{function_no=\"{}\"}} {}", name, f.function_no, value(f));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn metrics() -> Metrics {
+    let metrics = Metrics::new();
+    for (elapsed_ms, return_code) in [(3, 0), (8, 0), (40, -1), (60_000, 0)] {
+      metrics.started(100);
+      metrics.sent(100);
+      metrics.answered(100, Duration::from_millis(elapsed_ms), return_code);
+    }
+    metrics.started(100);
+    metrics.send_failed(100);
+    metrics.started(200);
+    metrics.sent(200);
+    metrics.started(200);
+    metrics.sent(200);
+    metrics.timed_out(200);
+    metrics
+  }
+
+  #[test]
+  fn counts_sent_only_after_send_succeeds() {
+    let snapshot = metrics().snapshot(0);
+    let f = &snapshot.functions[0];
+    assert_eq!((f.sent, f.answered, f.send_failures, f.in_flight), (4, 4, 1, 0));
+    let f = &snapshot.functions[1];
+    assert_eq!((f.sent, f.timeouts, f.in_flight), (2, 1, 1));
+    assert_eq!(snapshot.in_flight, 1);
+  }
+
+  #[test]
+  fn latency_buckets_are_cumulative() {
+    let snapshot = metrics().snapshot(0);
+    let f = &snapshot.functions[0];
+    let counts: Vec<_> = f.latency_buckets.iter().map(|b| (b.le, b.count)).collect();
+    assert_eq!(&counts[..5], &[(1.0, 0), (5.0, 1), (10.0, 2), (25.0, 2), (50.0, 3)]);
+    // 超过最大桶上界的应答只计入 +Inf
+    assert_eq!(counts.last(), Some(&(30000.0, 3)));
+    assert_eq!(f.latency_count, 4);
+    assert_eq!(f.latency_sum_ms, 60_051.0);
+    assert_eq!(f.business_errors.len(), 1);
+    assert_eq!((f.business_errors[0].return_code, f.business_errors[0].count), (-1, 1));
+  }
+
+  #[test]
+  fn prometheus_output_uses_seconds() {
+    let text = to_prometheus(&metrics().snapshot(2));
+    for line in [
+      "# TYPE t2sdk_requests_sent_total counter",
+      "t2sdk_requests_sent_total{function_no=\"100\"} 4",
+      "t2sdk_requests_send_failed_total{function_no=\"100\"} 1",
+      "t2sdk_requests_timeout_total{function_no=\"200\"} 1",
+      "t2sdk_business_errors_total{function_no=\"100\",return_code=\"-1\"} 1",
+      "t2sdk_request_duration_seconds_bucket{function_no=\"100\",le=\"0.005\"} 1",
+      "t2sdk_request_duration_seconds_bucket{function_no=\"100\",le=\"+Inf\"} 4",
+      "t2sdk_request_duration_seconds_sum{function_no=\"100\"} 60.051",
+      "t2sdk_requests_in_flight{function_no=\"200\"} 1",
+      "t2sdk_connection_status 2",
+    ] {
+      assert!(text.lines().any(|l| l == line), "missing {:?} in\n{}", line, text);
+    }
+  }
+}
@@ -0,0 +1,242 @@
+use napi::bindgen_prelude::{Buffer, Either};
+use napi::Result;
+use napi_derive::napi;
+use std::collections::HashMap;
+use std::os::raw::{c_int, c_void};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::biz_message::{BizMessage, IBizMessage, IBizMessageRust};
+use crate::charset::TextEncoding;
+use crate::connection::{CConnectionInterface, IConnectionRust};
+use crate::def::*;
+use crate::event::Listeners;
+use crate::f2_packer::Packer;
+use crate::f2_unpacker::UnPacker;
+use crate::log::{log, LogLevel, Target};
+
+#[napi(object)]
+pub struct SubscribeV1Params {
+  /** 订阅类型 */
+  pub issue_type: i32,
+  /** 关键字段，打包为单行作为 KeyInfo，数字按 'I'、字符串按 'S' 打包 */
+  pub key_info: Option<HashMap<String, Either<String, i32>>>,
+  /** 附加数据 */
+  pub app_data: Option<Buffer>,
+  /** 序号 */
+  pub sequence_no: Option<i32>,
+  /** 等待应答的超时时间，默认 30000 毫秒 */
+  pub timeout_ms: Option<u32>,
+}
+
+/// 消息中心 1.0 的推送
+#[napi(object)]
+#[derive(Clone)]
+pub struct PushMessageV1 {
+  /** 订阅 id */
+  pub id: u32,
+  pub issue_type: i32,
+  pub sequence_no: i32,
+  /** 解包后的关键字段，取第一行 */
+  pub key_info: HashMap<String, String>,
+  /** 业务内容 */
+  pub content: Buffer,
+  pub app_data: Option<Buffer>,
+}
+
+pub(crate) struct V1Subscription {
+  id: u32,
+  issue_type: i32,
+  sequence_no: Option<i32>,
+  // 用于匹配推送的关键字段
+  key: HashMap<String, String>,
+  key_info: Vec<u8>,
+  app_data: Option<Vec<u8>>,
+  listeners: Listeners<PushMessageV1>,
+}
+
+impl V1Subscription {
+  pub fn id(&self) -> u32 {
+    self.id
+  }
+
+  fn matches(&self, issue_type: i32, key: &HashMap<String, String>) -> bool {
+    // 推送的关键字段中没有的字段不参与比较
+    issue_type == self.issue_type && self.key.iter().all(|(name, value)| key.get(name).is_none_or(|v| v.trim() == value))
+  }
+
+  /// 构造订阅或取消订阅的请求
+  pub fn request(&self, function_no: i32) -> Result<BizMessage> {
+    let message = BizMessage::new()?;
+    message.set_packet_type(REQUEST_PACKET)?;
+    message.set_function(function_no)?;
+    message.set_issue_type(self.issue_type)?;
+    if let Some(sequence_no) = self.sequence_no {
+      message.set_sequence_no(sequence_no)?;
+    }
+    if !self.key_info.is_empty() {
+      message.set_key_info(Buffer::from(self.key_info.as_slice()))?;
+    }
+    if let Some(app_data) = &self.app_data {
+      message.set_app_data(Buffer::from(app_data.as_slice()))?;
+    }
+    Ok(message)
+  }
+}
+
+/// T2SDK 上消息中心 1.0 的订阅，跨连接保留
+pub(crate) struct MsgCenter {
+  next_id: AtomicU32,
+  subscriptions: Mutex<Vec<Arc<V1Subscription>>>,
+}
+
+impl MsgCenter {
+  pub fn new() -> Self {
+    MsgCenter {
+      next_id: AtomicU32::new(1),
+      subscriptions: Mutex::new(Vec::new()),
+    }
+  }
+
+  /// 在发送请求之前登记订阅，保证应答之后的推送不会丢失
+  pub fn add(&self, params: SubscribeV1Params, listeners: Listeners<PushMessageV1>, encoding: TextEncoding) -> Result<Arc<V1Subscription>> {
+    let mut fields: Vec<(String, Either<String, i32>)> = params.key_info.into_iter().flatten().collect();
+    // HashMap 无序，按字段名排序保证打包结果稳定
+    fields.sort_by(|a, b| a.0.cmp(&b.0));
+    let key = fields
+      .iter()
+      .map(|(name, value)| {
+        let value = match value {
+          Either::A(s) => s.clone(),
+          Either::B(n) => n.to_string(),
+        };
+        (name.clone(), value)
+      })
+      .collect();
+    let subscription = Arc::new(V1Subscription {
+      id: self.next_id.fetch_add(1, Ordering::Relaxed),
+      issue_type: params.issue_type,
+      sequence_no: params.sequence_no,
+      key,
+      key_info: pack_key_info(&fields, encoding)?,
+      app_data: params.app_data.map(|b| b.to_vec()),
+      listeners,
+    });
+    self.subscriptions.lock().unwrap().push(subscription.clone());
+    Ok(subscription)
+  }
+
+  pub fn remove(&self, id: u32) -> Option<Arc<V1Subscription>> {
+    let mut subscriptions = self.subscriptions.lock().unwrap();
+    let index = subscriptions.iter().position(|s| s.id == id)?;
+    Some(subscriptions.remove(index))
+  }
+
+  /**
+   * 处理消息中心 1.0 的消息，在 SDK 回调线程中调用
+   * 自动应答心跳，按订阅类型和关键字段派发推送
+   * @returns 消息已处理时返回 true
+   */
+  pub fn dispatch(&self, connection: *mut IConnectionRust, msg: *mut IBizMessageRust, encoding: TextEncoding) -> bool {
+    let (function_no, packet_type) = unsafe { ((*msg).get_function(), (*msg).get_packet_type()) };
+    if function_no == MSGCENTER_FUNC_HEART && packet_type == REQUEST_PACKET {
+      log(LogLevel::Trace, Target::Callback, || {
+        "Answering message center heartbeat".to_string()
+      });
+      unsafe {
+        (*msg).change_req_2_ans_message();
+        (*connection).send_biz_msg(msg, 1);
+      }
+      return true;
+    }
+    if function_no != MSGCENTER_FUNC_SENDED {
+      return false;
+    }
+
+    let message = BizMessage::new_form_ptr(msg, encoding);
+    let issue_type = message.get_issue_type().unwrap_or_default();
+    let key_info = message.get_key_info().map(|b| decode_key_info(&b, encoding)).unwrap_or_default();
+    let subscriptions: Vec<Arc<V1Subscription>> = self
+      .subscriptions
+      .lock()
+      .unwrap()
+      .iter()
+      .filter(|s| s.matches(issue_type, &key_info))
+      .cloned()
+      .collect();
+    if subscriptions.is_empty() {
+      return false;
+    }
+
+    let sequence_no = message.get_sequence_no().unwrap_or_default();
+    let content = message.get_content().unwrap_or_else(|_| Buffer::from(Vec::new()));
+    let app_data = message.get_app_data().ok().filter(|b| !b.is_empty());
+    for subscription in subscriptions {
+      subscription.listeners.emit(PushMessageV1 {
+        id: subscription.id,
+        issue_type,
+        sequence_no,
+        key_info: key_info.clone(),
+        content: content.clone(),
+        app_data: app_data.clone(),
+      });
+    }
+    true
+  }
+}
+
+/// 订阅、取消订阅的应答出错时返回错误
+pub(crate) fn check_answer(answer: &BizMessage) -> Result<()> {
+  let error_no = answer.get_error_no()?;
+  let return_code = answer.get_return_code()?;
+  if error_no != 0 || return_code != 0 {
+    return Err(napi::Error::from_reason(format!(
+      "Message center request failed ({}): {}",
+      if error_no != 0 { error_no } else { return_code },
+      answer.get_error_info()?
+    )));
+  }
+  Ok(())
+}
+
+fn pack_key_info(fields: &[(String, Either<String, i32>)], encoding: TextEncoding) -> Result<Vec<u8>> {
+  if fields.is_empty() {
+    return Ok(Vec::new());
+  }
+  let mut packer = Packer::new(PACKER_VERSION_V2)?;
+  packer.set_encoding(encoding);
+  packer.begin_pack()?;
+  for (name, value) in fields {
+    match value {
+      Either::A(_) => packer.add_field_def(name, b'S', 255, 0)?,
+      Either::B(_) => packer.add_field_def(name, b'I', 0, 0)?,
+    };
+  }
+  for (_, value) in fields {
+    match value {
+      Either::A(s) => packer.add_str(s.clone())?,
+      Either::B(n) => packer.add_int(*n)?,
+    };
+  }
+  packer.end_pack()?;
+  Ok(packer.get_pack_buf()?.to_vec())
+}
+
+fn decode_key_info(data: &[u8], encoding: TextEncoding) -> HashMap<String, String> {
+  let mut key_info = HashMap::new();
+  if data.is_empty() {
+    return key_info;
+  }
+  let mut data = data.to_vec();
+  let Ok(mut unpacker) = UnPacker::new(data.as_mut_ptr() as *mut c_void, data.len() as c_int, 0) else {
+    return key_info;
+  };
+  unpacker.set_encoding(encoding);
+  unpacker.set_coerce(true);
+  for column in 0..unpacker.get_col_count().unwrap_or(0) {
+    if let (Ok(name), Ok(value)) = (unpacker.get_col_name(column), unpacker.get_str_by_index(column)) {
+      key_info.insert(name, value);
+    }
+  }
+  key_info
+}
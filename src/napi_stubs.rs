@@ -0,0 +1,28 @@
+//! 单元测试的可执行文件不在 Node 中运行，这里为测试引用到的 Node-API 符号提供桩函数
+//! 测试只覆盖不依赖 JS 值的逻辑，桩函数被调用时直接中止
+
+macro_rules! stubs {
+  ($($name:ident),* $(,)?) => {
+    $(
+      #[no_mangle]
+      extern "C" fn $name() -> ! {
+        eprintln!(concat!(stringify!($name), " called outside Node"));
+        std::process::abort()
+      }
+    )*
+  };
+}
+
+stubs!(
+  napi_call_threadsafe_function,
+  napi_create_error,
+  napi_create_string_utf8,
+  napi_delete_reference,
+  napi_get_and_clear_last_exception,
+  napi_get_reference_value,
+  napi_is_error,
+  napi_is_exception_pending,
+  napi_reference_unref,
+  napi_release_threadsafe_function,
+  napi_throw,
+);
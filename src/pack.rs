@@ -0,0 +1,1131 @@
+use napi::bindgen_prelude::FromNapiValue;
+use napi::{sys, JsBigInt, JsBuffer, JsDate, JsFunction, JsObject, JsString, JsTypedArray, JsUnknown, Result, TypedArrayType, ValueType};
+
+use crate::charset::TextEncoding;
+use crate::f2_packer::Packer;
+
+/// 嵌套层级上限，防止循环引用导致栈溢出
+const MAX_DEPTH: usize = 64;
+
+/// JS number 可精确表示的最大整数 (2^53)
+const MAX_SAFE_INTEGER: i128 = 1 << 53;
+
+/// 日期值，同时保留时间戳与本地时间各分量
+#[derive(Clone, Copy)]
+pub(crate) struct DateValue {
+  pub epoch_ms: f64,
+  pub year: i32,
+  pub month: i32,
+  pub day: i32,
+  pub hour: i32,
+  pub minute: i32,
+  pub second: i32,
+}
+
+impl DateValue {
+  /// YYYYMMDD 形式的整数日期
+  fn to_int(self) -> i32 {
+    self.year * 10000 + self.month * 100 + self.day
+  }
+
+  /// YYYY-MM-DD HH:mm:ss 形式的字符串
+  fn to_text(self) -> String {
+    format!(
+      "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+      self.year, self.month, self.day, self.hour, self.minute, self.second
+    )
+  }
+}
+
+/// 请求体中的值，对象保留键的原始顺序
+pub(crate) enum PackValue {
+  Null,
+  Bool(bool),
+  Int(i64),
+  BigInt(i128),
+  Double(f64),
+  String(String),
+  Raw(Vec<u8>),
+  Date(DateValue),
+  Array(Vec<PackValue>),
+  Object(Vec<(String, PackValue)>),
+}
+
+impl From<serde_json::Value> for PackValue {
+  fn from(value: serde_json::Value) -> Self {
+    match value {
+      serde_json::Value::Null => PackValue::Null,
+      serde_json::Value::Bool(b) => PackValue::Bool(b),
+      serde_json::Value::Number(n) => {
+        if let Some(i) = n.as_i64() {
+          PackValue::Int(i)
+        } else if let Some(u) = n.as_u64() {
+          PackValue::BigInt(u as i128)
+        } else {
+          PackValue::Double(n.as_f64().unwrap_or(0.0))
+        }
+      }
+      serde_json::Value::String(s) => PackValue::String(s),
+      serde_json::Value::Array(arr) => PackValue::Array(arr.into_iter().map(PackValue::from).collect()),
+      serde_json::Value::Object(obj) => PackValue::Object(obj.into_iter().map(|(k, v)| (k, PackValue::from(v))).collect()),
+    }
+  }
+}
+
+impl PackValue {
+  fn as_object(&self) -> Option<&[(String, PackValue)]> {
+    match self {
+      PackValue::Object(entries) => Some(entries),
+      _ => None,
+    }
+  }
+
+  fn kind(&self) -> &'static str {
+    match self {
+      PackValue::Null => "null",
+      PackValue::Bool(_) => "boolean",
+      PackValue::Int(_) | PackValue::BigInt(_) | PackValue::Double(_) => "number",
+      PackValue::String(_) => "string",
+      PackValue::Raw(_) => "Buffer",
+      PackValue::Date(_) => "Date",
+      PackValue::Array(_) => "array",
+      PackValue::Object(_) => "object",
+    }
+  }
+
+  fn is_object(&self) -> bool {
+    matches!(self, PackValue::Object(_))
+  }
+
+  /// 转换为 JSON，用于将复杂值写入字符串字段
+  fn to_json(&self) -> serde_json::Value {
+    match self {
+      PackValue::Null => serde_json::Value::Null,
+      PackValue::Bool(b) => serde_json::Value::Bool(*b),
+      PackValue::Int(i) => serde_json::Value::from(*i),
+      PackValue::BigInt(i) => serde_json::Value::String(i.to_string()),
+      PackValue::Double(f) => serde_json::Value::from(*f),
+      PackValue::String(s) => serde_json::Value::String(s.clone()),
+      PackValue::Raw(bytes) => serde_json::Value::from(bytes.clone()),
+      PackValue::Date(date) => serde_json::Value::String(date.to_text()),
+      PackValue::Array(arr) => serde_json::Value::Array(arr.iter().map(|v| v.to_json()).collect()),
+      PackValue::Object(entries) => serde_json::Value::Object(entries.iter().map(|(k, v)| (k.clone(), v.to_json())).collect()),
+    }
+  }
+}
+
+/**
+ * `send` 的请求体，可以是 JS 对象/数组，也可以是 JSON 字符串（兼容旧调用方式）
+ * - `Buffer`/`Uint8Array` 按 'R' 二进制字段打包
+ * - `BigInt` 按整数处理，超出 int32 时宽松模式按字符串打包，严格模式报错
+ * - `Date` 按字段类型转换：'I' 为 YYYYMMDD，'D' 为毫秒时间戳，'S' 为 `YYYY-MM-DD HH:mm:ss`
+ */
+pub struct SendBody(pub(crate) PackValue);
+
+impl FromNapiValue for SendBody {
+  unsafe fn from_napi_value(env: sys::napi_env, napi_val: sys::napi_value) -> Result<Self> {
+    let value = JsUnknown::from_napi_value(env, napi_val)?;
+    match value.get_type()? {
+      ValueType::String => {
+        let text = String::from_napi_value(env, napi_val)?;
+        match serde_json::from_str::<serde_json::Value>(&text) {
+          Ok(json) => Ok(SendBody(PackValue::from(json))),
+          Err(_) => Err(napi::Error::from_reason("Invalid JSON format".to_string())),
+        }
+      }
+      ValueType::Object => Ok(SendBody(from_js(value, "", 0)?.unwrap_or(PackValue::Null))),
+      _ => Err(napi::Error::from_reason("Body must be an object or a JSON string".to_string())),
+    }
+  }
+}
+
+/// Packer.fromRows/appendRows 的字段列表和行数据，按 send 请求体的规则转换
+pub struct PackInput(pub(crate) PackValue);
+
+impl FromNapiValue for PackInput {
+  unsafe fn from_napi_value(env: sys::napi_env, napi_val: sys::napi_value) -> Result<Self> {
+    let value = JsUnknown::from_napi_value(env, napi_val)?;
+    Ok(PackInput(from_js(value, "", 0)?.unwrap_or(PackValue::Null)))
+  }
+}
+
+fn call_date_getter(date: &JsObject, name: &str) -> Result<i32> {
+  let getter: JsFunction = date.get_named_property(name)?;
+  getter.call_without_args(Some(date))?.coerce_to_number()?.get_int32()
+}
+
+fn date_from_js(value: JsUnknown, path: &str) -> Result<DateValue> {
+  let date: JsDate = unsafe { value.cast() };
+  let epoch_ms = date.value_of()?;
+  if epoch_ms.is_nan() {
+    return Err(invalid_body(path, "invalid Date"));
+  }
+  let obj: JsObject = unsafe { date.into_unknown().cast() };
+  Ok(DateValue {
+    epoch_ms,
+    year: call_date_getter(&obj, "getFullYear")?,
+    month: call_date_getter(&obj, "getMonth")? + 1,
+    day: call_date_getter(&obj, "getDate")?,
+    hour: call_date_getter(&obj, "getHours")?,
+    minute: call_date_getter(&obj, "getMinutes")?,
+    second: call_date_getter(&obj, "getSeconds")?,
+  })
+}
+
+fn bytes_from_typed_array(value: JsUnknown, path: &str) -> Result<Vec<u8>> {
+  let array: JsTypedArray = unsafe { value.cast() };
+  let info = array.into_value()?;
+  match info.typedarray_type {
+    TypedArrayType::Uint8 | TypedArrayType::Uint8Clamped | TypedArrayType::Int8 => {
+      let buffer = info.arraybuffer.into_value()?;
+      Ok(buffer[info.byte_offset..info.byte_offset + info.length].to_vec())
+    }
+    _ => Err(invalid_body(path, "only Uint8Array typed arrays can be packed")),
+  }
+}
+
+/// 遍历 JS 值构造 PackValue，undefined、函数、Symbol 返回 None（与 JSON.stringify 一致）
+fn from_js(value: JsUnknown, path: &str, depth: usize) -> Result<Option<PackValue>> {
+  if depth > MAX_DEPTH {
+    return Err(invalid_body(path, "nesting is too deep (circular reference?)"));
+  }
+  let packed = match value.get_type()? {
+    ValueType::Undefined | ValueType::Function | ValueType::Symbol | ValueType::External => return Ok(None),
+    ValueType::Null => PackValue::Null,
+    ValueType::Boolean => PackValue::Bool(value.coerce_to_bool()?.get_value()?),
+    ValueType::Number => {
+      let n = value.coerce_to_number()?.get_double()?;
+      if n.is_finite() && n.fract() == 0.0 && (n.abs() as i128) <= MAX_SAFE_INTEGER {
+        PackValue::Int(n as i64)
+      } else {
+        PackValue::Double(n)
+      }
+    }
+    ValueType::String => {
+      let s: JsString = unsafe { value.cast() };
+      PackValue::String(s.into_utf8()?.into_owned()?)
+    }
+    ValueType::BigInt => {
+      let mut big: JsBigInt = unsafe { value.cast() };
+      match big.get_i128()? {
+        (n, true) => PackValue::BigInt(n),
+        _ => return Err(invalid_body(path, "BigInt is out of range")),
+      }
+    }
+    ValueType::Object => {
+      if value.is_buffer()? {
+        let buffer: JsBuffer = unsafe { value.cast() };
+        PackValue::Raw(buffer.into_value()?.to_vec())
+      } else if value.is_typedarray()? {
+        PackValue::Raw(bytes_from_typed_array(value, path)?)
+      } else if value.is_date()? {
+        PackValue::Date(date_from_js(value, path)?)
+      } else if value.is_array()? {
+        let arr: JsObject = unsafe { value.cast() };
+        let len = arr.get_array_length()?;
+        let mut items = Vec::with_capacity(len as usize);
+        for i in 0..len {
+          let item = arr.get_element::<JsUnknown>(i)?;
+          items.push(from_js(item, &format!("{}[{}]", path, i), depth + 1)?.unwrap_or(PackValue::Null));
+        }
+        PackValue::Array(items)
+      } else {
+        let obj: JsObject = unsafe { value.cast() };
+        let keys = obj.get_property_names()?;
+        let len = keys.get_array_length()?;
+        let mut entries = Vec::with_capacity(len as usize);
+        for i in 0..len {
+          let key = keys.get_element::<JsString>(i)?.into_utf8()?.into_owned()?;
+          let item = obj.get_named_property::<JsUnknown>(&key)?;
+          if let Some(item) = from_js(item, &join_path(path, &key), depth + 1)? {
+            entries.push((key, item));
+          }
+        }
+        PackValue::Object(entries)
+      }
+    }
+    #[allow(unreachable_patterns)]
+    _ => return Ok(None),
+  };
+  Ok(Some(packed))
+}
+
+/// 字段定义
+#[derive(Clone)]
+pub(crate) struct FieldDef {
+  pub name: String,
+  pub field_type: u8,
+  pub width: i32,
+  pub scale: i32,
+}
+
+/// 转换后的单元格值，字符串已按目标编码转换
+pub(crate) enum Cell {
+  Int(i32),
+  Double(f64),
+  Char(u8),
+  Str(Vec<u8>),
+  Raw(Vec<u8>),
+}
+
+impl Cell {
+  pub(crate) fn len(&self) -> usize {
+    match self {
+      Cell::Str(bytes) | Cell::Raw(bytes) => bytes.len(),
+      _ => 0,
+    }
+  }
+}
+
+/// 待打包的结果集，rows 中每一行的值与 fields 一一对应
+pub(crate) struct Dataset {
+  pub name: Option<String>,
+  pub return_code: i32,
+  pub fields: Vec<FieldDef>,
+  pub rows: Vec<Vec<Cell>>,
+}
+
+fn invalid_body(path: &str, reason: &str) -> napi::Error {
+  napi::Error::from_reason(format!("Invalid body at {}: {}", path, reason))
+}
+
+fn join_path(path: &str, key: &str) -> String {
+  if path.is_empty() {
+    key.to_string()
+  } else {
+    format!("{}.{}", path, key)
+  }
+}
+
+/**
+ * 打包上下文
+ * - 严格模式：有损或不支持的值记录为问题，检查完整个请求体后统一报错
+ * - 宽松模式：按约定转换（布尔为 'C' 的 '0'/'1'，null 为空值，超出 int32 的 BigInt 为字符串，
+ *   数字字符串写入 'I'/'D' 字段时按数值打包，目标编码无法表示的字符替换为 '?'，嵌套对象/数组所在的列忽略），
+ *   无法转换的值（如声明为 'I' 的字段溢出、非数字字符串写入 'I'/'D' 字段）直接报错
+ * - decimal 为 true 时 'D' 字段接受十进制字符串
+ */
+struct PackContext {
+  strict: bool,
+  decimal: bool,
+  encoding: TextEncoding,
+  issues: Vec<String>,
+}
+
+impl PackContext {
+  fn new(strict: bool, decimal: bool, encoding: TextEncoding) -> Self {
+    PackContext {
+      strict,
+      decimal,
+      encoding,
+      issues: Vec::new(),
+    }
+  }
+
+  /// 有损但可以转换的值，仅在严格模式下记录
+  fn lossy(&mut self, path: &str, reason: &str) {
+    if self.strict {
+      self.issues.push(format!("{}: {}", path, reason));
+    }
+  }
+
+  /// 无法转换的值，严格模式下记录后继续检查，宽松模式下直接报错
+  fn fail(&mut self, path: &str, reason: &str) -> Result<()> {
+    if self.strict {
+      self.issues.push(format!("{}: {}", path, reason));
+      Ok(())
+    } else {
+      Err(invalid_body(path, reason))
+    }
+  }
+
+  fn finish(self) -> Result<()> {
+    if self.issues.is_empty() {
+      return Ok(());
+    }
+    Err(napi::Error::from_reason(format!(
+      "Invalid body ({} issues):\n  {}",
+      self.issues.len(),
+      self.issues.join("\n  ")
+    )))
+  }
+}
+
+fn encode_text(ctx: &mut PackContext, s: &str, path: &str) -> Vec<u8> {
+  match ctx.encoding.encode(s) {
+    Ok(bytes) => bytes,
+    Err(e) => {
+      ctx.lossy(path, &e.reason);
+      ctx.encoding.encode_lossy(s)
+    }
+  }
+}
+
+fn is_byte_array(value: &PackValue) -> bool {
+  match value {
+    PackValue::Array(arr) => arr.iter().all(|v| matches!(v, PackValue::Int(_))),
+    _ => false,
+  }
+}
+
+fn to_byte_array(ctx: &mut PackContext, arr: &[PackValue], path: &str) -> Result<Vec<u8>> {
+  let mut bytes = Vec::with_capacity(arr.len());
+  for (i, v) in arr.iter().enumerate() {
+    let byte = match v {
+      PackValue::Int(n) => u8::try_from(*n).ok(),
+      _ => None,
+    };
+    match byte {
+      Some(byte) => bytes.push(byte),
+      None => ctx.fail(&format!("{}[{}]", path, i), "byte array must contain only integers from 0 to 255")?,
+    }
+  }
+  Ok(bytes)
+}
+
+fn to_int32(ctx: &mut PackContext, n: i128, path: &str) -> Result<i32> {
+  match i32::try_from(n) {
+    Ok(n) => Ok(n),
+    Err(_) => ctx.fail(path, &format!("integer {} overflows int32", n)).map(|_| 0),
+  }
+}
+
+fn to_double(ctx: &mut PackContext, n: i128, path: &str) -> Result<f64> {
+  if n.abs() <= MAX_SAFE_INTEGER {
+    Ok(n as f64)
+  } else {
+    ctx
+      .fail(path, &format!("integer {} cannot be represented as a double", n))
+      .map(|_| 0.0)
+  }
+}
+
+/**
+ * 按字段精度解析十进制字符串，'D' 字段以 double 打包
+ * double 只能无损往返 15 位有效数字，小数位超过字段的 scale 或有效数字超过 15 位时无法精确打包，按无法转换的值处理
+ */
+fn to_decimal(ctx: &mut PackContext, s: &str, scale: i32, path: &str) -> Result<f64> {
+  let text = s.trim();
+  let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+  let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+  let valid = !(int_part.is_empty() && frac_part.is_empty())
+    && int_part.bytes().all(|b| b.is_ascii_digit())
+    && frac_part.bytes().all(|b| b.is_ascii_digit());
+  if !valid {
+    return ctx.fail(path, &format!("{:?} is not a decimal", s)).map(|_| 0.0);
+  }
+  let frac_part = frac_part.trim_end_matches('0');
+  let scale = scale.max(0) as usize;
+  if frac_part.len() > scale {
+    ctx.fail(path, &format!("{:?} has more than {} decimal places", s, scale))?;
+  }
+  let significant = format!("{}{}", int_part, frac_part);
+  let significant = significant.trim_start_matches('0').trim_end_matches('0');
+  if significant.len() > 15 {
+    ctx.fail(path, &format!("{:?} exceeds 15 significant digits", s))?;
+  }
+  Ok(text.parse().unwrap_or(0.0))
+}
+
+/// 值的类型与字段类型不符，按无法转换的值处理，不静默打包为空值
+fn mismatch(ctx: &mut PackContext, value: &PackValue, field_type: u8, path: &str) -> Result<()> {
+  let hint = match (value, field_type) {
+    (PackValue::String(_), b'D') if !ctx.decimal => ", set decimal to pack decimal strings",
+    _ => "",
+  };
+  ctx.fail(
+    path,
+    &format!("{} cannot be packed into '{}' field{}", value.kind(), field_type as char, hint),
+  )
+}
+
+/// 根据值推断字段类型，无法打包的值返回 None
+fn infer_type(value: &PackValue, strict: bool) -> Option<u8> {
+  match value {
+    // 字符串按目标编码以二进制方式打包
+    PackValue::String(_) | PackValue::Raw(_) => Some(b'R'),
+    PackValue::Int(n) if i32::try_from(*n).is_ok() => Some(b'I'),
+    // 超出 int32 的 number 仍可用 double 精确表示
+    PackValue::Int(_) | PackValue::Double(_) => Some(b'D'),
+    PackValue::BigInt(n) if strict || i32::try_from(*n).is_ok() => Some(b'I'),
+    PackValue::BigInt(_) => Some(b'S'),
+    PackValue::Bool(_) => Some(b'C'),
+    PackValue::Date(_) => Some(b'I'),
+    PackValue::Array(_) if is_byte_array(value) => Some(b'R'),
+    _ => None,
+  }
+}
+
+/// 合并同一列在不同行推断出的类型
+fn merge_type(current: Option<u8>, next: u8) -> u8 {
+  match (current, next) {
+    (None, t) => t,
+    (Some(a), b) if a == b => a,
+    (Some(b'I'), b'D') | (Some(b'D'), b'I') => b'D',
+    // 数字与字符串混合时统一按字符串处理
+    _ => b'S',
+  }
+}
+
+fn empty_cell(field_type: u8) -> Cell {
+  match field_type {
+    b'I' => Cell::Int(0),
+    b'D' => Cell::Double(0.0),
+    b'C' => Cell::Char(b' '),
+    b'R' => Cell::Raw(Vec::new()),
+    _ => Cell::Str(Vec::new()),
+  }
+}
+
+/// 按字段类型转换单元格，scale 为 'D' 字段的精度
+fn to_cell(ctx: &mut PackContext, field_type: u8, scale: i32, value: &PackValue, path: &str) -> Result<Cell> {
+  match value {
+    PackValue::Null => ctx.lossy(path, "null is packed as an empty value"),
+    PackValue::Bool(_) => ctx.lossy(path, "boolean is packed as '0'/'1'"),
+    _ => {}
+  }
+  let cell = match field_type {
+    b'I' => Cell::Int(match value {
+      PackValue::Null => 0,
+      PackValue::Bool(b) => *b as i32,
+      PackValue::Int(n) => to_int32(ctx, *n as i128, path)?,
+      PackValue::BigInt(n) => to_int32(ctx, *n, path)?,
+      PackValue::Double(f) if f.fract() == 0.0 && *f >= i32::MIN as f64 && *f <= i32::MAX as f64 => *f as i32,
+      PackValue::Double(f) => ctx.fail(path, &format!("{} is not an int32", f)).map(|_| 0)?,
+      PackValue::Date(date) => date.to_int(),
+      PackValue::String(s) if !ctx.strict => match s.trim().parse::<i64>() {
+        Ok(n) => to_int32(ctx, n as i128, path)?,
+        Err(_) => mismatch(ctx, value, field_type, path).map(|_| 0)?,
+      },
+      _ => mismatch(ctx, value, field_type, path).map(|_| 0)?,
+    }),
+    b'D' => Cell::Double(match value {
+      PackValue::Null => 0.0,
+      PackValue::Bool(b) => *b as i32 as f64,
+      PackValue::Int(n) => to_double(ctx, *n as i128, path)?,
+      PackValue::BigInt(n) => to_double(ctx, *n, path)?,
+      PackValue::Double(f) => *f,
+      PackValue::String(s) if ctx.decimal => to_decimal(ctx, s, scale, path)?,
+      PackValue::Date(date) => date.epoch_ms,
+      PackValue::String(s) if !ctx.strict => match s.trim().parse::<f64>() {
+        Ok(f) if f.is_finite() => f,
+        _ => mismatch(ctx, value, field_type, path).map(|_| 0.0)?,
+      },
+      _ => mismatch(ctx, value, field_type, path).map(|_| 0.0)?,
+    }),
+    b'C' => Cell::Char(match value {
+      PackValue::Null => b' ',
+      PackValue::Bool(b) => b'0' + *b as u8,
+      PackValue::String(s) => match s.as_bytes() {
+        [] => b' ',
+        [c] => *c,
+        [c, ..] => {
+          ctx.lossy(path, &format!("{:?} is truncated to a single char", s));
+          *c
+        }
+      },
+      PackValue::Int(n) => match u8::try_from(*n) {
+        Ok(c) => c,
+        Err(_) => ctx.fail(path, &format!("{} is not a char", n)).map(|_| b' ')?,
+      },
+      _ => mismatch(ctx, value, field_type, path).map(|_| b' ')?,
+    }),
+    b'R' => Cell::Raw(match value {
+      PackValue::Null => Vec::new(),
+      PackValue::Bool(b) => vec![b'0' + *b as u8],
+      PackValue::String(s) => encode_text(ctx, s, path),
+      PackValue::Raw(bytes) => bytes.clone(),
+      PackValue::Array(arr) if is_byte_array(value) => to_byte_array(ctx, arr, path)?,
+      PackValue::Date(date) => date.to_text().into_bytes(),
+      _ => mismatch(ctx, value, field_type, path).map(|_| Vec::new())?,
+    }),
+    _ => {
+      let text = match value {
+        PackValue::Null => String::new(),
+        PackValue::Bool(b) => (*b as i32).to_string(),
+        PackValue::String(s) => s.clone(),
+        PackValue::Int(n) => n.to_string(),
+        PackValue::BigInt(n) => n.to_string(),
+        PackValue::Double(f) => f.to_string(),
+        PackValue::Date(date) => date.to_text(),
+        PackValue::Raw(_) => ctx.fail(path, "Buffer can only be packed into 'R' fields").map(|_| String::new())?,
+        other => {
+          ctx.lossy(path, &format!("{} is packed as JSON text", other.kind()));
+          other.to_json().to_string()
+        }
+      };
+      Cell::Str(encode_text(ctx, &text, path))
+    }
+  };
+  Ok(cell)
+}
+
+fn parse_field_type(value: &PackValue, path: &str) -> Result<u8> {
+  match value {
+    PackValue::String(s) => match s.as_bytes() {
+      [t @ (b'I' | b'D' | b'C' | b'S' | b'R')] => Ok(*t),
+      _ => Err(invalid_body(path, "field type must be one of 'I', 'D', 'C', 'S', 'R'")),
+    },
+    _ => Err(invalid_body(path, "field type must be one of 'I', 'D', 'C', 'S', 'R'")),
+  }
+}
+
+fn get<'a>(obj: &'a [(String, PackValue)], key: &str) -> Option<&'a PackValue> {
+  obj.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+fn get_int(obj: &[(String, PackValue)], key: &str, path: &str) -> Result<Option<i32>> {
+  match get(obj, key) {
+    None | Some(PackValue::Null) => Ok(None),
+    Some(PackValue::Int(n)) => i32::try_from(*n)
+      .map(Some)
+      .map_err(|_| invalid_body(&join_path(path, key), &format!("integer {} overflows int32", n))),
+    Some(_) => Err(invalid_body(&join_path(path, key), "must be an int32")),
+  }
+}
+
+fn parse_fields(fields: &[PackValue], path: &str) -> Result<Vec<FieldDef>> {
+  let mut result = Vec::with_capacity(fields.len());
+  for (i, field) in fields.iter().enumerate() {
+    let field_path = format!("{}[{}]", path, i);
+    let def = match field {
+      PackValue::String(name) => FieldDef {
+        name: name.clone(),
+        field_type: 0,
+        width: 0,
+        scale: 4,
+      },
+      PackValue::Object(obj) => {
+        let name = match get(obj, "name") {
+          Some(PackValue::String(name)) => name,
+          _ => return Err(invalid_body(&field_path, "field name is required")),
+        };
+        let field_type = match get(obj, "type") {
+          Some(t) => parse_field_type(t, &format!("{}.type", field_path))?,
+          None => 0,
+        };
+        FieldDef {
+          name: name.to_string(),
+          field_type,
+          width: get_int(obj, "width", &field_path)?.unwrap_or(0),
+          scale: get_int(obj, "scale", &field_path)?.unwrap_or(4),
+        }
+      }
+      _ => return Err(invalid_body(&field_path, "field must be a name or { name, type, width, scale }")),
+    };
+    result.push(def);
+  }
+  Ok(result)
+}
+
+/// 未声明宽度的字段按类型取默认宽度，'S'、'R' 取最长值的长度
+pub(crate) fn default_width(field_type: u8, max_len: usize) -> i32 {
+  match field_type {
+    b'S' | b'R' => max_len.max(1) as i32,
+    b'C' => 1,
+    _ => 4,
+  }
+}
+
+/// 一行数据及其在请求体中的路径
+type Row<'a> = (String, &'a [(String, PackValue)]);
+
+/// 根据行数据补全字段类型和宽度；未声明字段时取所有行键的并集
+fn build_dataset(
+  ctx: &mut PackContext,
+  name: Option<String>,
+  return_code: i32,
+  fields: Option<Vec<FieldDef>>,
+  rows: &[Row],
+) -> Result<Dataset> {
+  let mut fields = match fields {
+    Some(fields) => fields,
+    None => {
+      let mut fields: Vec<FieldDef> = Vec::new();
+      for (_, row) in rows {
+        for (key, _) in row.iter() {
+          if !fields.iter().any(|f| &f.name == key) {
+            fields.push(FieldDef {
+              name: key.clone(),
+              field_type: 0,
+              width: 0,
+              scale: 4,
+            });
+          }
+        }
+      }
+      fields
+    }
+  };
+
+  // 宽松模式下无法打包的列（嵌套对象、非数字数组）直接忽略，严格模式下逐个报告
+  let strict = ctx.strict;
+  fields.retain_mut(|field| {
+    if field.field_type != 0 {
+      return true;
+    }
+    let mut inferred = None;
+    let mut packable = true;
+    for (_, row) in rows {
+      match get(row, &field.name) {
+        None | Some(PackValue::Null) => {}
+        Some(value) => match infer_type(value, strict) {
+          Some(t) => inferred = Some(merge_type(inferred, t)),
+          None => packable = false,
+        },
+      }
+    }
+    // 全部为空的列按空字符串打包，保证各行列数一致
+    field.field_type = inferred.unwrap_or(b'S');
+    packable || strict
+  });
+
+  let mut cells = Vec::with_capacity(rows.len());
+  for (path, row) in rows {
+    let mut cell_row = Vec::with_capacity(fields.len());
+    for field in &fields {
+      let cell = match get(row, &field.name) {
+        Some(value) => to_cell(ctx, field.field_type, field.scale, value, &join_path(path, &field.name))?,
+        // 行中缺少的键按空值打包
+        None => empty_cell(field.field_type),
+      };
+      cell_row.push(cell);
+    }
+    cells.push(cell_row);
+  }
+
+  for (index, field) in fields.iter_mut().enumerate() {
+    if field.width <= 0 {
+      field.width = default_width(field.field_type, cells.iter().map(|row| row[index].len()).max().unwrap_or(0));
+    }
+  }
+
+  Ok(Dataset {
+    name,
+    return_code,
+    fields,
+    rows: cells,
+  })
+}
+
+/// 解析 `['name', { name, type, width, scale }]` 形式的字段列表
+pub(crate) fn parse_field_list(fields: &PackValue) -> Result<Vec<FieldDef>> {
+  match fields {
+    PackValue::Array(fields) => parse_fields(fields, "fields"),
+    _ => Err(invalid_body("fields", "must be an array")),
+  }
+}
+
+/**
+ * 按字段列表转换多行数据，行可以是对象（按字段名取值）或数组（按字段顺序取值）
+ * 未声明类型的字段按各行的值推断，转换规则与 parse_body 相同
+ */
+pub(crate) fn parse_rows(fields: Vec<FieldDef>, rows: PackValue, strict: bool, decimal: bool, encoding: TextEncoding) -> Result<Dataset> {
+  let mut ctx = PackContext::new(strict, decimal, encoding);
+  let rows = match rows {
+    PackValue::Array(rows) => rows,
+    _ => return Err(invalid_body("rows", "must be an array of objects or arrays")),
+  };
+  let mut objects = Vec::with_capacity(rows.len());
+  for (i, row) in rows.into_iter().enumerate() {
+    let path = format!("rows[{}]", i);
+    let object = match row {
+      PackValue::Object(entries) => entries,
+      PackValue::Array(values) => {
+        if values.len() > fields.len() {
+          ctx.fail(&path, &format!("row has {} values but only {} fields", values.len(), fields.len()))?;
+        }
+        fields.iter().map(|f| f.name.clone()).zip(values).collect()
+      }
+      other => {
+        return Err(invalid_body(
+          &path,
+          &format!("row must be an object or array, got {}", other.kind()),
+        ))
+      }
+    };
+    objects.push((path, object));
+  }
+  let rows: Vec<Row> = objects.iter().map(|(path, object)| (path.clone(), object.as_slice())).collect();
+  let dataset = build_dataset(&mut ctx, None, 0, Some(fields), &rows)?;
+  ctx.finish()?;
+  Ok(dataset)
+}
+
+/**
+ * 按已声明类型的字段列表转换一行数据，行可以是对象（按字段名取值）或数组（按字段顺序取值）
+ * 不推断类型，缺少的字段按空值打包，转换规则与 parse_body 相同
+ */
+pub(crate) fn convert_row(fields: &[FieldDef], row: &PackValue, strict: bool, decimal: bool, encoding: TextEncoding) -> Result<Vec<Cell>> {
+  let mut ctx = PackContext::new(strict, decimal, encoding);
+  let mut cells = Vec::with_capacity(fields.len());
+  match row {
+    PackValue::Object(entries) => {
+      for field in fields {
+        cells.push(match get(entries, &field.name) {
+          Some(value) => to_cell(&mut ctx, field.field_type, field.scale, value, &join_path("values", &field.name))?,
+          None => empty_cell(field.field_type),
+        });
+      }
+    }
+    PackValue::Array(values) => {
+      if values.len() > fields.len() {
+        ctx.fail(
+          "values",
+          &format!("row has {} values but only {} fields", values.len(), fields.len()),
+        )?;
+      }
+      for (index, field) in fields.iter().enumerate() {
+        cells.push(match values.get(index) {
+          Some(value) => to_cell(&mut ctx, field.field_type, field.scale, value, &format!("values[{}]", index))?,
+          None => empty_cell(field.field_type),
+        });
+      }
+    }
+    other => {
+      return Err(invalid_body(
+        "values",
+        &format!("row must be an object or array, got {}", other.kind()),
+      ))
+    }
+  }
+  ctx.finish()?;
+  Ok(cells)
+}
+
+fn parse_dataset_spec(ctx: &mut PackContext, spec: &PackValue, path: &str) -> Result<Dataset> {
+  let obj = spec.as_object().ok_or_else(|| invalid_body(path, "dataset must be an object"))?;
+
+  let name = match get(obj, "name") {
+    None | Some(PackValue::Null) => String::new(),
+    Some(PackValue::String(s)) => s.clone(),
+    Some(_) => return Err(invalid_body(&format!("{}.name", path), "must be a string")),
+  };
+  let return_code = get_int(obj, "returnCode", path)?.unwrap_or(0);
+  let fields = match get(obj, "fields") {
+    None | Some(PackValue::Null) => None,
+    Some(PackValue::Array(fields)) => Some(parse_fields(fields, &format!("{}.fields", path))?),
+    Some(_) => return Err(invalid_body(&format!("{}.fields", path), "must be an array")),
+  };
+
+  let rows_path = format!("{}.rows", path);
+  let rows = match get(obj, "rows") {
+    Some(PackValue::Array(rows)) => rows,
+    _ => return Err(invalid_body(&rows_path, "must be an array of objects")),
+  };
+  let rows = rows
+    .iter()
+    .enumerate()
+    .map(|(i, row)| {
+      let row_path = format!("{}[{}]", rows_path, i);
+      match row.as_object() {
+        Some(obj) => Ok((row_path, obj)),
+        None => Err(invalid_body(&row_path, "row must be an object")),
+      }
+    })
+    .collect::<Result<Vec<_>>>()?;
+
+  build_dataset(ctx, Some(name), return_code, fields, &rows)
+}
+
+/// 判断是否为 `{ datasets: [...] }` 形式的多结果集请求体
+fn as_dataset_list(obj: &[(String, PackValue)]) -> Option<&Vec<PackValue>> {
+  match obj {
+    [(key, PackValue::Array(arr))] if key == "datasets" && arr.iter().all(|v| v.is_object()) => Some(arr),
+    _ => None,
+  }
+}
+
+/**
+ * 将请求体解析为结果集列表，支持以下格式：
+ * - `{ datasets: [{ name, returnCode, fields?, rows: [...] }] }` 多结果集，每个结果集多行
+ * - `{ a: 1, b: 'x' }` 单结果集单行
+ * - `{ ds1: { ... }, ds2: { ... } }` 每个对象值为一个结果集，单行
+ * - `[{ ... }, { ... }]` 每个元素为一个无名结果集，单行
+ *
+ * strict 为 true 时，有损或不支持的值不做转换，汇总所有问题的路径和原因后报错
+ * decimal 为 true 时，声明为 'D' 的字段接受十进制字符串，按字段的 scale 打包，无法精确打包时报错
+ */
+pub(crate) fn parse_body(body: &PackValue, strict: bool, decimal: bool, encoding: TextEncoding) -> Result<Vec<Dataset>> {
+  let mut ctx = PackContext::new(strict, decimal, encoding);
+  let datasets = parse_datasets(&mut ctx, body)?;
+  ctx.finish()?;
+  Ok(datasets)
+}
+
+fn parse_datasets(ctx: &mut PackContext, body: &PackValue) -> Result<Vec<Dataset>> {
+  match body {
+    PackValue::Object(obj) => {
+      if let Some(datasets) = as_dataset_list(obj) {
+        return datasets
+          .iter()
+          .enumerate()
+          .map(|(i, spec)| parse_dataset_spec(ctx, spec, &format!("datasets[{}]", i)))
+          .collect();
+      }
+
+      // 检查是否为简单包（不包含嵌套对象）
+      let is_simple_pack = obj.iter().all(|(_, v)| !v.is_object());
+      if is_simple_pack {
+        return Ok(vec![build_dataset(ctx, None, 0, None, &[(String::new(), obj)])?]);
+      }
+      let mut datasets = Vec::new();
+      for (key, value) in obj {
+        match value.as_object() {
+          Some(nested) => datasets.push(build_dataset(ctx, Some(key.to_string()), 0, None, &[(key.clone(), nested)])?),
+          None => ctx.lossy(key, "is ignored because sibling keys are datasets"),
+        }
+      }
+      Ok(datasets)
+    }
+    PackValue::Array(arr) => {
+      let mut datasets = Vec::new();
+      for (i, value) in arr.iter().enumerate() {
+        let path = format!("[{}]", i);
+        match value.as_object() {
+          Some(obj) => datasets.push(build_dataset(ctx, Some(String::new()), 0, None, &[(path, obj)])?),
+          None => ctx.lossy(&path, &format!("{} is ignored, datasets must be objects", value.kind())),
+        }
+      }
+      Ok(datasets)
+    }
+    _ => Err(napi::Error::from_reason("JSON must be an object or array".to_string())),
+  }
+}
+
+fn write_cell(packer: &Packer, cell: &Cell) -> Result<i32> {
+  match cell {
+    Cell::Int(n) => packer.add_int(*n),
+    Cell::Double(f) => packer.add_double(*f),
+    Cell::Char(c) => packer.add_char(*c as i8),
+    Cell::Str(bytes) => packer.add_str_bytes(bytes),
+    Cell::Raw(bytes) => packer.add_raw_bytes(bytes),
+  }
+}
+
+/// 将结果集写入打包器，调用前需先 begin_pack
+pub(crate) fn write_datasets(packer: &Packer, datasets: &[Dataset]) -> Result<()> {
+  for dataset in datasets {
+    if let Some(name) = &dataset.name {
+      packer.new_dataset(name.to_string(), dataset.return_code)?;
+    }
+    for field in &dataset.fields {
+      let ret = packer.add_field_def(&field.name, field.field_type, field.width, field.scale)?;
+      if ret < 0 {
+        return Err(napi::Error::from_reason(format!("Failed to add field {}", field.name)));
+      }
+    }
+    write_rows(packer, dataset)?;
+  }
+  Ok(())
+}
+
+/// 只写入结果集的行，字段需已添加到打包器
+pub(crate) fn write_rows(packer: &Packer, dataset: &Dataset) -> Result<()> {
+  for row in &dataset.rows {
+    write_row(packer, &dataset.fields, row)?;
+  }
+  Ok(())
+}
+
+/// 写入一行，cells 与 fields 一一对应
+pub(crate) fn write_row(packer: &Packer, fields: &[FieldDef], cells: &[Cell]) -> Result<()> {
+  for (field, cell) in fields.iter().zip(cells) {
+    if write_cell(packer, cell)? < 0 {
+      return Err(napi::Error::from_reason(format!("Failed to pack value of field {}", field.name)));
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn parse(body: serde_json::Value, strict: bool) -> Result<Vec<Dataset>> {
+    parse_body(&PackValue::from(body), strict, false, TextEncoding::Utf8)
+  }
+
+  fn rows(fields: serde_json::Value, rows: serde_json::Value) -> serde_json::Value {
+    json!({ "datasets": [{ "name": "ds", "fields": fields, "rows": rows }] })
+  }
+
+  fn field_types(dataset: &Dataset) -> Vec<(&str, char)> {
+    dataset.fields.iter().map(|f| (f.name.as_str(), f.field_type as char)).collect()
+  }
+
+  #[test]
+  fn infers_types_and_unions_row_keys() {
+    let body = json!({ "datasets": [{ "rows": [{ "a": 1, "b": "x" }, { "a": 1.5, "c": true }] }] });
+    let datasets = parse(body, false).unwrap();
+    assert_eq!(field_types(&datasets[0]), vec![("a", 'D'), ("b", 'R'), ("c", 'C')]);
+    assert_eq!(datasets[0].rows.len(), 2);
+    // 第一行缺少 c，按空值打包
+    assert!(matches!(datasets[0].rows[0][2], Cell::Char(b' ')));
+    assert!(matches!(datasets[0].rows[1][2], Cell::Char(b'1')));
+  }
+
+  #[test]
+  fn mixed_numbers_and_strings_are_packed_as_strings() {
+    let datasets = parse(rows(json!(null), json!([{ "a": 1 }, { "a": "x" }])), false).unwrap();
+    assert_eq!(field_types(&datasets[0]), vec![("a", 'S')]);
+  }
+
+  #[test]
+  fn lenient_mode_drops_nested_columns_and_keeps_null_as_empty() {
+    let datasets = parse(json!({ "a": null, "b": { "c": 1 } }), false).unwrap();
+    // b 为嵌套对象，整个请求体按 { b: {...} } 结果集处理，a 被忽略
+    assert_eq!(datasets.len(), 1);
+    assert_eq!(datasets[0].name.as_deref(), Some("b"));
+    let datasets = parse(rows(json!(null), json!([{ "a": null, "b": [{ "c": 1 }] }])), false).unwrap();
+    assert_eq!(field_types(&datasets[0]), vec![("a", 'S')]);
+  }
+
+  #[test]
+  fn lenient_mode_coerces_numeric_strings_in_int_fields() {
+    let body = rows(json!([{ "name": "qty", "type": "I" }]), json!([{ "qty": " 100 " }]));
+    let datasets = parse(body, false).unwrap();
+    assert!(matches!(datasets[0].rows[0][0], Cell::Int(100)));
+    let body = rows(json!([{ "name": "qty", "type": "I" }]), json!([{ "qty": "abc" }]));
+    let err = parse(body, false).err().unwrap();
+    assert!(err.reason.contains("datasets[0].rows[0].qty"), "{}", err.reason);
+    assert!(err.reason.contains("string cannot be packed into 'I' field"), "{}", err.reason);
+    let body = rows(json!([{ "name": "qty", "type": "I" }]), json!([{ "qty": "10000000000" }]));
+    assert!(parse(body, false).err().unwrap().reason.contains("overflows int32"));
+  }
+
+  #[test]
+  fn lenient_mode_coerces_numeric_strings_in_double_fields() {
+    let body = rows(json!([{ "name": "price", "type": "D" }]), json!([{ "price": "1.5" }]));
+    let datasets = parse(body, false).unwrap();
+    assert!(matches!(datasets[0].rows[0][0], Cell::Double(f) if f == 1.5));
+    let body = rows(json!([{ "name": "price", "type": "D" }]), json!([{ "price": "NaN" }]));
+    assert!(parse(body, false).is_err());
+    let body = rows(json!([{ "name": "price", "type": "D" }]), json!([{ "price": { "v": 1 } }]));
+    assert!(parse(body, false).is_err());
+  }
+
+  #[test]
+  fn strict_mode_rejects_numeric_strings() {
+    let body = rows(
+      json!([{ "name": "qty", "type": "I" }, { "name": "price", "type": "D" }]),
+      json!([{ "qty": "100", "price": "1.5" }]),
+    );
+    let err = parse(body, true).err().unwrap();
+    assert!(err.reason.starts_with("Invalid body (2 issues)"), "{}", err.reason);
+    assert!(err.reason.contains("set decimal"), "{}", err.reason);
+  }
+
+  #[test]
+  fn lenient_mode_rejects_int32_overflow() {
+    let body = rows(json!([{ "name": "qty", "type": "I" }]), json!([{ "qty": 1i64 << 40 }]));
+    assert!(parse(body, false).err().unwrap().reason.contains("overflows int32"));
+  }
+
+  #[test]
+  fn lenient_mode_converts_documented_values() {
+    let body = rows(
+      json!([{ "name": "flag", "type": "C" }, { "name": "n", "type": "I" }, { "name": "s", "type": "S" }]),
+      json!([{ "flag": true, "n": null, "s": 12 }]),
+    );
+    let datasets = parse(body, false).unwrap();
+    let row = &datasets[0].rows[0];
+    assert!(matches!(row[0], Cell::Char(b'1')));
+    assert!(matches!(row[1], Cell::Int(0)));
+    assert!(matches!(&row[2], Cell::Str(s) if s == b"12"));
+  }
+
+  #[test]
+  fn strict_mode_reports_every_issue() {
+    let body = rows(
+      json!([{ "name": "qty", "type": "I" }, { "name": "flag", "type": "C" }]),
+      json!([{ "qty": "1", "flag": true }, { "qty": null, "flag": "yes" }]),
+    );
+    let err = parse(body, true).err().unwrap();
+    assert!(err.reason.starts_with("Invalid body (4 issues)"), "{}", err.reason);
+    assert!(err.reason.contains("rows[0].qty: string cannot be packed into 'I' field"));
+    assert!(err.reason.contains("rows[0].flag: boolean is packed as '0'/'1'"));
+    assert!(err.reason.contains("rows[1].qty: null is packed as an empty value"));
+    assert!(err.reason.contains("rows[1].flag: \"yes\" is truncated to a single char"));
+  }
+
+  fn pack_decimal(value: &str, scale: i32, strict: bool) -> Result<f64> {
+    let body = rows(
+      json!([{ "name": "price", "type": "D", "scale": scale }]),
+      json!([{ "price": value }]),
+    );
+    let datasets = parse_body(&PackValue::from(body), strict, true, TextEncoding::Utf8)?;
+    match datasets[0].rows[0][0] {
+      Cell::Double(f) => Ok(f),
+      _ => unreachable!(),
+    }
+  }
+
+  #[test]
+  fn decimal_strings_pack_within_scale_and_precision() {
+    assert_eq!(pack_decimal("123.45", 2, false).unwrap(), 123.45);
+    assert_eq!(pack_decimal(" -0.10 ", 1, false).unwrap(), -0.1);
+    assert_eq!(pack_decimal("123456789012345", 0, false).unwrap(), 123456789012345.0);
+    assert_eq!(pack_decimal("1000000000000000000", 0, false).unwrap(), 1e18);
+    assert_eq!(pack_decimal("0.000000000000001234", 18, false).unwrap(), 1.234e-15);
+  }
+
+  #[test]
+  fn decimal_strings_fail_when_precision_would_be_lost() {
+    for strict in [false, true] {
+      let err = pack_decimal("1.234", 2, strict).err().unwrap();
+      assert!(err.reason.contains("more than 2 decimal places"), "{}", err.reason);
+      let err = pack_decimal("1234567890123456", 0, strict).err().unwrap();
+      assert!(err.reason.contains("exceeds 15 significant digits"), "{}", err.reason);
+      let err = pack_decimal("1e5", 2, strict).err().unwrap();
+      assert!(err.reason.contains("is not a decimal"), "{}", err.reason);
+    }
+  }
+
+  #[test]
+  fn array_rows_follow_field_order() {
+    let fields = parse_field_list(&PackValue::from(json!(["a", { "name": "b", "type": "S" }]))).unwrap();
+    let dataset = parse_rows(
+      fields,
+      PackValue::from(json!([[1, "x"], { "b": "y" }])),
+      false,
+      false,
+      TextEncoding::Utf8,
+    )
+    .unwrap();
+    assert_eq!(field_types(&dataset), vec![("a", 'I'), ("b", 'S')]);
+    assert!(matches!(dataset.rows[1][0], Cell::Int(0)));
+    let fields = parse_field_list(&PackValue::from(json!(["a"]))).unwrap();
+    assert!(parse_rows(fields, PackValue::from(json!([[1, 2]])), false, false, TextEncoding::Utf8).is_err());
+  }
+
+  #[test]
+  fn convert_row_uses_declared_types() {
+    let fields = parse_field_list(&PackValue::from(json!([
+      { "name": "id", "type": "I" },
+      { "name": "name", "type": "S" },
+      { "name": "price", "type": "D", "scale": 2 }
+    ])))
+    .unwrap();
+    let convert = |row: serde_json::Value, strict: bool| convert_row(&fields, &PackValue::from(row), strict, true, TextEncoding::Utf8);
+
+    let cells = convert(json!({ "id": 7, "name": "abc" }), false).unwrap();
+    assert!(matches!(cells[0], Cell::Int(7)));
+    assert!(matches!(&cells[1], Cell::Str(bytes) if bytes == b"abc"));
+    assert!(matches!(cells[2], Cell::Double(f) if f == 0.0));
+    assert_eq!(default_width(b'S', cells[1].len()), 3);
+
+    let cells = convert(json!([1, "x", "12.34"]), false).unwrap();
+    assert!(matches!(cells[2], Cell::Double(f) if f == 12.34));
+
+    let cells = convert(json!({ "id": "7" }), false).unwrap();
+    assert!(matches!(cells[0], Cell::Int(7)));
+    assert!(convert(json!({ "id": "x" }), false).is_err());
+    assert!(convert(json!([1, "x", 1, 2]), false).is_err());
+    let Err(error) = convert(json!({ "id": "7", "price": "1.234" }), true) else {
+      panic!("expected an error");
+    };
+    assert!(error.reason.starts_with("Invalid body (2 issues)"), "{}", error.reason);
+  }
+}
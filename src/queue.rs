@@ -0,0 +1,358 @@
+use napi::Result;
+use napi_derive::napi;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+use crate::subscribe::PushMessage;
+
+/// 队列已满时的处理方式
+#[napi]
+#[derive(Debug, Default, PartialEq)]
+pub enum OverflowPolicy {
+  /// 阻塞 SDK 回调线程，直到队列有空位或队列关闭；读取方停止消费时该订阅者不再收到任何推送
+  Block,
+  /// 丢弃最早的消息
+  #[default]
+  DropOldest,
+  /// 丢弃新收到的消息
+  DropNewest,
+  /// 丢弃新收到的消息，并在下一次读取时报错、关闭队列
+  Error,
+  /// 同 Block，但最多等待 blockTimeoutMs，超时后丢弃新收到的消息并计入 blockTimeouts
+  BlockWithTimeout,
+}
+
+#[napi(object)]
+pub struct QueueOptions {
+  /** 队列容量，默认 1024 */
+  pub capacity: Option<u32>,
+  /** 队列已满时的处理方式，默认 DropOldest */
+  pub overflow: Option<OverflowPolicy>,
+  /** BlockWithTimeout 策略下 SDK 回调线程最长等待的毫秒数，默认 1000 */
+  pub block_timeout_ms: Option<u32>,
+}
+
+#[napi(object)]
+pub struct QueueStats {
+  /** 当前排队的消息数 */
+  pub queued: u32,
+  /** 进入队列的消息总数 */
+  pub received: u32,
+  /** 已读取的消息总数 */
+  pub delivered: u32,
+  /** 因队列已满丢弃的消息总数 */
+  pub dropped: u32,
+  /** BlockWithTimeout 策略下等待超时而丢弃的消息数，同时计入 dropped */
+  pub block_timeouts: u32,
+}
+
+/// 订阅者与 MessageQueue 共享的有界队列
+pub(crate) struct QueueInner {
+  topic: Option<String>,
+  capacity: usize,
+  policy: OverflowPolicy,
+  block_timeout: Duration,
+  messages: Mutex<VecDeque<PushMessage>>,
+  // Block、BlockWithTimeout 策略下 SDK 线程等待空位
+  space: Condvar,
+  // 读取方等待新消息
+  ready: Notify,
+  closed: AtomicBool,
+  overflowed: AtomicBool,
+  received: AtomicU32,
+  delivered: AtomicU32,
+  dropped: AtomicU32,
+  block_timeouts: AtomicU32,
+}
+
+impl QueueInner {
+  pub fn new(topic: Option<String>, options: Option<QueueOptions>) -> Self {
+    let capacity = options.as_ref().and_then(|o| o.capacity).unwrap_or(1024).max(1) as usize;
+    let block_timeout = options.as_ref().and_then(|o| o.block_timeout_ms).unwrap_or(1000);
+    let policy = options.and_then(|o| o.overflow).unwrap_or_default();
+    QueueInner {
+      topic,
+      capacity,
+      policy,
+      block_timeout: Duration::from_millis(block_timeout as u64),
+      messages: Mutex::new(VecDeque::new()),
+      space: Condvar::new(),
+      ready: Notify::new(),
+      closed: AtomicBool::new(false),
+      overflowed: AtomicBool::new(false),
+      received: AtomicU32::new(0),
+      delivered: AtomicU32::new(0),
+      dropped: AtomicU32::new(0),
+      block_timeouts: AtomicU32::new(0),
+    }
+  }
+
+  pub fn is_closed(&self) -> bool {
+    self.closed.load(Ordering::Acquire)
+  }
+
+  pub fn matches(&self, topic: &str) -> bool {
+    self.topic.as_deref().is_none_or(|t| t == topic)
+  }
+
+  /// 在 SDK 回调线程中调用
+  pub fn push(&self, message: PushMessage) {
+    let mut messages = self.messages.lock().unwrap();
+    if self.is_closed() {
+      return;
+    }
+    self.received.fetch_add(1, Ordering::Relaxed);
+    if messages.len() >= self.capacity {
+      match self.policy {
+        OverflowPolicy::Block => {
+          messages = self
+            .space
+            .wait_while(messages, |m| m.len() >= self.capacity && !self.is_closed())
+            .unwrap();
+          if self.is_closed() {
+            return;
+          }
+        }
+        OverflowPolicy::BlockWithTimeout => {
+          let (guard, wait) = self
+            .space
+            .wait_timeout_while(messages, self.block_timeout, |m| m.len() >= self.capacity && !self.is_closed())
+            .unwrap();
+          messages = guard;
+          if self.is_closed() {
+            return;
+          }
+          // 读取方长时间没有消费，不能让 SDK 回调线程一直阻塞
+          if wait.timed_out() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            self.block_timeouts.fetch_add(1, Ordering::Relaxed);
+            return;
+          }
+        }
+        OverflowPolicy::DropOldest => {
+          messages.pop_front();
+          self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        OverflowPolicy::DropNewest => {
+          self.dropped.fetch_add(1, Ordering::Relaxed);
+          return;
+        }
+        OverflowPolicy::Error => {
+          self.dropped.fetch_add(1, Ordering::Relaxed);
+          self.overflowed.store(true, Ordering::Release);
+          drop(messages);
+          self.ready.notify_one();
+          return;
+        }
+      }
+    }
+    messages.push_back(message);
+    drop(messages);
+    self.ready.notify_one();
+  }
+
+  pub fn close(&self) {
+    let messages = self.messages.lock().unwrap();
+    self.closed.store(true, Ordering::Release);
+    drop(messages);
+    self.space.notify_all();
+    self.ready.notify_waiters();
+    self.ready.notify_one();
+  }
+}
+
+/**
+ * 订阅推送的有界队列，通过 Subscriber.createQueue 创建
+ * JS 侧的 `subscriber.messages()` 和 `subscriber.stream()` 基于该队列实现
+ */
+#[napi]
+pub struct MessageQueue {
+  inner: Arc<QueueInner>,
+  // 订阅者登记的队列列表，释放时从中移除
+  registry: Weak<Mutex<Vec<Arc<QueueInner>>>>,
+}
+
+impl MessageQueue {
+  pub(crate) fn new(inner: Arc<QueueInner>, registry: Weak<Mutex<Vec<Arc<QueueInner>>>>) -> Self {
+    MessageQueue { inner, registry }
+  }
+}
+
+impl Drop for MessageQueue {
+  fn drop(&mut self) {
+    // 队列被丢弃（如 messages() 的迭代器被回收）时不再接收推送
+    self.inner.close();
+    if let Some(registry) = self.registry.upgrade() {
+      registry.lock().unwrap().retain(|q| !Arc::ptr_eq(q, &self.inner));
+    }
+  }
+}
+
+#[napi]
+impl MessageQueue {
+  /**
+   * 读取下一条消息，队列为空时等待
+   * @returns 队列关闭后返回 null；Error 策略下队列溢出时报错
+   */
+  #[napi]
+  pub async fn next(&self) -> Result<Option<PushMessage>> {
+    let inner = &self.inner;
+    loop {
+      if let Some(message) = inner.messages.lock().unwrap().pop_front() {
+        inner.delivered.fetch_add(1, Ordering::Relaxed);
+        inner.space.notify_one();
+        return Ok(Some(message));
+      }
+      if inner.overflowed.load(Ordering::Acquire) {
+        inner.close();
+        return Err(napi::Error::from_reason(format!(
+          "Message queue overflow: {} messages dropped",
+          inner.dropped.load(Ordering::Relaxed)
+        )));
+      }
+      if inner.is_closed() {
+        return Ok(None);
+      }
+      inner.ready.notified().await;
+    }
+  }
+
+  /// 关闭队列，未读取的消息被丢弃，等待中的 next() 返回 null
+  #[napi]
+  pub fn close(&self) {
+    self.inner.close();
+    self.inner.messages.lock().unwrap().clear();
+  }
+
+  #[napi(getter)]
+  pub fn is_closed(&self) -> bool {
+    self.inner.is_closed()
+  }
+
+  /// 获取队列计数
+  #[napi]
+  pub fn get_stats(&self) -> QueueStats {
+    let inner = &self.inner;
+    QueueStats {
+      queued: inner.messages.lock().unwrap().len() as u32,
+      received: inner.received.load(Ordering::Relaxed),
+      delivered: inner.delivered.load(Ordering::Relaxed),
+      dropped: inner.dropped.load(Ordering::Relaxed),
+      block_timeouts: inner.block_timeouts.load(Ordering::Relaxed),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use napi::bindgen_prelude::Buffer;
+
+  fn message(topic: &str, msg_no: i64) -> PushMessage {
+    PushMessage {
+      id: None,
+      subscribe_index: 0,
+      topic: topic.to_string(),
+      msg_no: Some(msg_no),
+      data: Buffer::from(Vec::new()),
+      filter_data: None,
+      app_data: None,
+    }
+  }
+
+  fn queue(capacity: u32, overflow: Option<OverflowPolicy>) -> QueueInner {
+    QueueInner::new(
+      None,
+      Some(QueueOptions {
+        capacity: Some(capacity),
+        overflow,
+        block_timeout_ms: Some(10),
+      }),
+    )
+  }
+
+  fn queued(queue: &QueueInner) -> Vec<i64> {
+    queue.messages.lock().unwrap().iter().filter_map(|m| m.msg_no).collect()
+  }
+
+  #[test]
+  fn drops_oldest_by_default() {
+    let queue = queue(2, None);
+    for msg_no in 1..=3 {
+      queue.push(message("t", msg_no));
+    }
+    assert_eq!(queued(&queue), vec![2, 3]);
+    assert_eq!(queue.dropped.load(Ordering::Relaxed), 1);
+  }
+
+  #[test]
+  fn block_waits_for_space() {
+    let queue = Arc::new(queue(1, Some(OverflowPolicy::Block)));
+    queue.push(message("t", 1));
+    let reader = queue.clone();
+    let handle = std::thread::spawn(move || {
+      std::thread::sleep(Duration::from_millis(50));
+      reader.messages.lock().unwrap().pop_front();
+      reader.space.notify_one();
+    });
+    queue.push(message("t", 2));
+    handle.join().unwrap();
+    assert_eq!(queued(&queue), vec![2]);
+    assert_eq!(queue.dropped.load(Ordering::Relaxed), 0);
+  }
+
+  #[test]
+  fn block_with_timeout_gives_up_and_counts() {
+    let queue = queue(1, Some(OverflowPolicy::BlockWithTimeout));
+    queue.push(message("t", 1));
+    queue.push(message("t", 2));
+    assert_eq!(queued(&queue), vec![1]);
+    assert_eq!(queue.dropped.load(Ordering::Relaxed), 1);
+    assert_eq!(queue.block_timeouts.load(Ordering::Relaxed), 1);
+  }
+
+  #[test]
+  fn block_resumes_when_closed() {
+    let queue = Arc::new(queue(1, Some(OverflowPolicy::Block)));
+    queue.push(message("t", 1));
+    let closer = queue.clone();
+    let handle = std::thread::spawn(move || closer.close());
+    queue.push(message("t", 2));
+    handle.join().unwrap();
+    assert!(queue.is_closed());
+    assert_eq!(queued(&queue), vec![1]);
+  }
+
+  #[test]
+  fn drop_newest_and_error_keep_queued_messages() {
+    for policy in [OverflowPolicy::DropNewest, OverflowPolicy::Error] {
+      let error = policy == OverflowPolicy::Error;
+      let queue = queue(1, Some(policy));
+      queue.push(message("t", 1));
+      queue.push(message("t", 2));
+      assert_eq!(queued(&queue), vec![1]);
+      assert_eq!(queue.overflowed.load(Ordering::Relaxed), error);
+    }
+  }
+
+  #[test]
+  fn matches_topic_filter() {
+    let queue = QueueInner::new(Some("a".to_string()), None);
+    assert!(queue.matches("a"));
+    assert!(!queue.matches("b"));
+    assert!(QueueInner::new(None, None).matches("b"));
+  }
+
+  #[test]
+  fn dropping_the_queue_unregisters_it() {
+    let registry = Arc::new(Mutex::new(Vec::new()));
+    let inner = Arc::new(QueueInner::new(None, None));
+    registry.lock().unwrap().push(inner.clone());
+    drop(MessageQueue::new(inner.clone(), Arc::downgrade(&registry)));
+    assert!(inner.is_closed());
+    assert!(registry.lock().unwrap().is_empty());
+  }
+}
@@ -0,0 +1,334 @@
+use napi::bindgen_prelude::{Buffer, Either4, Float64Array, Generator, Int32Array, Reference, ToNapiValue};
+use napi::{sys, Env, JsError, NapiRaw, Result};
+use napi_derive::napi;
+use std::ptr;
+
+use crate::f2_unpacker::{Decimal, DecimalMode, UnPacker};
+
+/// 字段值，'I'、'D' 字段为数字，'R' 字段为 Buffer，其余为字符串；'D' 字段按解包器的 DecimalMode 返回
+pub(crate) type CellValue = Either4<String, f64, Buffer, Decimal>;
+
+/// 当前结果集的字段名和类型
+pub(crate) fn read_columns(unpacker: &UnPacker) -> Result<Vec<(String, u8)>> {
+  (0..unpacker.get_col_count()?)
+    .map(|c| Ok((unpacker.get_col_name(c)?, unpacker.get_col_type(c)? as u8)))
+    .collect()
+}
+
+/// 按字段类型读取当前记录
+pub(crate) fn read_row(unpacker: &UnPacker, columns: &[(String, u8)]) -> Result<Vec<(String, CellValue)>> {
+  let decimal = unpacker.decimal_mode();
+  columns
+    .iter()
+    .enumerate()
+    .map(|(column, (name, col_type))| {
+      let column = column as i32;
+      let value = match col_type {
+        b'I' => Either4::B(unpacker.get_int_by_index(column)? as f64),
+        b'D' | b'F' => match decimal {
+          DecimalMode::Number => Either4::B(unpacker.get_double_by_index(column)?),
+          DecimalMode::String => Either4::A(unpacker.get_decimal_by_index(column)?),
+          DecimalMode::Units => Either4::D(unpacker.get_decimal_units_by_index(column)?),
+        },
+        b'R' => Either4::C(unpacker.get_raw_by_index(column)?),
+        _ => Either4::A(unpacker.get_str_by_index(column)?),
+      };
+      Ok((name.clone(), value))
+    })
+    .collect()
+}
+
+/// 一条记录，按字段顺序转为 JS 对象
+pub struct Row(Vec<(String, CellValue)>);
+
+impl ToNapiValue for Row {
+  unsafe fn to_napi_value(env: sys::napi_env, val: Self) -> Result<sys::napi_value> {
+    let mut row = Env::from_raw(env).create_object()?;
+    for (name, value) in val.0 {
+      row.set(name, value)?;
+    }
+    Ok(row.raw())
+  }
+}
+
+/// 迭代器产出的值，读取失败时在 next() 中抛出
+pub struct Yielded<T>(Result<T>);
+
+impl<T: ToNapiValue> ToNapiValue for Yielded<T> {
+  unsafe fn to_napi_value(env: sys::napi_env, val: Self) -> Result<sys::napi_value> {
+    match val.0 {
+      Ok(value) => T::to_napi_value(env, value),
+      Err(e) => {
+        // 迭代器只能产出值，这里直接抛出异常并返回 undefined
+        JsError::from(e).throw_into(env);
+        let mut undefined = ptr::null_mut();
+        sys::napi_get_undefined(env, &mut undefined);
+        Ok(undefined)
+      }
+    }
+  }
+}
+
+/**
+ * 逐行读取结果集的迭代器，每次迭代在一次调用中构造一行
+ * 与解包器共用游标，同一解包器同时只应有一个迭代器在读取
+ */
+#[napi(iterator)]
+pub struct RowIterator {
+  unpacker: Reference<UnPacker>,
+  columns: Vec<(String, u8)>,
+  done: bool,
+}
+
+impl RowIterator {
+  fn new(unpacker: Reference<UnPacker>) -> Result<Self> {
+    unpacker.first()?;
+    let columns = read_columns(&unpacker)?;
+    Ok(RowIterator {
+      unpacker,
+      columns,
+      done: false,
+    })
+  }
+}
+
+impl Generator for RowIterator {
+  type Yield = Yielded<Row>;
+  type Next = ();
+  type Return = ();
+
+  fn next(&mut self, _value: Option<()>) -> Option<Yielded<Row>> {
+    if self.done || self.unpacker.is_eof().map_or(true, |eof| eof != 0) {
+      self.done = true;
+      return None;
+    }
+    let row = read_row(&self.unpacker, &self.columns).and_then(|row| {
+      self.unpacker.next()?;
+      Ok(Row(row))
+    });
+    self.done = row.is_err();
+    Some(Yielded(row))
+  }
+}
+
+/// 一个结果集，rows() 切换到该结果集后逐行读取
+#[napi]
+pub struct DatasetCursor {
+  unpacker: Reference<UnPacker>,
+  index: i32,
+  name: String,
+  row_count: i32,
+}
+
+#[napi]
+impl DatasetCursor {
+  #[napi(getter)]
+  pub fn index(&self) -> i32 {
+    self.index
+  }
+
+  #[napi(getter)]
+  pub fn name(&self) -> String {
+    self.name.clone()
+  }
+
+  #[napi(getter)]
+  pub fn row_count(&self) -> i32 {
+    self.row_count
+  }
+
+  #[napi]
+  pub fn rows(&self, env: Env) -> Result<RowIterator> {
+    self.unpacker.set_current_dataset_by_index(self.index)?;
+    RowIterator::new(self.unpacker.clone(env)?)
+  }
+}
+
+/// 依次返回各结果集的迭代器
+#[napi(iterator)]
+pub struct DatasetIterator {
+  unpacker: Reference<UnPacker>,
+  // 创建迭代器时的 env，只在 JS 线程中使用
+  env: Env,
+  index: i32,
+  count: i32,
+}
+
+impl Generator for DatasetIterator {
+  type Yield = Yielded<DatasetCursor>;
+  type Next = ();
+  type Return = ();
+
+  fn next(&mut self, _value: Option<()>) -> Option<Yielded<DatasetCursor>> {
+    if self.index >= self.count {
+      return None;
+    }
+    let index = self.index;
+    self.index += 1;
+    let unpacker = &self.unpacker;
+    Some(Yielded((|| {
+      unpacker.set_current_dataset_by_index(index)?;
+      Ok(DatasetCursor {
+        unpacker: unpacker.clone(self.env)?,
+        index,
+        name: unpacker.get_dataset_name()?,
+        row_count: unpacker.get_row_count()?,
+      })
+    })()))
+  }
+}
+
+/// 一个字段的全部值
+enum ColumnValues {
+  Int(Vec<i32>),
+  Double(Vec<f64>),
+  Str(Vec<String>),
+  Raw(Vec<Buffer>),
+  // DecimalMode.String 下的 'D' 字段
+  Decimal(Vec<String>),
+  // DecimalMode.Units 下的 'D' 字段
+  Units(Vec<Decimal>),
+}
+
+struct Column {
+  name: String,
+  values: ColumnValues,
+  // 空值位图，第 i 行为空时第 i 位（字节 i / 8 的第 i % 8 位）为 1
+  nulls: Option<Vec<u8>>,
+}
+
+impl Column {
+  fn new(name: String, col_type: u8, decimal: DecimalMode, capacity: usize) -> Self {
+    let values = match col_type {
+      b'I' => ColumnValues::Int(Vec::with_capacity(capacity)),
+      b'D' | b'F' => match decimal {
+        DecimalMode::Number => ColumnValues::Double(Vec::with_capacity(capacity)),
+        DecimalMode::String => ColumnValues::Decimal(Vec::with_capacity(capacity)),
+        DecimalMode::Units => ColumnValues::Units(Vec::with_capacity(capacity)),
+      },
+      b'R' => ColumnValues::Raw(Vec::with_capacity(capacity)),
+      _ => ColumnValues::Str(Vec::with_capacity(capacity)),
+    };
+    Column { name, values, nulls: None }
+  }
+
+  /// 读取当前记录的第 column 个字段
+  fn push(&mut self, unpacker: &UnPacker, column: i32, row: usize) -> Result<()> {
+    match &mut self.values {
+      ColumnValues::Int(values) => values.push(unpacker.get_int_by_index(column)?),
+      ColumnValues::Double(values) => values.push(unpacker.get_double_by_index(column)?),
+      ColumnValues::Str(values) => values.push(unpacker.get_str_by_index(column)?),
+      ColumnValues::Raw(values) => values.push(unpacker.get_raw_by_index(column)?),
+      ColumnValues::Decimal(values) => values.push(unpacker.get_decimal_by_index(column)?),
+      ColumnValues::Units(values) => values.push(unpacker.get_decimal_units_by_index(column)?),
+    }
+    if unpacker.was_null()? != 0 {
+      let nulls = self.nulls.get_or_insert_with(Vec::new);
+      if nulls.len() <= row / 8 {
+        nulls.resize(row / 8 + 1, 0);
+      }
+      nulls[row / 8] |= 1 << (row % 8);
+    }
+    Ok(())
+  }
+}
+
+/// toColumns() 返回的一个结果集
+pub struct ColumnarDataset {
+  name: String,
+  row_count: usize,
+  columns: Vec<Column>,
+}
+
+impl ColumnarDataset {
+  /// 从第一行开始读取当前结果集
+  fn read(unpacker: &UnPacker) -> Result<Self> {
+    let capacity = unpacker.get_row_count()?.max(0) as usize;
+    let mut columns: Vec<Column> = read_columns(unpacker)?
+      .into_iter()
+      .map(|(name, col_type)| Column::new(name, col_type, unpacker.decimal_mode(), capacity))
+      .collect();
+    let mut row_count = 0;
+    unpacker.first()?;
+    while unpacker.is_eof()? == 0 {
+      for (index, column) in columns.iter_mut().enumerate() {
+        column.push(unpacker, index as i32, row_count)?;
+      }
+      row_count += 1;
+      unpacker.next()?;
+    }
+    Ok(ColumnarDataset {
+      name: unpacker.get_dataset_name()?,
+      row_count,
+      columns,
+    })
+  }
+}
+
+impl ToNapiValue for ColumnarDataset {
+  unsafe fn to_napi_value(env: sys::napi_env, val: Self) -> Result<sys::napi_value> {
+    let js_env = Env::from_raw(env);
+    let mut dataset = js_env.create_object()?;
+    let mut columns = js_env.create_object()?;
+    let mut nulls = js_env.create_object()?;
+    for column in val.columns {
+      match column.values {
+        ColumnValues::Int(values) => columns.set(&column.name, Int32Array::new(values))?,
+        ColumnValues::Double(values) => columns.set(&column.name, Float64Array::new(values))?,
+        ColumnValues::Str(values) | ColumnValues::Decimal(values) => columns.set(&column.name, values)?,
+        ColumnValues::Raw(values) => columns.set(&column.name, values)?,
+        ColumnValues::Units(values) => columns.set(&column.name, values)?,
+      }
+      if let Some(mut bitmap) = column.nulls {
+        bitmap.resize(val.row_count.div_ceil(8), 0);
+        nulls.set(&column.name, Buffer::from(bitmap))?;
+      }
+    }
+    dataset.set("name", val.name)?;
+    dataset.set("rowCount", val.row_count as u32)?;
+    dataset.set("columns", columns)?;
+    dataset.set("nulls", nulls)?;
+    Ok(dataset.raw())
+  }
+}
+
+#[napi]
+impl UnPacker {
+  /**
+   * 从第一行开始逐行读取当前结果集
+   * for (const row of unpacker.rows()) { ... }
+   */
+  #[napi]
+  pub fn rows(&self, reference: Reference<UnPacker>) -> Result<RowIterator> {
+    RowIterator::new(reference)
+  }
+
+  /**
+   * 依次读取各结果集
+   * for (const dataset of unpacker.datasets()) { for (const row of dataset.rows()) { ... } }
+   */
+  #[napi]
+  pub fn datasets(&self, env: Env, reference: Reference<UnPacker>) -> Result<DatasetIterator> {
+    Ok(DatasetIterator {
+      count: self.get_dataset_count()?,
+      unpacker: reference,
+      env,
+      index: 0,
+    })
+  }
+
+  /**
+   * 按列读取所有结果集，'I' 字段为 Int32Array，'D' 字段为 Float64Array，'R' 字段为 Buffer 数组，其余为字符串数组
+   * DecimalMode 不为 Number 时 'D' 字段为十进制字符串数组或 Decimal 数组
+   * 读取后当前结果集为最后一个
+   */
+  #[napi(ts_return_type = "Array<ColumnarDataset>")]
+  pub fn to_columns(&self) -> Result<Vec<ColumnarDataset>> {
+    (0..self.get_dataset_count()?)
+      .map(|index| {
+        self.set_current_dataset_by_index(index)?;
+        ColumnarDataset::read(self)
+      })
+      .collect()
+  }
+}
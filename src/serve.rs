@@ -0,0 +1,213 @@
+use napi::bindgen_prelude::{Buffer, FromNapiValue, Promise};
+use napi::threadsafe_function::{ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction};
+use napi::{sys, Env, JsFunction, Result};
+use napi_derive::napi;
+use std::collections::HashMap;
+use std::os::raw::{c_int, c_void};
+use std::sync::Mutex;
+
+use crate::biz_message::{BizMessage, IBizMessage, IBizMessageRust, JSRouteInfo};
+use crate::charset::TextEncoding;
+use crate::connection::{CConnectionInterface, ConnectionAlive, IConnectionRust};
+use crate::def::{PACKER_VERSION_V2, REQUEST_PACKET};
+use crate::f2_packer::Packer;
+use crate::f2_unpacker::UnPacker;
+use crate::log::{log, LogLevel, Target};
+use crate::pack::{self, SendBody};
+use crate::rows::{self, CellValue};
+
+/// 请求中的一个结果集
+#[napi(object)]
+pub struct RequestDataset {
+  pub name: String,
+  /** 'I'、'D' 字段为数字，'R' 字段为 Buffer，其余为字符串 */
+  pub rows: Vec<HashMap<String, CellValue>>,
+}
+
+/// 发给本进程的请求，由 serve 注册的 handler 处理
+#[napi(object)]
+pub struct ServeRequest {
+  pub function_no: i32,
+  pub packet_id: i32,
+  pub sender_id: i32,
+  pub system_no: i32,
+  pub sub_system_no: i32,
+  pub branch_no: i32,
+  pub company_id: i32,
+  /** 发送者路由 */
+  pub send_info: JSRouteInfo,
+  /** 业务内容 */
+  pub content: Buffer,
+  /** 解包后的业务内容 */
+  pub datasets: Vec<RequestDataset>,
+}
+
+/// handler 的返回值，可以是应答体或 Promise
+enum ServeReturn {
+  Body(Option<SendBody>),
+  Promise(Promise<Option<SendBody>>),
+}
+
+impl FromNapiValue for ServeReturn {
+  unsafe fn from_napi_value(env: sys::napi_env, napi_val: sys::napi_value) -> Result<Self> {
+    let mut is_promise = false;
+    sys::napi_is_promise(env, napi_val, &mut is_promise);
+    if is_promise {
+      Ok(ServeReturn::Promise(Promise::from_napi_value(env, napi_val)?))
+    } else {
+      Ok(ServeReturn::Body(Option::<SendBody>::from_napi_value(env, napi_val)?))
+    }
+  }
+}
+
+/// 按功能号注册的请求处理函数
+pub(crate) struct Server {
+  handlers: Mutex<HashMap<i32, ThreadsafeFunction<ServeRequest, ErrorStrategy::Fatal>>>,
+}
+
+impl Server {
+  pub fn new() -> Self {
+    Server {
+      handlers: Mutex::new(HashMap::new()),
+    }
+  }
+
+  pub fn add(&self, env: &Env, function_no: i32, callback: JsFunction) -> Result<()> {
+    let mut handler: ThreadsafeFunction<ServeRequest, ErrorStrategy::Fatal> =
+      callback.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<ServeRequest>| Ok(vec![ctx.value]))?;
+    handler.unref(env)?;
+    self.handlers.lock().unwrap().insert(function_no, handler);
+    Ok(())
+  }
+
+  pub fn remove(&self, function_no: i32) -> bool {
+    self.handlers.lock().unwrap().remove(&function_no).is_some()
+  }
+
+  /**
+   * 处理发给本进程的请求，在 SDK 回调线程中调用
+   * 复制请求并转为应答，handler 返回后填入应答体发回
+   * @returns 已注册该功能号时返回 true
+   */
+  pub fn dispatch(
+    &self,
+    connection: *mut IConnectionRust,
+    alive: &ConnectionAlive,
+    msg: *mut IBizMessageRust,
+    encoding: TextEncoding,
+  ) -> bool {
+    let (function_no, packet_type) = unsafe { ((*msg).get_function(), (*msg).get_packet_type()) };
+    if packet_type != REQUEST_PACKET {
+      return false;
+    }
+    let Some(handler) = self.handlers.lock().unwrap().get(&function_no).cloned() else {
+      return false;
+    };
+
+    let message = BizMessage::new_form_ptr(msg, encoding);
+    let (request, answer) = match read_request(&message, encoding) {
+      Ok(request) => request,
+      Err(e) => {
+        log(LogLevel::Warn, Target::Receive, || {
+          format!("Failed to read function {} request: {}", function_no, e.reason)
+        });
+        return false;
+      }
+    };
+    let connection = ConnectionPtr(connection);
+    let alive = alive.clone();
+    napi::bindgen_prelude::spawn(async move {
+      let body = match handler.call_async::<ServeReturn>(request).await {
+        Ok(ServeReturn::Body(body)) => Ok(body),
+        Ok(ServeReturn::Promise(promise)) => promise.await,
+        Err(e) => Err(e),
+      };
+      if let Err(e) = write_answer(&answer, body, encoding) {
+        log(LogLevel::Warn, Target::Callback, || {
+          format!("Function {} handler failed: {}", function_no, e.reason)
+        });
+        let _ = answer.set_error_no(-1);
+        let _ = answer.set_return_code(-1);
+        let _ = answer.set_error_info(e.reason.trim_start_matches("Error: ").to_string());
+      }
+      // 发送期间持有连接的读锁，连接已释放时放弃应答
+      let Ok(_guard) = alive.guard() else {
+        return;
+      };
+      connection.send(&answer);
+    });
+    true
+  }
+}
+
+struct ConnectionPtr(*mut IConnectionRust);
+
+impl ConnectionPtr {
+  fn send(&self, message: &BizMessage) {
+    unsafe {
+      (*self.0).send_biz_msg(message.get_ptr(), 1);
+    }
+  }
+}
+
+unsafe impl Send for ConnectionPtr {}
+
+/// 复制请求，返回交给 handler 的请求和已转为应答的消息
+fn read_request(message: &BizMessage, encoding: TextEncoding) -> Result<(ServeRequest, BizMessage)> {
+  let content = message.get_content()?;
+  let request = ServeRequest {
+    function_no: message.get_function()?,
+    packet_id: message.get_packet_id()?,
+    sender_id: message.get_sender_id()?,
+    system_no: message.get_system_no()?,
+    sub_system_no: message.get_sub_system_no()?,
+    branch_no: message.get_branch_no()?,
+    company_id: message.get_company_id()?,
+    send_info: message.get_send_info()?,
+    datasets: decode_datasets(&content, encoding)?,
+    content,
+  };
+  let answer = BizMessage::new()?;
+  answer.set_buff(message.get_buff()?)?;
+  answer.change_req_2_ans_message()?;
+  Ok((request, answer))
+}
+
+fn write_answer(answer: &BizMessage, body: Result<Option<SendBody>>, encoding: TextEncoding) -> Result<()> {
+  let Some(body) = body? else {
+    answer.set_content(Buffer::from(Vec::new()))?;
+    return Ok(());
+  };
+  let datasets = pack::parse_body(&body.0, false, false, encoding)?;
+  let mut packer = Packer::new(PACKER_VERSION_V2)?;
+  packer.set_encoding(encoding);
+  packer.begin_pack()?;
+  pack::write_datasets(&packer, &datasets)?;
+  packer.end_pack()?;
+  answer.set_content(packer.get_pack_buf()?)
+}
+
+fn decode_datasets(content: &[u8], encoding: TextEncoding) -> Result<Vec<RequestDataset>> {
+  if content.is_empty() {
+    return Ok(Vec::new());
+  }
+  let mut data = content.to_vec();
+  let mut unpacker = UnPacker::new(data.as_mut_ptr() as *mut c_void, data.len() as c_int, 0)?;
+  unpacker.set_encoding(encoding);
+  let mut datasets = Vec::new();
+  for index in 0..unpacker.get_dataset_count()? {
+    unpacker.set_current_dataset_by_index(index)?;
+    let columns = rows::read_columns(&unpacker)?;
+    let mut rows = Vec::new();
+    unpacker.first()?;
+    while unpacker.is_eof()? == 0 {
+      rows.push(rows::read_row(&unpacker, &columns)?.into_iter().collect());
+      unpacker.next()?;
+    }
+    datasets.push(RequestDataset {
+      name: unpacker.get_dataset_name()?,
+      rows,
+    });
+  }
+  Ok(datasets)
+}
@@ -0,0 +1,1098 @@
+use napi::bindgen_prelude::Buffer;
+use napi::{Env, JsFunction, Result};
+use napi_derive::napi;
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_int, c_uint, c_ulong, c_void};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use crate::callback::{callback_add_ref, callback_query_interface, callback_release};
+use crate::charset::TextEncoding;
+use crate::checkpoint::{subscription_key, MsgNoTracker, Sequence};
+use crate::connection::ConnectionAlive;
+use crate::def::PACKER_VERSION_V2;
+use crate::event::Listeners;
+use crate::f2_packer::{IF2PackerRust, Packer};
+use crate::f2_unpacker::{IF2UnPackerRust, UnPacker};
+use crate::iknown::{IKnown, IKnownVTable};
+use crate::log::{log, LogLevel, Target};
+use crate::queue::{MessageQueue, QueueInner, QueueOptions};
+
+/// 过滤字段最多 16 个
+const MAX_FILTERS: usize = 16;
+/// GetSessionStatus：与消息中心连接已建立
+const SESSION_CONNECTED: c_int = 2;
+
+pub(crate) trait CSubscribeInterface: IKnown {
+  // virtual int FUNCTION_CALL_MODE SubscribeTopic(CSubscribeParamInterface* lpSubscribeParamInter ,unsigned int uiTimeout,IF2UnPacker** lppBizUnPack=NULL,IF2Packer* lpBizPack=NULL) = 0;
+  #[allow(dead_code)]
+  unsafe fn subscribe_topic(
+    &self,
+    param: *mut c_void,
+    timeout: u32,
+    biz_unpack: *mut *mut IF2UnPackerRust,
+    biz_pack: *mut IF2PackerRust,
+  ) -> c_int;
+  // virtual int FUNCTION_CALL_MODE CancelSubscribeTopic(int subscribeIndex) = 0;
+  unsafe fn cancel_subscribe_topic(&self, subscribe_index: c_int) -> c_int;
+  // virtual int FUNCTION_CALL_MODE CancelSubscribeTopicEx(char* topicName,CFilterInterface* lpFilterInterface) = 0;
+  #[allow(dead_code)]
+  unsafe fn cancel_subscribe_topic_ex(&self, topic_name: *const c_char, filter: *mut c_void) -> c_int;
+  // virtual void FUNCTION_CALL_MODE GetSubcribeTopic(IF2Packer* lpPack)=0;
+  unsafe fn get_subcribe_topic(&self, pack: *mut IF2PackerRust);
+  // virtual const char * FUNCTION_CALL_MODE GetServerAddress(int *lpPort) = 0;
+  #[allow(dead_code)]
+  unsafe fn get_server_address(&self, port: *mut c_int) -> *const c_char;
+  // virtual IF2UnPacker* FUNCTION_CALL_MODE GetTopic(bool byForce,int iTimeOut) = 0;
+  #[allow(dead_code)]
+  unsafe fn get_topic(&self, by_force: bool, timeout: c_int) -> *mut IF2UnPackerRust;
+  // virtual int FUNCTION_CALL_MODE BatchSubscribeTopic(IF2UnPacker* lpSubReqUnpack,IF2Packer* lpSubAnsPacker,unsigned int uiTimeout) = 0;
+  unsafe fn batch_subscribe_topic(&self, req: *mut IF2UnPackerRust, ans: *mut IF2PackerRust, timeout: u32) -> c_int;
+  // virtual int FUNCTION_CALL_MODE GetSessionStatus() = 0;
+  unsafe fn get_session_status(&self) -> c_int;
+}
+
+#[repr(C)]
+struct SubscribeVTable {
+  iknown: IKnownVTable,
+  subscribe_topic: unsafe extern "C" fn(
+    this: *mut c_void,
+    param: *mut c_void,
+    timeout: c_uint,
+    biz_unpack: *mut *mut IF2UnPackerRust,
+    biz_pack: *mut IF2PackerRust,
+  ) -> c_int,
+  cancel_subscribe_topic: unsafe extern "C" fn(this: *mut c_void, subscribe_index: c_int) -> c_int,
+  cancel_subscribe_topic_ex: unsafe extern "C" fn(this: *mut c_void, topic_name: *const c_char, filter: *mut c_void) -> c_int,
+  get_subcribe_topic: unsafe extern "C" fn(this: *mut c_void, pack: *mut IF2PackerRust),
+  get_server_address: unsafe extern "C" fn(this: *mut c_void, port: *mut c_int) -> *const c_char,
+  get_topic: unsafe extern "C" fn(this: *mut c_void, by_force: bool, timeout: c_int) -> *mut IF2UnPackerRust,
+  batch_subscribe_topic:
+    unsafe extern "C" fn(this: *mut c_void, req: *mut IF2UnPackerRust, ans: *mut IF2PackerRust, timeout: c_uint) -> c_int,
+  get_session_status: unsafe extern "C" fn(this: *mut c_void) -> c_int,
+}
+
+#[repr(C)]
+pub(crate) struct CSubscribeRust {
+  vtable: *const SubscribeVTable,
+}
+
+impl IKnown for CSubscribeRust {
+  unsafe fn query_interface(&self, iid: *const c_char, ppv: *mut *mut c_void) -> c_ulong {
+    ((*self.vtable).iknown.query_interface)(self as *const _ as *mut c_void, iid, ppv)
+  }
+
+  unsafe fn add_ref(&self) -> c_ulong {
+    ((*self.vtable).iknown.add_ref)(self as *const _ as *mut c_void)
+  }
+
+  unsafe fn release(&self) -> c_ulong {
+    ((*self.vtable).iknown.release)(self as *const _ as *mut c_void)
+  }
+}
+
+impl CSubscribeInterface for CSubscribeRust {
+  unsafe fn subscribe_topic(
+    &self,
+    param: *mut c_void,
+    timeout: u32,
+    biz_unpack: *mut *mut IF2UnPackerRust,
+    biz_pack: *mut IF2PackerRust,
+  ) -> c_int {
+    ((*self.vtable).subscribe_topic)(self as *const _ as *mut c_void, param, timeout, biz_unpack, biz_pack)
+  }
+
+  unsafe fn cancel_subscribe_topic(&self, subscribe_index: c_int) -> c_int {
+    ((*self.vtable).cancel_subscribe_topic)(self as *const _ as *mut c_void, subscribe_index)
+  }
+
+  unsafe fn cancel_subscribe_topic_ex(&self, topic_name: *const c_char, filter: *mut c_void) -> c_int {
+    ((*self.vtable).cancel_subscribe_topic_ex)(self as *const _ as *mut c_void, topic_name, filter)
+  }
+
+  unsafe fn get_subcribe_topic(&self, pack: *mut IF2PackerRust) {
+    ((*self.vtable).get_subcribe_topic)(self as *const _ as *mut c_void, pack)
+  }
+
+  unsafe fn get_server_address(&self, port: *mut c_int) -> *const c_char {
+    ((*self.vtable).get_server_address)(self as *const _ as *mut c_void, port)
+  }
+
+  unsafe fn get_topic(&self, by_force: bool, timeout: c_int) -> *mut IF2UnPackerRust {
+    ((*self.vtable).get_topic)(self as *const _ as *mut c_void, by_force, timeout)
+  }
+
+  unsafe fn batch_subscribe_topic(&self, req: *mut IF2UnPackerRust, ans: *mut IF2PackerRust, timeout: u32) -> c_int {
+    ((*self.vtable).batch_subscribe_topic)(self as *const _ as *mut c_void, req, ans, timeout)
+  }
+
+  unsafe fn get_session_status(&self) -> c_int {
+    ((*self.vtable).get_session_status)(self as *const _ as *mut c_void)
+  }
+}
+
+/// 主推消息的其他字段，对应 SUBSCRIBE_RECVDATA
+#[repr(C)]
+pub(crate) struct SubscribeRecvData {
+  filter_data: *const c_char,
+  filter_data_len: c_int,
+  app_data: *const c_char,
+  app_data_len: c_int,
+  topic_name: [c_char; 260],
+}
+
+/// 订阅推送的消息
+#[napi(object)]
+#[derive(Clone)]
+pub struct PushMessage {
+  /** 订阅项的稳定标识，重新订阅后不变 */
+  pub id: Option<u32>,
+  /** 订阅标识，来自 SDK，重新订阅后会变化 */
+  pub subscribe_index: i32,
+  /** 主题名字 */
+  pub topic: String,
+  /** 消息序号，来自业务体中的 msgNoField 字段 */
+  pub msg_no: Option<i64>,
+  /** 业务体打包内容，可用 newUnpacker 解包 */
+  pub data: Buffer,
+  /** 过滤字段的打包内容 */
+  pub filter_data: Option<Buffer>,
+  /** 订阅时设置的附加数据 */
+  pub app_data: Option<Buffer>,
+}
+
+/// 订阅项被剔除的通知，剔除的订阅项不会再重新订阅
+#[napi(object)]
+#[derive(Clone)]
+pub struct TickEvent {
+  pub id: Option<u32>,
+  pub subscribe_index: i32,
+  /** 剔除原因，包含重复的订阅项位置信息 */
+  pub info: String,
+}
+
+/// 重新订阅成功的订阅项
+#[napi(object)]
+#[derive(Clone)]
+pub struct ResubscribedItem {
+  pub id: u32,
+  pub topic: String,
+  pub old_index: i32,
+  pub new_index: i32,
+}
+
+/// resubscribed 事件
+#[napi(object)]
+#[derive(Clone)]
+pub struct ResubscribedEvent {
+  pub subscriptions: Vec<ResubscribedItem>,
+}
+
+/// resubscribeFailed 事件，每个失败的订阅项触发一次
+#[napi(object)]
+#[derive(Clone)]
+pub struct ResubscribeFailedEvent {
+  pub id: u32,
+  pub topic: String,
+  pub error_no: i32,
+  pub error_info: Option<String>,
+}
+
+/// gap 事件，收到的消息序号与上一条不连续
+#[napi(object)]
+#[derive(Clone)]
+pub struct GapEvent {
+  pub id: Option<u32>,
+  pub topic: String,
+  /** 订阅项的唯一键，如 `topic?a=1&b=2` */
+  pub key: String,
+  /** 期望的消息序号 */
+  pub expected: i64,
+  /** 实际收到的消息序号 */
+  pub received: i64,
+}
+
+/// duplicate 事件，收到的消息序号不大于上一条
+#[napi(object)]
+#[derive(Clone)]
+pub struct DuplicateEvent {
+  pub id: Option<u32>,
+  pub topic: String,
+  pub key: String,
+  pub msg_no: i64,
+  /** 已记录的最后一条消息序号 */
+  pub last_msg_no: i64,
+}
+
+/// reset 事件，消息序号回退（服务端重启或序号重置），检查点已改为新的序号
+#[napi(object)]
+#[derive(Clone)]
+pub struct ResetEvent {
+  pub id: Option<u32>,
+  pub topic: String,
+  pub key: String,
+  pub msg_no: i64,
+  /** 重置前记录的最后一条消息序号 */
+  pub last_msg_no: i64,
+}
+
+/// 订阅项的检查点
+#[napi(object)]
+pub struct CheckpointInfo {
+  pub key: String,
+  pub topic: String,
+  pub filters: HashMap<String, String>,
+  pub msg_no: i64,
+}
+
+#[napi(object)]
+pub struct SubscriberOptions {
+  /** 超时时间（毫秒），默认 5000 */
+  pub timeout_ms: Option<i32>,
+  /** 接收队列初始长度，默认 256 */
+  pub init_recv_q_len: Option<i32>,
+  /** 接收队列扩展步长，默认 512 */
+  pub step_recv_q_len: Option<i32>,
+  /** 重连后是否自动重新订阅，默认 true */
+  pub auto_resubscribe: Option<bool>,
+  /** 检查订阅会话状态、写入检查点文件的间隔（毫秒），默认 1000 */
+  pub session_check_interval_ms: Option<u32>,
+  /** 业务体中的消息序号字段，设置后按订阅项检测缺失和重复的消息 */
+  pub msg_no_field: Option<String>,
+  /**
+   * 检查点文件，保存每个订阅项最后的消息序号，需要同时设置 msgNoField
+   * 检查点只在客户端使用：有检查点的订阅项以补缺方式订阅，由服务端重发，收到后按序号去重；
+   * 不支持从检查点的序号续订：SDK 的订阅接口没有设置起始序号的方法，服务端不再保留的消息只能通过 gap 事件发现
+   */
+  pub checkpoint_file: Option<String>,
+  /** 是否丢弃重复的消息，默认 true */
+  pub drop_duplicates: Option<bool>,
+  /** 序号回退到 1 或回退达到该值时视为序号重置（触发 reset 事件并改写检查点），而不是重复，默认 1000 */
+  pub msg_no_reset_threshold: Option<u32>,
+}
+
+/// 订阅参数
+#[napi(object)]
+#[derive(Clone)]
+pub struct SubscribeParams {
+  /** 主题名字 */
+  pub topic: String,
+  /** 过滤条件，字段名到值，最多 16 个 */
+  pub filters: Option<HashMap<String, String>>,
+  /** 发送间隔（秒） */
+  pub send_interval: Option<i32>,
+  /** 返回字段 */
+  pub return_fields: Option<String>,
+  /** 是否补缺，true 表示需要订阅之前的数据；默认 false，有检查点时为 true */
+  pub from_now: Option<bool>,
+  /** 附加数据，推送时原样返回 */
+  pub app_data: Option<Buffer>,
+  /** 业务校验包 */
+  pub biz_check: Option<Buffer>,
+}
+
+/// 批量订阅中单个订阅项的结果
+#[napi(object)]
+pub struct SubscribeResult {
+  /** 订阅项的稳定标识，订阅失败时为空 */
+  pub id: Option<u32>,
+  /** 主题名字 */
+  pub topic: String,
+  /** 订阅标识，订阅失败时为空 */
+  pub subscribe_index: Option<i32>,
+  pub notify_status: i32,
+  pub error_no: i32,
+  pub error_info: Option<String>,
+}
+
+/// 订阅者记录的订阅项
+#[napi(object)]
+pub struct SubscriptionInfo {
+  pub id: u32,
+  pub subscribe_index: i32,
+  pub topic: String,
+  pub filters: Option<HashMap<String, String>>,
+}
+
+/// GetSubcribeTopic 返回的 SDK 当前订阅项
+#[napi(object)]
+pub struct SubscribedTopic {
+  pub subscribe_index: i32,
+  pub topic: String,
+  pub topic_no: i32,
+  pub is_born_topic: bool,
+  pub send_interval: i32,
+  pub return_fields: String,
+  pub is_replace: bool,
+  pub is_from_now: bool,
+}
+
+struct Subscription {
+  id: u32,
+  index: i32,
+  key: String,
+  params: SubscribeParams,
+}
+
+struct SubscribePtr(*mut CSubscribeRust);
+
+unsafe impl Send for SubscribePtr {}
+unsafe impl Sync for SubscribePtr {}
+
+struct SubscriberState {
+  encoding: TextEncoding,
+  timeout_ms: i32,
+  // 创建订阅者的连接，释放后不再调用 SDK，后台线程退出
+  alive: ConnectionAlive,
+  // 调用 SDK 时持有读锁，释放时持有写锁，避免后台线程访问已释放的订阅者
+  ptr: RwLock<SubscribePtr>,
+  closed: AtomicBool,
+  next_id: AtomicU32,
+  subscriptions: Mutex<Vec<Subscription>>,
+  tracker: Option<MsgNoTracker>,
+  queues: Arc<Mutex<Vec<Arc<QueueInner>>>>,
+  on_message: Listeners<PushMessage>,
+  on_tick: Listeners<TickEvent>,
+  on_gap: Listeners<GapEvent>,
+  on_duplicate: Listeners<DuplicateEvent>,
+  on_reset: Listeners<ResetEvent>,
+  on_resubscribed: Listeners<ResubscribedEvent>,
+  on_resubscribe_failed: Listeners<ResubscribeFailedEvent>,
+}
+
+impl SubscriberState {
+  /// 在连接和订阅者都未释放时调用 SDK
+  fn with_sub<R>(&self, f: impl FnOnce(&CSubscribeRust) -> R) -> Result<R> {
+    let _guard = self.alive.guard()?;
+    let ptr = self.ptr.read().unwrap();
+    if ptr.0.is_null() {
+      return Err(napi::Error::from_reason("Subscriber pointer is null"));
+    }
+    Ok(f(unsafe { &*ptr.0 }))
+  }
+
+  fn id_of(&self, subscribe_index: i32) -> Option<u32> {
+    let subscriptions = self.subscriptions.lock().unwrap();
+    subscriptions.iter().find(|s| s.index == subscribe_index).map(|s| s.id)
+  }
+
+  /// 批量订阅并记录成功的订阅项，返回每个结果对应的参数下标
+  fn subscribe(&self, params: &[SubscribeParams], timeout: u32) -> Result<Vec<(SubscribeResult, Option<usize>)>> {
+    let results = self.with_sub(|sub| batch_subscribe(sub, params, self.encoding, timeout))??;
+    Ok(match_results(params, results))
+  }
+
+  /**
+   * 重新订阅 SDK 中已不存在的订阅项
+   * 先通过 GetSubcribeTopic 取得仍有效的订阅标识，只补订缺失的部分
+   * GetSubcribeTopic 失败时无法判断哪些订阅项仍有效，跳过本轮并返回 false，由下一轮重试
+   */
+  fn resubscribe(&self) -> bool {
+    let active: Vec<i32> = match self.with_sub(|sub| subscribed_topics(sub, self.encoding)).and_then(|r| r) {
+      Ok(topics) => topics.iter().map(|t| t.subscribe_index).collect(),
+      Err(e) => {
+        log(LogLevel::Warn, Target::Receive, || {
+          format!("Failed to get subscribed topics, resubscribe skipped: {}", e.reason)
+        });
+        return false;
+      }
+    };
+    let lost: Vec<(u32, i32, SubscribeParams)> = {
+      let subscriptions = self.subscriptions.lock().unwrap();
+      subscriptions
+        .iter()
+        .filter(|s| !active.contains(&s.index))
+        .map(|s| (s.id, s.index, s.params.clone()))
+        .collect()
+    };
+    if lost.is_empty() {
+      return true;
+    }
+
+    let params: Vec<SubscribeParams> = lost.iter().map(|(_, _, p)| p.clone()).collect();
+    let results = match self.subscribe(&params, self.timeout_ms as u32) {
+      Ok(results) => results,
+      Err(e) => {
+        for (id, _, params) in lost {
+          self.on_resubscribe_failed.emit(ResubscribeFailedEvent {
+            id,
+            topic: params.topic,
+            error_no: -1,
+            error_info: Some(e.reason.clone()),
+          });
+        }
+        return true;
+      }
+    };
+
+    let mut resubscribed = Vec::new();
+    let mut answered = vec![false; lost.len()];
+    {
+      let mut subscriptions = self.subscriptions.lock().unwrap();
+      for (result, param_index) in results {
+        let Some(param_index) = param_index else { continue };
+        answered[param_index] = true;
+        let (id, old_index, params) = &lost[param_index];
+        match result.subscribe_index {
+          Some(new_index) => {
+            if let Some(subscription) = subscriptions.iter_mut().find(|s| s.id == *id) {
+              subscription.index = new_index;
+            }
+            resubscribed.push(ResubscribedItem {
+              id: *id,
+              topic: params.topic.clone(),
+              old_index: *old_index,
+              new_index,
+            });
+          }
+          None => self.on_resubscribe_failed.emit(ResubscribeFailedEvent {
+            id: *id,
+            topic: params.topic.clone(),
+            error_no: result.error_no,
+            error_info: result.error_info,
+          }),
+        }
+      }
+    }
+    for (index, (id, _, params)) in lost.iter().enumerate() {
+      if !answered[index] {
+        self.on_resubscribe_failed.emit(ResubscribeFailedEvent {
+          id: *id,
+          topic: params.topic.clone(),
+          error_no: -1,
+          error_info: Some("No answer for subscription".to_string()),
+        });
+      }
+    }
+    if !resubscribed.is_empty() {
+      self.on_resubscribed.emit(ResubscribedEvent {
+        subscriptions: resubscribed,
+      });
+    }
+    true
+  }
+}
+
+/**
+ * 定期写入检查点，并轮询订阅会话状态，连接断开后重新建立时重新订阅
+ * 订阅者或创建它的连接释放后退出
+ */
+fn run_background(state: Arc<SubscriberState>, interval: Duration, auto_resubscribe: bool) {
+  let mut disconnected = false;
+  while !state.closed.load(Ordering::Acquire) && state.alive.is_open() {
+    std::thread::sleep(interval);
+    if let Some(tracker) = &state.tracker {
+      let _ = tracker.save();
+    }
+    if !auto_resubscribe {
+      continue;
+    }
+    let status = match state.with_sub(|sub| unsafe { sub.get_session_status() }) {
+      Ok(status) => status,
+      Err(_) => break,
+    };
+    if status != SESSION_CONNECTED {
+      disconnected = true;
+    } else if disconnected {
+      disconnected = !state.resubscribe();
+    }
+  }
+}
+
+#[repr(C)]
+pub(crate) struct SubCallbackRust {
+  vtable: *const SubCallbackVTable,
+  state: Arc<SubscriberState>,
+}
+
+#[repr(C)]
+struct SubCallbackVTable {
+  iknown: IKnownVTable,
+  on_received: extern "C" fn(
+    this: *mut SubCallbackRust,
+    sub: *mut CSubscribeRust,
+    subscribe_index: c_int,
+    data: *const c_void,
+    len: c_int,
+    recv_data: *const SubscribeRecvData,
+  ),
+  on_recv_tick_msg: extern "C" fn(this: *mut SubCallbackRust, sub: *mut CSubscribeRust, subscribe_index: c_int, info: *const c_char),
+}
+
+unsafe fn copy_buffer(ptr: *const c_void, len: c_int) -> Option<Buffer> {
+  if ptr.is_null() || len <= 0 {
+    return None;
+  }
+  Some(Buffer::from(std::slice::from_raw_parts(ptr as *const u8, len as usize)))
+}
+
+/// 在推送的业务体上直接建立解包器，不复制数据，解包器只读取缓冲区
+fn unpack_payload(data: &[u8], encoding: TextEncoding) -> Option<UnPacker> {
+  if data.is_empty() {
+    return None;
+  }
+  let mut unpacker = UnPacker::new(data.as_ptr() as *mut c_void, data.len() as c_int, 0).ok()?;
+  unpacker.set_encoding(encoding);
+  unpacker.set_coerce(true);
+  Some(unpacker)
+}
+
+extern "C" fn on_received(
+  this: *mut SubCallbackRust,
+  _sub: *mut CSubscribeRust,
+  subscribe_index: c_int,
+  data: *const c_void,
+  len: c_int,
+  recv_data: *const SubscribeRecvData,
+) {
+  let state = unsafe { &(*this).state };
+  let mut message = PushMessage {
+    id: state.id_of(subscribe_index),
+    subscribe_index,
+    topic: String::new(),
+    msg_no: None,
+    data: unsafe { copy_buffer(data, len) }.unwrap_or_default(),
+    filter_data: None,
+    app_data: None,
+  };
+  if let Some(recv_data) = unsafe { recv_data.as_ref() } {
+    unsafe {
+      message.topic = state.encoding.decode_c_str(recv_data.topic_name.as_ptr()).unwrap_or_default();
+      message.filter_data = copy_buffer(recv_data.filter_data as *const c_void, recv_data.filter_data_len);
+      message.app_data = copy_buffer(recv_data.app_data as *const c_void, recv_data.app_data_len);
+    }
+  }
+
+  if let Some(tracker) = &state.tracker {
+    message.msg_no = unpack_payload(&message.data, state.encoding).and_then(|unpacker| tracker.read_msg_no(&unpacker));
+    let recorded = message.msg_no.and_then(|msg_no| {
+      let subscriptions = state.subscriptions.lock().unwrap();
+      let subscription = subscriptions.iter().find(|s| s.index == subscribe_index)?;
+      let params = &subscription.params;
+      let sequence = tracker.record(&subscription.key, &params.topic, params.filters.as_ref(), msg_no);
+      Some((sequence, subscription.key.clone(), params.topic.clone(), msg_no))
+    });
+    match recorded {
+      Some((Sequence::Gap { expected }, key, topic, received)) => state.on_gap.emit(GapEvent {
+        id: message.id,
+        topic,
+        key,
+        expected,
+        received,
+      }),
+      Some((Sequence::Duplicate { last }, key, topic, msg_no)) => {
+        state.on_duplicate.emit(DuplicateEvent {
+          id: message.id,
+          topic,
+          key,
+          msg_no,
+          last_msg_no: last,
+        });
+        if tracker.drop_duplicates {
+          return;
+        }
+      }
+      Some((Sequence::Reset { last }, key, topic, msg_no)) => state.on_reset.emit(ResetEvent {
+        id: message.id,
+        topic,
+        key,
+        msg_no,
+        last_msg_no: last,
+      }),
+      _ => {}
+    }
+  }
+
+  let queues: Vec<Arc<QueueInner>> = {
+    let mut queues = state.queues.lock().unwrap();
+    queues.retain(|q| !q.is_closed());
+    queues.iter().filter(|q| q.matches(&message.topic)).cloned().collect()
+  };
+  for queue in queues {
+    queue.push(message.clone());
+  }
+  state.on_message.emit(message);
+}
+
+extern "C" fn on_recv_tick_msg(this: *mut SubCallbackRust, _sub: *mut CSubscribeRust, subscribe_index: c_int, info: *const c_char) {
+  let state = unsafe { &(*this).state };
+  let info = unsafe { state.encoding.decode_c_str(info) }.unwrap_or_default();
+  let id = state.id_of(subscribe_index);
+  // 底层已取消被剔除的订阅项
+  state.subscriptions.lock().unwrap().retain(|s| s.index != subscribe_index);
+  state.on_tick.emit(TickEvent { id, subscribe_index, info });
+}
+
+fn create_sub_callback(state: Arc<SubscriberState>) -> Box<SubCallbackRust> {
+  static VTABLE: SubCallbackVTable = SubCallbackVTable {
+    iknown: IKnownVTable {
+      query_interface: callback_query_interface,
+      add_ref: callback_add_ref,
+      release: callback_release,
+    },
+    on_received,
+    on_recv_tick_msg,
+  };
+
+  Box::new(SubCallbackRust { vtable: &VTABLE, state })
+}
+
+/**
+ * 按 BatchSubscribeTopic 要求的字段打包订阅参数
+ * TopicName SendInterval ReturnFileds isFromNow AppData FilterField1..16 FilterValue1..16 BizCheckPack
+ */
+fn pack_subscribe_params(params: &[SubscribeParams], encoding: TextEncoding) -> Result<Packer> {
+  let mut packer = Packer::new(PACKER_VERSION_V2)?;
+  packer.set_encoding(encoding);
+  packer.begin_pack()?;
+  packer.add_field_def("TopicName", b'S', 255, 0)?;
+  packer.add_field_def("SendInterval", b'I', 0, 0)?;
+  packer.add_field_def("ReturnFileds", b'S', 255, 0)?;
+  packer.add_field_def("isFromNow", b'I', 0, 0)?;
+  packer.add_field_def("AppData", b'R', 0, 0)?;
+  for index in 1..=MAX_FILTERS {
+    packer.add_field_def(&format!("FilterField{}", index), b'S', 63, 0)?;
+    packer.add_field_def(&format!("FilterValue{}", index), b'S', 255, 0)?;
+  }
+  packer.add_field_def("BizCheckPack", b'R', 0, 0)?;
+
+  for (row, param) in params.iter().enumerate() {
+    // HashMap 无序，按字段名排序保证打包结果稳定
+    let mut filters: Vec<(&String, &String)> = param.filters.iter().flatten().collect();
+    filters.sort();
+    if filters.len() > MAX_FILTERS {
+      return Err(napi::Error::from_reason(format!(
+        "params[{}]: at most {} filters are supported, got {}",
+        row,
+        MAX_FILTERS,
+        filters.len()
+      )));
+    }
+
+    packer.add_str(param.topic.clone())?;
+    packer.add_int(param.send_interval.unwrap_or(0))?;
+    packer.add_str(param.return_fields.clone().unwrap_or_default())?;
+    packer.add_int(param.from_now.unwrap_or(false) as i32)?;
+    packer.add_raw_bytes(param.app_data.as_deref().unwrap_or_default())?;
+    for index in 0..MAX_FILTERS {
+      let (name, value) = filters.get(index).map(|(n, v)| (n.as_str(), v.as_str())).unwrap_or(("", ""));
+      packer.add_str(name.to_string())?;
+      packer.add_str(value.to_string())?;
+    }
+    packer.add_raw_bytes(param.biz_check.as_deref().unwrap_or_default())?;
+  }
+  packer.end_pack()?;
+  Ok(packer)
+}
+
+fn get_int(unpacker: &UnPacker, column: &str) -> Result<i32> {
+  if unpacker.find_col_index(column.to_string())? < 0 {
+    return Ok(0);
+  }
+  // 标志位字段可能以字符或字符串返回
+  match unpacker.get_col_type_by_name(column.to_string())? as u8 {
+    b'C' => {
+      let c = unpacker.get_char(column.to_string())? as u8;
+      Ok(if c.is_ascii_digit() { (c - b'0') as i32 } else { c as i32 })
+    }
+    b'S' => Ok(unpacker.get_str(column.to_string())?.trim().parse().unwrap_or(0)),
+    _ => unpacker.get_int(column.to_string()),
+  }
+}
+
+fn get_str(unpacker: &UnPacker, column: &str) -> Result<String> {
+  if unpacker.find_col_index(column.to_string())? < 0 {
+    return Ok(String::new());
+  }
+  unpacker.get_str(column.to_string())
+}
+
+/// 解析 BatchSubscribeTopic 的应答，每行对应一个订阅项
+fn decode_subscribe_results(unpacker: &UnPacker) -> Result<Vec<SubscribeResult>> {
+  let mut results = Vec::new();
+  unpacker.first()?;
+  while unpacker.is_eof()? == 0 {
+    let subscribe_index = get_int(unpacker, "SubscribeIndex")?;
+    let error_no = get_int(unpacker, "ErrorNo")?;
+    let error_info = get_str(unpacker, "ErrorInfo")?;
+    results.push(SubscribeResult {
+      id: None,
+      topic: get_str(unpacker, "TopicName")?,
+      subscribe_index: if subscribe_index > 0 { Some(subscribe_index) } else { None },
+      notify_status: get_int(unpacker, "NotifyStatus")?,
+      error_no,
+      error_info: if error_info.is_empty() { None } else { Some(error_info) },
+    });
+    unpacker.next()?;
+  }
+  Ok(results)
+}
+
+fn batch_subscribe(sub: &CSubscribeRust, params: &[SubscribeParams], encoding: TextEncoding, timeout: u32) -> Result<Vec<SubscribeResult>> {
+  // 从打包结果重新创建解包器，避免释放打包器内部的解包器
+  let request = pack_subscribe_params(params, encoding)?;
+  let mut request_buf = request.get_pack_buf()?.to_vec();
+  let request_unpacker = UnPacker::new(request_buf.as_mut_ptr() as *mut c_void, request_buf.len() as c_int, 0)?;
+
+  let mut answer = Packer::new(PACKER_VERSION_V2)?;
+  answer.set_encoding(encoding);
+  answer.begin_pack()?;
+  let ret = unsafe { sub.batch_subscribe_topic(request_unpacker.get_ptr(), answer.get_ptr(), timeout) };
+  answer.end_pack()?;
+
+  let mut answer_buf = answer.get_pack_buf()?.to_vec();
+  let results = UnPacker::new(answer_buf.as_mut_ptr() as *mut c_void, answer_buf.len() as c_int, 0).and_then(|mut unpacker| {
+    unpacker.set_encoding(encoding);
+    unpacker.set_coerce(true);
+    decode_subscribe_results(&unpacker)
+  });
+  // 整体失败时应答中仍可能带有每个订阅项的失败原因
+  match results {
+    Ok(results) if ret == 0 || !results.is_empty() => Ok(results),
+    Err(e) if ret == 0 => Err(e),
+    _ => Err(napi::Error::from_reason(format!("Failed to batch subscribe: {}", ret))),
+  }
+}
+
+/// 将应答行与订阅参数对应：优先按位置，主题不一致时取第一个未对应的同名主题
+fn match_results(params: &[SubscribeParams], results: Vec<SubscribeResult>) -> Vec<(SubscribeResult, Option<usize>)> {
+  let mut used = vec![false; params.len()];
+  results
+    .into_iter()
+    .enumerate()
+    .map(|(row, result)| {
+      let param_index = if row < params.len() && !used[row] && params[row].topic == result.topic {
+        Some(row)
+      } else {
+        (0..params.len()).find(|&i| !used[i] && params[i].topic == result.topic)
+      };
+      if let Some(i) = param_index {
+        used[i] = true;
+      }
+      (result, param_index)
+    })
+    .collect()
+}
+
+/// 通过 GetSubcribeTopic 获取 SDK 当前的订阅项
+fn subscribed_topics(sub: &CSubscribeRust, encoding: TextEncoding) -> Result<Vec<SubscribedTopic>> {
+  let mut packer = Packer::new(PACKER_VERSION_V2)?;
+  packer.set_encoding(encoding);
+  packer.begin_pack()?;
+  unsafe { sub.get_subcribe_topic(packer.get_ptr()) };
+  packer.end_pack()?;
+
+  let mut buf = packer.get_pack_buf()?.to_vec();
+  let mut unpacker = UnPacker::new(buf.as_mut_ptr() as *mut c_void, buf.len() as c_int, 0)?;
+  unpacker.set_encoding(encoding);
+  unpacker.set_coerce(true);
+  let mut topics = Vec::new();
+  unpacker.first()?;
+  while unpacker.is_eof()? == 0 {
+    topics.push(SubscribedTopic {
+      subscribe_index: get_int(&unpacker, "SubcribeIndex")?,
+      topic: get_str(&unpacker, "TopicName")?,
+      topic_no: get_int(&unpacker, "TopicNo")?,
+      is_born_topic: get_int(&unpacker, "IsBornTopic")? != 0,
+      send_interval: get_int(&unpacker, "SendInterval")?,
+      return_fields: get_str(&unpacker, "ReturnFileds")?,
+      is_replace: get_int(&unpacker, "isReplace")? != 0,
+      is_from_now: get_int(&unpacker, "isFromNow")? != 0,
+    });
+    unpacker.next()?;
+  }
+  Ok(topics)
+}
+
+/**
+ * 订阅者，通过 T2SDK.newSubscriber 创建
+ * 订阅者记录成功的订阅项，连接断开重连后自动重新订阅，订阅项的 id 保持不变
+ * 事件：message（收到推送）、tick（订阅项被剔除）、resubscribed（重新订阅成功）、resubscribeFailed（重新订阅失败）
+ */
+#[napi]
+pub struct Subscriber {
+  // 回调对象需要在订阅者释放前一直有效
+  callback: Box<SubCallbackRust>,
+}
+
+impl Subscriber {
+  /// 创建回调对象后调用 create 取得 CSubscribeInterface
+  pub(crate) fn new<F>(encoding: TextEncoding, alive: ConnectionAlive, options: Option<SubscriberOptions>, create: F) -> Result<Self>
+  where
+    F: FnOnce(*mut c_void, i32, i32, i32) -> Result<*mut CSubscribeRust>,
+  {
+    let timeout_ms = options.as_ref().and_then(|o| o.timeout_ms).unwrap_or(5000);
+    let init_recv_q_len = options.as_ref().and_then(|o| o.init_recv_q_len).unwrap_or(256);
+    let step_recv_q_len = options.as_ref().and_then(|o| o.step_recv_q_len).unwrap_or(512);
+    let auto_resubscribe = options.as_ref().and_then(|o| o.auto_resubscribe).unwrap_or(true);
+    let interval = options.as_ref().and_then(|o| o.session_check_interval_ms).unwrap_or(1000);
+    let drop_duplicates = options.as_ref().and_then(|o| o.drop_duplicates).unwrap_or(true);
+    let reset_threshold = options.as_ref().and_then(|o| o.msg_no_reset_threshold).unwrap_or(1000);
+    let checkpoint_file = options.as_ref().and_then(|o| o.checkpoint_file.clone());
+    let tracker = match options.and_then(|o| o.msg_no_field) {
+      Some(field) => Some(MsgNoTracker::new(field, checkpoint_file, drop_duplicates, reset_threshold as i64)?),
+      None if checkpoint_file.is_some() => return Err(napi::Error::from_reason("checkpointFile requires msgNoField")),
+      None => None,
+    };
+    let state = Arc::new(SubscriberState {
+      encoding,
+      timeout_ms,
+      alive,
+      ptr: RwLock::new(SubscribePtr(std::ptr::null_mut())),
+      closed: AtomicBool::new(false),
+      next_id: AtomicU32::new(1),
+      subscriptions: Mutex::new(Vec::new()),
+      tracker,
+      queues: Arc::new(Mutex::new(Vec::new())),
+      on_message: Listeners::new(),
+      on_tick: Listeners::new(),
+      on_gap: Listeners::new(),
+      on_duplicate: Listeners::new(),
+      on_reset: Listeners::new(),
+      on_resubscribed: Listeners::new(),
+      on_resubscribe_failed: Listeners::new(),
+    });
+    let mut callback = create_sub_callback(state.clone());
+    let ptr = create(
+      &mut *callback as *mut SubCallbackRust as *mut c_void,
+      timeout_ms,
+      init_recv_q_len,
+      step_recv_q_len,
+    )?;
+    *state.ptr.write().unwrap() = SubscribePtr(ptr);
+
+    if auto_resubscribe || state.tracker.is_some() {
+      let interval = Duration::from_millis(interval.max(1) as u64);
+      std::thread::spawn(move || run_background(state, interval, auto_resubscribe));
+    }
+    Ok(Subscriber { callback })
+  }
+
+  fn state(&self) -> &SubscriberState {
+    &self.callback.state
+  }
+}
+
+#[napi]
+impl Subscriber {
+  /**
+   * 注册事件监听
+   * - message: `(message: PushMessage) => void`
+   * - tick: `(event: TickEvent) => void`
+   * - resubscribed: `(event: ResubscribedEvent) => void`
+   * - resubscribeFailed: `(event: ResubscribeFailedEvent) => void`
+   * - gap: `(event: GapEvent) => void`，需要设置 msgNoField
+   * - duplicate: `(event: DuplicateEvent) => void`，需要设置 msgNoField
+   * - reset: `(event: ResetEvent) => void`，需要设置 msgNoField
+   */
+  #[napi(
+    ts_args_type = "event: 'message' | 'tick' | 'resubscribed' | 'resubscribeFailed' | 'gap' | 'duplicate' | 'reset', callback: (event: any) => void"
+  )]
+  pub fn on(&self, env: Env, event: String, callback: JsFunction) -> Result<()> {
+    let state = self.state();
+    match event.as_str() {
+      "message" => state.on_message.add(&env, callback),
+      "tick" => state.on_tick.add(&env, callback),
+      "resubscribed" => state.on_resubscribed.add(&env, callback),
+      "resubscribeFailed" => state.on_resubscribe_failed.add(&env, callback),
+      "gap" => state.on_gap.add(&env, callback),
+      "duplicate" => state.on_duplicate.add(&env, callback),
+      "reset" => state.on_reset.add(&env, callback),
+      _ => Err(napi::Error::from_reason(format!("Unknown event: {}", event))),
+    }
+  }
+
+  /// 移除所有事件监听
+  #[napi]
+  pub fn remove_all_listeners(&self) {
+    let state = self.state();
+    state.on_message.clear();
+    state.on_tick.clear();
+    state.on_resubscribed.clear();
+    state.on_resubscribe_failed.clear();
+    state.on_gap.clear();
+    state.on_duplicate.clear();
+    state.on_reset.clear();
+  }
+
+  /**
+   * 批量订阅，一次往返创建多个订阅项
+   * @param params 订阅参数列表
+   * @param timeoutMs 超时时间，默认为创建时的超时时间
+   * @returns 每个订阅项的结果，顺序与服务端应答一致；失败的订阅项没有 id / subscribeIndex，带有 errorNo / errorInfo
+   *
+   * 订阅项有检查点且未指定 fromNow 时按补缺订阅，已处理过的消息按序号去重
+   */
+  #[napi]
+  pub async fn subscribe_many(&self, mut params: Vec<SubscribeParams>, timeout_ms: Option<u32>) -> Result<Vec<SubscribeResult>> {
+    if params.is_empty() {
+      return Ok(Vec::new());
+    }
+    let state = self.callback.state.clone();
+    let keys: Vec<String> = params.iter().map(|p| subscription_key(&p.topic, p.filters.as_ref())).collect();
+    if let Some(tracker) = &state.tracker {
+      for (param, key) in params.iter_mut().zip(&keys) {
+        if param.from_now.is_none() && tracker.has_checkpoint(key) {
+          param.from_now = Some(true);
+        }
+      }
+    }
+    let timeout = timeout_ms.unwrap_or(state.timeout_ms as u32);
+    // BatchSubscribeTopic 同步等待应答，在阻塞线程池中执行，避免阻塞 JS 线程
+    let (params, results) = tokio::task::spawn_blocking(move || {
+      let results = state.subscribe(&params, timeout);
+      (params, results)
+    })
+    .await
+    .map_err(|e| napi::Error::from_reason(format!("Task join error: {}", e)))?;
+    let results = results?;
+    let state = self.state();
+
+    let mut subscriptions = state.subscriptions.lock().unwrap();
+    Ok(
+      results
+        .into_iter()
+        .map(|(mut result, param_index)| {
+          if let (Some(index), Some(param_index)) = (result.subscribe_index, param_index) {
+            let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+            subscriptions.push(Subscription {
+              id,
+              index,
+              key: keys[param_index].clone(),
+              params: params[param_index].clone(),
+            });
+            result.id = Some(id);
+          }
+          result
+        })
+        .collect(),
+    )
+  }
+
+  /**
+   * 按稳定标识取消订阅
+   * @param id 订阅项的 id，来自 subscribeMany 的返回
+   * @returns 0 表示成功
+   */
+  #[napi]
+  pub fn unsubscribe(&self, id: u32) -> Result<i32> {
+    let state = self.state();
+    let index = state
+      .subscriptions
+      .lock()
+      .unwrap()
+      .iter()
+      .find(|s| s.id == id)
+      .map(|s| s.index)
+      .ok_or_else(|| napi::Error::from_reason(format!("Unknown subscription id: {}", id)))?;
+    self.cancel(index)
+  }
+
+  /**
+   * 按 SDK 的订阅标识取消订阅
+   * @param subscribeIndex 订阅标识
+   * @returns 0 表示成功
+   */
+  #[napi]
+  pub fn cancel(&self, subscribe_index: i32) -> Result<i32> {
+    let state = self.state();
+    let ret = state.with_sub(|sub| unsafe { sub.cancel_subscribe_topic(subscribe_index) })?;
+    if ret == 0 {
+      state.subscriptions.lock().unwrap().retain(|s| s.index != subscribe_index);
+    }
+    Ok(ret)
+  }
+
+  /// 获取订阅者记录的订阅项，重连后用于重新订阅
+  #[napi]
+  pub fn get_subscriptions(&self) -> Vec<SubscriptionInfo> {
+    let subscriptions = self.state().subscriptions.lock().unwrap();
+    subscriptions
+      .iter()
+      .map(|s| SubscriptionInfo {
+        id: s.id,
+        subscribe_index: s.index,
+        topic: s.params.topic.clone(),
+        filters: s.params.filters.clone(),
+      })
+      .collect()
+  }
+
+  /**
+   * 创建推送消息的有界队列，用于按需读取消息
+   * @param topic 只接收该主题的消息，默认接收所有主题
+   */
+  #[napi]
+  pub fn create_queue(&self, topic: Option<String>, options: Option<QueueOptions>) -> MessageQueue {
+    let inner = Arc::new(QueueInner::new(topic, options));
+    let queues = &self.state().queues;
+    queues.lock().unwrap().push(inner.clone());
+    MessageQueue::new(inner, Arc::downgrade(queues))
+  }
+
+  /// 获取每个订阅项最后收到的消息序号
+  #[napi]
+  pub fn get_checkpoints(&self) -> Vec<CheckpointInfo> {
+    let Some(tracker) = &self.state().tracker else { return Vec::new() };
+    tracker
+      .checkpoints()
+      .into_iter()
+      .map(|(key, checkpoint)| CheckpointInfo {
+        key,
+        topic: checkpoint.topic,
+        filters: checkpoint.filters.into_iter().collect(),
+        msg_no: checkpoint.msg_no,
+      })
+      .collect()
+  }
+
+  /// 立即写入检查点文件
+  #[napi]
+  pub fn save_checkpoints(&self) -> Result<()> {
+    match &self.state().tracker {
+      Some(tracker) => tracker.save(),
+      None => Ok(()),
+    }
+  }
+
+  /// 获取 SDK 当前的订阅项（GetSubcribeTopic）
+  #[napi]
+  pub fn get_subscribed_topics(&self) -> Result<Vec<SubscribedTopic>> {
+    let state = self.state();
+    state.with_sub(|sub| subscribed_topics(sub, state.encoding))?
+  }
+
+  /**
+   * 获取订阅会话状态
+   * @returns 0 与消息中心连接断开，1 正在建立连接，2 连接已建立
+   */
+  #[napi]
+  pub fn get_session_status(&self) -> Result<i32> {
+    self.state().with_sub(|sub| unsafe { sub.get_session_status() })
+  }
+}
+
+unsafe impl Send for Subscriber {}
+unsafe impl Sync for Subscriber {}
+
+impl Drop for Subscriber {
+  fn drop(&mut self) {
+    let state = self.state();
+    state.closed.store(true, Ordering::Release);
+    // 先关闭队列，唤醒阻塞在队列上的 SDK 回调线程
+    for queue in state.queues.lock().unwrap().drain(..) {
+      queue.close();
+    }
+    if let Some(tracker) = &state.tracker {
+      let _ = tracker.save();
+    }
+    let guard = state.alive.guard();
+    let mut ptr = state.ptr.write().unwrap();
+    // 连接已释放时不再调用 SDK
+    if !ptr.0.is_null() && guard.is_ok() {
+      unsafe { (*ptr.0).release() };
+    }
+    ptr.0 = std::ptr::null_mut();
+  }
+}
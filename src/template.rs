@@ -0,0 +1,156 @@
+use napi::bindgen_prelude::{Buffer, Reference, ToNapiValue};
+use napi::{Env, JsObject, JsUnknown, NapiValue, Result};
+use napi_derive::napi;
+use std::time::Duration;
+
+use crate::biz_message::BizMessage;
+use crate::def::{PACKER_VERSION_V2, REQUEST_PACKET};
+use crate::f2_packer::Packer;
+use crate::pack::{self, FieldDef, PackInput, PackValue};
+use crate::{SendOptions, T2SDK};
+
+// 未声明宽度的 'S'、'R' 字段的宽度，与 SDK AddField 的默认值一致
+const DEFAULT_STR_WIDTH: usize = 255;
+
+#[napi(object)]
+pub struct TemplateOptions {
+  pub system_no: Option<i32>,
+  pub branch_no: Option<i32>,
+  pub sub_system_no: Option<i32>,
+  pub company_id: Option<i32>,
+}
+
+/**
+ * 预编译的请求模板，固定功能号和字段列表
+ * 每次 send 复用同一个打包器和消息，按声明的字段类型转换取值，不再推断类型
+ */
+#[napi]
+pub struct PackTemplate {
+  sdk: Reference<T2SDK>,
+  function_no: i32,
+  options: TemplateOptions,
+  fields: Vec<FieldDef>,
+  packer: Packer,
+  message: BizMessage,
+}
+
+impl PackTemplate {
+  pub(crate) fn new(sdk: Reference<T2SDK>, function_no: i32, field_schema: &PackValue, options: Option<TemplateOptions>) -> Result<Self> {
+    let mut fields = pack::parse_field_list(field_schema)?;
+    if fields.is_empty() {
+      return Err(napi::Error::from_reason("fieldSchema must not be empty"));
+    }
+    if let Some(i) = fields.iter().position(|f| f.field_type == 0) {
+      return Err(napi::Error::from_reason(format!("fieldSchema[{}] must declare a type", i)));
+    }
+    // 宽度在编译时确定，send 时不随取值变化
+    for field in fields.iter_mut().filter(|f| f.width <= 0) {
+      field.width = pack::default_width(field.field_type, DEFAULT_STR_WIDTH);
+    }
+    Ok(PackTemplate {
+      sdk,
+      function_no,
+      options: options.unwrap_or(TemplateOptions {
+        system_no: None,
+        branch_no: None,
+        sub_system_no: None,
+        company_id: None,
+      }),
+      fields,
+      packer: Packer::new(PACKER_VERSION_V2)?,
+      message: BizMessage::new()?,
+    })
+  }
+
+  /// 打包一行并填写请求头，结果留在复用的消息中
+  fn prepare(&mut self, values: &PackValue, strict: bool, decimal: bool, trace_id: Option<String>) -> Result<()> {
+    let encoding = self.sdk.config.encoding();
+    let cells = pack::convert_row(&self.fields, values, strict, decimal, encoding)?;
+    for (field, cell) in self.fields.iter().zip(&cells) {
+      if cell.len() > field.width as usize {
+        return Err(napi::Error::from_reason(format!(
+          "Value of field {} is {} bytes, exceeds width {}",
+          field.name,
+          cell.len(),
+          field.width
+        )));
+      }
+    }
+
+    let packer = &mut self.packer;
+    packer.set_encoding(encoding);
+    packer.begin_pack()?;
+    for field in &self.fields {
+      if packer.add_field_def(&field.name, field.field_type, field.width, field.scale)? < 0 {
+        return Err(napi::Error::from_reason(format!("Failed to add field {}", field.name)));
+      }
+    }
+    pack::write_row(packer, &self.fields, &cells)?;
+    packer.end_pack()?;
+
+    let message = &self.message;
+    message.reset()?;
+    message.set_packet_type(REQUEST_PACKET)?;
+    message.set_function(self.function_no)?;
+    if let Some(system_no) = self.options.system_no {
+      message.set_system_no(system_no)?;
+    }
+    if let Some(branch_no) = self.options.branch_no {
+      message.set_branch_no(branch_no)?;
+    }
+    if let Some(sub_system_no) = self.options.sub_system_no {
+      message.set_sub_system_no(sub_system_no)?;
+    }
+    if let Some(company_id) = self.options.company_id {
+      message.set_company_id(company_id)?;
+    }
+    message.set_content_bytes(packer.pack_bytes()?)?;
+    if let Some(trace_id) = trace_id {
+      message.set_app_reserved(Buffer::from(trace_id.into_bytes()))?;
+    }
+    Ok(())
+  }
+}
+
+#[napi]
+impl PackTemplate {
+  #[napi(getter)]
+  pub fn function_no(&self) -> i32 {
+    self.function_no
+  }
+
+  /// 字段名，按打包顺序
+  #[napi(getter)]
+  pub fn field_names(&self) -> Vec<String> {
+    self.fields.iter().map(|f| f.name.clone()).collect()
+  }
+
+  /**
+   * 按模板打包一行并发送，转换规则与 T2SDK.send 相同
+   * @param values 对象（按字段名取值）或数组（按字段顺序取值），缺少的字段按空值打包
+   */
+  #[napi(
+    ts_args_type = "values: object | Array<unknown>, options?: SendOptions",
+    ts_return_type = "Promise<BizMessage>"
+  )]
+  pub fn send(&mut self, env: Env, values: PackInput, options: Option<SendOptions>) -> Result<JsObject> {
+    if !self.sdk.is_connected() {
+      return Err(napi::Error::from_reason("Not connected".to_string()));
+    }
+    let (strict, decimal, trace_id) = options.map_or((None, None, None), |o| (o.strict, o.decimal, o.trace_id));
+    let trace = self.sdk.new_trace(trace_id.clone(), self.function_no);
+    self.prepare(&values.0, strict.unwrap_or(false), decimal.unwrap_or(false), trace_id)?;
+    if let Some(trace) = &trace {
+      trace.span("pack");
+    }
+
+    let sdk = &*self.sdk;
+    let conn = sdk.connection.as_ref().unwrap();
+    // SDK 在发送时复制消息，返回后即可复用
+    let answer = conn.call(self.message.get_ptr(), sdk.config.encoding(), Duration::from_secs(30), trace)?;
+    env.execute_tokio_future(answer, |env, message| unsafe {
+      let value = BizMessage::to_napi_value(env.raw(), message)?;
+      Ok(JsUnknown::from_raw_unchecked(env.raw(), value))
+    })
+  }
+}
@@ -0,0 +1,96 @@
+use napi::Result;
+use napi_derive::napi;
+
+use crate::f2_unpacker::UnPacker;
+
+/// 过滤字段最多 16 个，列名为 FilterField1 ~ FilterField16
+const MAX_FILTER_FIELDS: usize = 16;
+
+/// 消息中心的主题信息，来自 GetTopic
+#[napi(object)]
+#[derive(Clone)]
+pub struct TopicInfo {
+  /** 主题名字 */
+  pub name: String,
+  /** 主题编号 */
+  pub no: i32,
+  /** 可靠等级 */
+  pub reliable_level: i32,
+  /** 发布优先级 */
+  pub issue_priority: i32,
+  /** 消息有效期 */
+  pub msg_lifetime: i32,
+  /** 主题状态 */
+  pub status: i32,
+  /** 剔除策略 */
+  pub tick_strategy: i32,
+  /** 是否业务校验 */
+  pub business_verify: i32,
+  /** 是否本地主题 */
+  pub local: i32,
+  /** 过滤字段名，按 FilterField1 ~ FilterField16 的顺序，忽略空值 */
+  pub filter_fields: Vec<String>,
+  /** 订阅者列表 */
+  pub subscribe_str: String,
+  /** 发布者列表 */
+  pub publish_str: String,
+}
+
+#[napi(object)]
+pub struct GetTopicsOptions {
+  /** 是否强制从服务端获取，默认使用缓存 */
+  pub force: Option<bool>,
+  /** 超时时间（毫秒），默认 5000 */
+  pub timeout_ms: Option<i32>,
+}
+
+fn get_str(unpacker: &UnPacker, column: &str) -> Result<String> {
+  if unpacker.find_col_index(column.to_string())? < 0 {
+    return Ok(String::new());
+  }
+  unpacker.get_str(column.to_string())
+}
+
+fn get_int(unpacker: &UnPacker, column: &str) -> Result<i32> {
+  if unpacker.find_col_index(column.to_string())? < 0 {
+    return Ok(0);
+  }
+  unpacker.get_int(column.to_string())
+}
+
+/// 逐行解析 GetTopic 返回的解包器，缺失的列取默认值
+pub(crate) fn decode_topics(unpacker: &UnPacker) -> Result<Vec<TopicInfo>> {
+  // 头文件中的列名为 Stutas，兼容拼写正确的 Status
+  let status_column = if unpacker.find_col_index("Stutas".to_string())? >= 0 {
+    "Stutas"
+  } else {
+    "Status"
+  };
+  let mut topics = Vec::new();
+  unpacker.first()?;
+  while unpacker.is_eof()? == 0 {
+    let mut filter_fields = Vec::new();
+    for index in 1..=MAX_FILTER_FIELDS {
+      let field = get_str(unpacker, &format!("FilterField{}", index))?;
+      if !field.is_empty() {
+        filter_fields.push(field);
+      }
+    }
+    topics.push(TopicInfo {
+      name: get_str(unpacker, "TopicName")?,
+      no: get_int(unpacker, "TopicNo")?,
+      reliable_level: get_int(unpacker, "ReliableLevel")?,
+      issue_priority: get_int(unpacker, "IssuePriority")?,
+      msg_lifetime: get_int(unpacker, "MsgLifetime")?,
+      status: get_int(unpacker, status_column)?,
+      tick_strategy: get_int(unpacker, "TickStrategy")?,
+      business_verify: get_int(unpacker, "BusinessVerify")?,
+      local: get_int(unpacker, "Local")?,
+      filter_fields,
+      subscribe_str: get_str(unpacker, "SubscribeStr")?,
+      publish_str: get_str(unpacker, "PublishStr")?,
+    });
+    unpacker.next()?;
+  }
+  Ok(topics)
+}
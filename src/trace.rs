@@ -0,0 +1,129 @@
+use napi_derive::napi;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::biz_message::BizMessage;
+use crate::event::Listeners;
+
+/// 请求的一个阶段：pack（打包）、enqueue（提交给 SDK）、sent（SDK 发出）、answered（收到应答）
+#[napi(object)]
+#[derive(Clone)]
+pub struct TraceSpan {
+  pub name: String,
+  /** 毫秒时间戳 */
+  pub start_time: f64,
+  pub end_time: f64,
+}
+
+/**
+ * 一次请求的跟踪记录，请求结束（收到应答、超时或发送失败）时通过 `sdk.on('trace', ...)` 派发
+ * 可在 JS 侧转为 OpenTelemetry 的 span
+ */
+#[napi(object)]
+#[derive(Clone)]
+pub struct RequestTrace {
+  pub trace_id: Option<String>,
+  pub function_no: i32,
+  pub h_send: Option<i32>,
+  /** 'ok' | 'error' | 'timeout' | 'sendFailed' */
+  pub status: String,
+  pub return_code: Option<i32>,
+  pub error_no: Option<i32>,
+  pub error_info: Option<String>,
+  /** 应答中带回的 traceId */
+  pub answer_trace_id: Option<String>,
+  pub start_time: f64,
+  pub end_time: f64,
+  pub spans: Vec<TraceSpan>,
+}
+
+fn now() -> f64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0.0, |d| d.as_secs_f64() * 1000.0)
+}
+
+struct TraceState {
+  h_send: Option<i32>,
+  spans: Vec<TraceSpan>,
+  // 最近一个阶段的结束时间，下一阶段从这里开始
+  last: f64,
+}
+
+/// 进行中的请求跟踪，在 JS 线程和 SDK 回调线程之间共享
+pub(crate) struct Trace {
+  listeners: Arc<Listeners<RequestTrace>>,
+  trace_id: Option<String>,
+  function_no: i32,
+  start_time: f64,
+  state: Mutex<TraceState>,
+}
+
+impl Trace {
+  pub fn new(listeners: Arc<Listeners<RequestTrace>>, trace_id: Option<String>, function_no: i32) -> Self {
+    let start_time = now();
+    Trace {
+      listeners,
+      trace_id,
+      function_no,
+      start_time,
+      state: Mutex::new(TraceState {
+        h_send: None,
+        spans: Vec::new(),
+        last: start_time,
+      }),
+    }
+  }
+
+  /// 记录从上一阶段结束到现在的阶段
+  pub fn span(&self, name: &str) {
+    let mut state = self.state.lock().unwrap();
+    let end_time = now();
+    let start_time = state.last;
+    state.spans.push(TraceSpan {
+      name: name.to_string(),
+      start_time,
+      end_time,
+    });
+    state.last = end_time;
+  }
+
+  pub fn set_h_send(&self, h_send: i32) {
+    self.state.lock().unwrap().h_send = Some(h_send);
+  }
+
+  /// 结束跟踪并派发，answer 为 None 时 status 为 timeout 或 sendFailed
+  pub fn finish(&self, status: &str, answer: Option<&BizMessage>) {
+    let state = self.state.lock().unwrap();
+    let mut trace = RequestTrace {
+      trace_id: self.trace_id.clone(),
+      function_no: self.function_no,
+      h_send: state.h_send,
+      status: status.to_string(),
+      return_code: None,
+      error_no: None,
+      error_info: None,
+      answer_trace_id: None,
+      start_time: self.start_time,
+      end_time: now(),
+      spans: state.spans.clone(),
+    };
+    drop(state);
+    if let Some(answer) = answer {
+      let return_code = answer.get_return_code().unwrap_or_default();
+      let error_no = answer.get_error_no().unwrap_or_default();
+      if return_code != 0 || error_no != 0 {
+        trace.status = "error".to_string();
+        trace.error_info = answer.get_error_info().ok().filter(|s| !s.is_empty());
+      }
+      trace.return_code = Some(return_code);
+      trace.error_no = Some(error_no);
+      trace.answer_trace_id = answer
+        .get_app_reserved()
+        .ok()
+        .filter(|b| !b.is_empty())
+        .map(|b| String::from_utf8_lossy(&b).into_owned());
+    }
+    self.listeners.emit(trace);
+  }
+}
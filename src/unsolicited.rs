@@ -0,0 +1,110 @@
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+
+use crate::biz_message::{BizMessage, IBizMessage, IBizMessageRust};
+use crate::charset::TextEncoding;
+use crate::def::REQUEST_PACKET;
+
+/// 没有对应请求回调的消息来源
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum UnsolicitedReason {
+  /// 请求已超时，应答迟到
+  Late,
+  /// 消息中心 1.0 的推送
+  Push,
+  /// 服务端发起的请求
+  Request,
+  Unknown,
+}
+
+impl UnsolicitedReason {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      UnsolicitedReason::Late => "late",
+      UnsolicitedReason::Push => "push",
+      UnsolicitedReason::Request => "request",
+      UnsolicitedReason::Unknown => "unknown",
+    }
+  }
+}
+
+/**
+ * 未匹配到请求的业务消息，通过 `sdk.on('message', ...)` 派发
+ * 可用 `newBizMessage().setBuff(message.buff)` 还原完整消息
+ */
+#[napi(object)]
+#[derive(Clone)]
+pub struct UnsolicitedMessage {
+  /** 'late' | 'push' | 'request' | 'unknown' */
+  pub reason: String,
+  /** 发送句柄 */
+  pub h_send: i32,
+  pub function_no: i32,
+  pub packet_type: i32,
+  pub issue_type: i32,
+  pub sequence_no: i32,
+  pub return_code: i32,
+  pub error_no: i32,
+  pub error_info: Option<String>,
+  /** 业务内容 */
+  pub content: Buffer,
+  /** 消息中心 1.0 推送的关键字段信息 */
+  pub key_info: Option<Buffer>,
+  /** 整个消息的二进制 */
+  pub buff: Buffer,
+}
+
+impl UnsolicitedMessage {
+  /// 在 SDK 回调线程中读取消息，回调返回后 msg 不再有效
+  pub(crate) fn from_ptr(msg: *mut IBizMessageRust, h_send: i32, reason: UnsolicitedReason, encoding: TextEncoding) -> Self {
+    let message = BizMessage::new_form_ptr(msg, encoding);
+    let error_info = message.get_error_info().ok().filter(|s| !s.is_empty());
+    let key_info = message.get_key_info().ok().filter(|b| !b.is_empty());
+    UnsolicitedMessage {
+      reason: reason.as_str().to_string(),
+      h_send,
+      function_no: message.get_function().unwrap_or_default(),
+      packet_type: message.get_packet_type().unwrap_or_default(),
+      issue_type: message.get_issue_type().unwrap_or_default(),
+      sequence_no: message.get_sequence_no().unwrap_or_default(),
+      return_code: message.get_return_code().unwrap_or_default(),
+      error_no: message.get_error_no().unwrap_or_default(),
+      error_info,
+      content: message.get_content().unwrap_or_else(|_| Buffer::from(Vec::new())),
+      key_info,
+      buff: message.get_buff().unwrap_or_else(|_| Buffer::from(Vec::new())),
+    }
+  }
+}
+
+/// 根据消息本身判断来源，迟到的应答由调用方根据已超时的句柄判断
+pub(crate) fn classify(msg: *mut IBizMessageRust) -> UnsolicitedReason {
+  let message = unsafe { &*msg };
+  unsafe { classify_packet(message.get_packet_type(), message.get_issue_type()) }
+}
+
+/// 服务端发起的请求优先于推送，二者都不是时为 unknown
+fn classify_packet(packet_type: i32, issue_type: i32) -> UnsolicitedReason {
+  if packet_type == REQUEST_PACKET {
+    UnsolicitedReason::Request
+  } else if issue_type != 0 {
+    UnsolicitedReason::Push
+  } else {
+    UnsolicitedReason::Unknown
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::def::ANSWER_PACKET;
+
+  #[test]
+  fn classifies_by_packet_and_issue_type() {
+    assert_eq!(classify_packet(REQUEST_PACKET, 0), UnsolicitedReason::Request);
+    assert_eq!(classify_packet(REQUEST_PACKET, 12), UnsolicitedReason::Request);
+    assert_eq!(classify_packet(ANSWER_PACKET, 12), UnsolicitedReason::Push);
+    assert_eq!(classify_packet(ANSWER_PACKET, 0), UnsolicitedReason::Unknown);
+    assert_eq!(classify_packet(ANSWER_PACKET, 12).as_str(), "push");
+  }
+}